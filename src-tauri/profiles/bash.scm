@@ -0,0 +1,3 @@
+(function_definition name: (_) @name) @unit
+(variable_assignment name: (_) @name) @unit
+(declaration_command (variable_assignment name: (_) @name)) @unit
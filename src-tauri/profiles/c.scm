@@ -0,0 +1,16 @@
+(enumerator_list) @container
+(field_declaration_list) @container
+
+; Declarations are named by the identifier inside their declarator
+(function_definition
+  declarator: (function_declarator declarator: (_) @name)) @unit
+(function_definition
+  declarator: (pointer_declarator declarator: (function_declarator declarator: (_) @name))) @unit
+(declaration declarator: (function_declarator declarator: (_) @name)) @unit
+(declaration declarator: (init_declarator declarator: (_) @name)) @unit
+(declaration declarator: (identifier) @name) @unit
+(type_definition declarator: (_) @name) @unit
+(field_declaration declarator: (_) @name) @unit
+(enumerator name: (_) @name) @unit
+(preproc_def name: (_) @name) @unit
+(preproc_function_def name: (_) @name) @unit
@@ -0,0 +1,9 @@
+; Namespace bodies
+(declaration_list) @container
+
+(namespace_definition name: (_) @name) @unit
+(class_specifier name: (_) @name) @unit
+(field_declaration declarator: (function_declarator declarator: (_) @name)) @unit
+(template_declaration (class_specifier name: (_) @name)) @unit
+(template_declaration
+  (function_definition declarator: (function_declarator declarator: (_) @name))) @unit
@@ -0,0 +1,14 @@
+(declaration_list) @container
+(enum_member_declaration_list) @container
+
+(namespace_declaration name: (_) @name) @unit
+(class_declaration name: (_) @name) @unit
+(struct_declaration name: (_) @name) @unit
+(interface_declaration name: (_) @name) @unit
+(enum_declaration name: (_) @name) @unit
+(record_declaration name: (_) @name) @unit
+(method_declaration name: (_) @name) @unit
+(constructor_declaration name: (_) @name) @unit
+(property_declaration name: (_) @name) @unit
+(field_declaration (variable_declaration (variable_declarator name: (_) @name))) @unit
+(enum_member_declaration name: (_) @name) @unit
@@ -0,0 +1,5 @@
+; Declarations inside a rule, rules inside @media
+(block) @container
+
+(rule_set (selectors) @name) @unit
+(declaration (property_name) @name) @unit
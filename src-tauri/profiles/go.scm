@@ -0,0 +1,16 @@
+(interface_type) @container
+(field_declaration_list) @container
+(import_spec_list) @unordered
+
+(function_declaration name: (_) @name) @unit
+(type_declaration [(type_spec name: (_) @name) (type_alias name: (_) @name)]) @unit
+(const_declaration (const_spec name: (_) @name)) @unit
+(var_declaration (var_spec name: (_) @name)) @unit
+(method_elem name: (_) @name) @unit
+(field_declaration name: (_) @name) @unit
+(import_spec path: (_) @name) @unit
+
+; Methods are named by their receiver type and name
+(method_declaration
+  receiver: (parameter_list (parameter_declaration type: (_) @name))
+  name: (_) @name) @unit
@@ -0,0 +1,35 @@
+((script_element
+  (raw_text) @injection.content)
+ (#set! injection.language "javascript"))
+
+((style_element
+  (raw_text) @injection.content)
+ (#set! injection.language "css"))
+
+; JSON data blocks, e.g. <script type="application/ld+json">
+(script_element
+  (start_tag
+    (attribute
+      (attribute_name) @_attr
+      (quoted_attribute_value (attribute_value) @_type)))
+  (raw_text) @injection.content
+  (#eq? @_attr "type")
+  (#match? @_type "json$")
+  (#set! injection.language "json"))
+
+; A `lang` attribute names the language instead, e.g. <script lang="ts">
+(script_element
+  (start_tag
+    (attribute
+      (attribute_name) @_attr
+      (quoted_attribute_value (attribute_value) @injection.language)))
+  (raw_text) @injection.content
+  (#eq? @_attr "lang"))
+
+(style_element
+  (start_tag
+    (attribute
+      (attribute_name) @_attr
+      (quoted_attribute_value (attribute_value) @injection.language)))
+  (raw_text) @injection.content
+  (#eq? @_attr "lang"))
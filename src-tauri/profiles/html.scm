@@ -0,0 +1,12 @@
+(element) @container
+
+(element (start_tag (tag_name) @name)) @unit
+
+; Elements with an id are identified by it
+(element
+  (start_tag
+    (tag_name) @name
+    (attribute
+      (attribute_name) @_attr
+      (quoted_attribute_value (attribute_value) @name)))
+  (#eq? @_attr "id")) @unit
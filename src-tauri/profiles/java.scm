@@ -0,0 +1,16 @@
+(class_body) @container
+(interface_body) @container
+(enum_body) @container
+(enum_body_declarations) @container
+(annotation_type_body) @container
+
+(class_declaration name: (_) @name) @unit
+(interface_declaration name: (_) @name) @unit
+(enum_declaration name: (_) @name) @unit
+(record_declaration name: (_) @name) @unit
+(annotation_type_declaration name: (_) @name) @unit
+(method_declaration name: (_) @name) @unit
+(constructor_declaration name: (_) @name) @unit
+(field_declaration declarator: (variable_declarator name: (_) @name)) @unit
+(constant_declaration declarator: (variable_declarator name: (_) @name)) @unit
+(enum_constant name: (_) @name) @unit
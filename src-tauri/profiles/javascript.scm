@@ -0,0 +1,12 @@
+(class_body) @container
+
+(function_declaration name: (_) @name) @unit
+(generator_function_declaration name: (_) @name) @unit
+(class_declaration name: (_) @name) @unit
+(method_definition name: (_) @name) @unit
+(field_definition property: (_) @name) @unit
+(lexical_declaration (variable_declarator name: (_) @name)) @unit
+(variable_declaration (variable_declarator name: (_) @name)) @unit
+
+(export_statement declaration: (_ name: (_) @name)) @unit
+(export_statement declaration: (lexical_declaration (variable_declarator name: (_) @name))) @unit
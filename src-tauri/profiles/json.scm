@@ -0,0 +1,4 @@
+(object) @unordered
+(array) @container
+
+(pair key: (_) @name) @unit
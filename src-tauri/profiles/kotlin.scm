@@ -0,0 +1,9 @@
+(class_body) @container
+(enum_class_body) @container
+
+(class_declaration name: (_) @name) @unit
+(object_declaration name: (_) @name) @unit
+(companion_object) @unit
+(function_declaration name: (_) @name) @unit
+(property_declaration (variable_declaration (identifier) @name)) @unit
+(enum_entry (identifier) @name) @unit
@@ -0,0 +1,12 @@
+(declaration_list) @container
+(enum_declaration_list) @container
+
+(class_declaration name: (_) @name) @unit
+(interface_declaration name: (_) @name) @unit
+(trait_declaration name: (_) @name) @unit
+(enum_declaration name: (_) @name) @unit
+(function_definition name: (_) @name) @unit
+(method_declaration name: (_) @name) @unit
+(property_declaration (property_element name: (_) @name)) @unit
+(const_declaration (const_element (name) @name)) @unit
+(enum_case name: (_) @name) @unit
@@ -0,0 +1,7 @@
+; Class bodies reuse the generic block
+(class_definition body: (block) @container)
+
+(function_definition name: (_) @name) @unit
+(class_definition name: (_) @name) @unit
+(decorated_definition definition: (_ name: (_) @name)) @unit
+(expression_statement (assignment left: (identifier) @name)) @unit
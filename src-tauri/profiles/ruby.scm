@@ -0,0 +1,8 @@
+(class body: (body_statement) @container)
+(module body: (body_statement) @container)
+
+(method name: (_) @name) @unit
+(singleton_method name: (_) @name) @unit
+(class name: (_) @name) @unit
+(module name: (_) @name) @unit
+(assignment left: (constant) @name) @unit
@@ -0,0 +1,23 @@
+; impl, trait and mod bodies, struct fields and enum variants
+(declaration_list) @container
+(field_declaration_list) @container
+(ordered_field_declaration_list) @container
+(enum_variant_list) @container
+
+(function_item name: (_) @name) @unit
+(function_signature_item name: (_) @name) @unit
+(struct_item name: (_) @name) @unit
+(enum_item name: (_) @name) @unit
+(union_item name: (_) @name) @unit
+(trait_item name: (_) @name) @unit
+(type_item name: (_) @name) @unit
+(const_item name: (_) @name) @unit
+(static_item name: (_) @name) @unit
+(mod_item name: (_) @name) @unit
+(macro_definition name: (_) @name) @unit
+(associated_type name: (_) @name) @unit
+(field_declaration name: (_) @name) @unit
+(enum_variant name: (_) @name) @unit
+
+; Impls are named by their trait and type
+(impl_item trait: (_)? @name type: (_) @name) @unit
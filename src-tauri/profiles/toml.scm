@@ -0,0 +1,6 @@
+(table) @unordered
+(table_array_element) @unordered
+
+(table [(bare_key) (dotted_key) (quoted_key)] @name) @unit
+(table_array_element [(bare_key) (dotted_key) (quoted_key)] @name) @unit
+(pair [(bare_key) (dotted_key) (quoted_key)] @name) @unit
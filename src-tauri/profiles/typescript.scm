@@ -0,0 +1,32 @@
+(class_body) @container
+(interface_body) @container
+(object_type) @container
+(enum_body) @container
+
+; Namespace bodies reuse the generic statement block
+(internal_module body: (statement_block) @container)
+(module body: (statement_block) @container)
+
+(function_declaration name: (_) @name) @unit
+(generator_function_declaration name: (_) @name) @unit
+(function_signature name: (_) @name) @unit
+(class_declaration name: (_) @name) @unit
+(abstract_class_declaration name: (_) @name) @unit
+(interface_declaration name: (_) @name) @unit
+(type_alias_declaration name: (_) @name) @unit
+(enum_declaration name: (_) @name) @unit
+(internal_module name: (_) @name) @unit
+(module name: (_) @name) @unit
+(lexical_declaration (variable_declarator name: (_) @name)) @unit
+(variable_declaration (variable_declarator name: (_) @name)) @unit
+
+(method_definition name: (_) @name) @unit
+(method_signature name: (_) @name) @unit
+(abstract_method_signature name: (_) @name) @unit
+(public_field_definition name: (_) @name) @unit
+(property_signature name: (_) @name) @unit
+(enum_assignment name: (_) @name) @unit
+
+(export_statement declaration: (_ name: (_) @name)) @unit
+(export_statement declaration: (lexical_declaration (variable_declarator name: (_) @name))) @unit
+(ambient_declaration (_ name: (_) @name)) @unit
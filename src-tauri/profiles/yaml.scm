@@ -0,0 +1,3 @@
+(block_mapping) @unordered
+
+(block_mapping_pair key: (_) @name) @unit
@@ -6,16 +6,26 @@ pub struct CliArgs {
     pub local: PathBuf,
     pub remote: PathBuf,
     pub merged: PathBuf,
+    /// Language given with `--language`, overriding detection.
+    pub language: Option<String>,
 }
 
 impl CliArgs {
     pub fn parse() -> Option<CliArgs> {
         let args: Vec<String> = std::env::args().collect();
-        // Filter out Tauri-specific args
-        let file_args: Vec<&String> = args[1..]
-            .iter()
-            .filter(|a| !a.starts_with('-'))
-            .collect();
+        let mut language = None;
+        let mut file_args: Vec<&String> = Vec::new();
+        let mut iter = args[1..].iter();
+        while let Some(arg) = iter.next() {
+            if arg == "--language" {
+                language = iter.next().cloned();
+            } else if let Some(value) = arg.strip_prefix("--language=") {
+                language = Some(value.to_string());
+            } else if !arg.starts_with('-') {
+                // Filter out Tauri-specific args
+                file_args.push(arg);
+            }
+        }
 
         if file_args.len() >= 4 {
             Some(CliArgs {
@@ -23,6 +33,7 @@ impl CliArgs {
                 local: PathBuf::from(file_args[1]),
                 remote: PathBuf::from(file_args[2]),
                 merged: PathBuf::from(file_args[3]),
+                language,
             })
         } else {
             None
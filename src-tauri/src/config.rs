@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+
+use crate::merge::notebook::OutputPolicy;
+use crate::merge::version::VersionPick;
+
+/// User configuration, read from `$WEAVER_CONFIG` or `~/.config/weaver/config.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    pub versions: VersionConfig,
+    /// Tree-sitter grammars loaded at startup, keyed by language name.
+    pub grammars: BTreeMap<String, GrammarConfig>,
+    /// Formatters run on all three sides before merging, keyed by language name.
+    pub formatters: BTreeMap<String, FormatterConfig>,
+    pub data: DataConfig,
+    pub lockfiles: LockfileConfig,
+    pub notebooks: NotebookConfig,
+    pub csv: CsvConfig,
+}
+
+/// `[versions]` — handling of conflicts that differ only in version numbers.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct VersionConfig {
+    /// Resolve version-only conflicts during auto-resolve by taking `"max"` or `"min"`.
+    pub auto_resolve: Option<VersionPick>,
+}
+
+/// `[grammars.<language>]` — a tree-sitter grammar built as a shared library.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct GrammarConfig {
+    /// Path to the `.so`, `.dylib` or `.dll`.
+    pub path: PathBuf,
+    /// File extensions that use this grammar, e.g. `["kt", "kts"]`.
+    pub extensions: Vec<String>,
+    /// Exported language function, `tree_sitter_<language>` by default.
+    pub symbol: Option<String>,
+}
+
+/// `[formatters.<language>]` — a command that formats stdin to stdout, such as
+/// `rustfmt --emit stdout` or `prettier --stdin-filepath x.ts`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct FormatterConfig {
+    pub command: String,
+    pub args: Vec<String>,
+    /// Seconds to wait for the formatter before merging unformatted, 10 by default.
+    pub timeout: Option<u64>,
+}
+
+/// `[data]` — key-by-key merging of JSON, TOML and YAML documents.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct DataConfig {
+    /// Arrays merged element by element rather than as a whole, keyed by their
+    /// dotted path (e.g. `"jobs.build.steps"`, or `"package.metadata.assets"`
+    /// for an array in TOML's `[package.metadata]`) or last key, with the field that
+    /// identifies an element as the value.
+    pub array_keys: BTreeMap<String, String>,
+}
+
+/// `[lockfiles]` — merging of `Cargo.lock`, `package-lock.json` and `pnpm-lock.yaml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LockfileConfig {
+    /// Resolve conflicts with local's lockfile and report the command that
+    /// regenerates it, instead of merging packages.
+    pub regenerate: bool,
+}
+
+/// `[notebooks]` — cell-by-cell merging of Jupyter notebooks.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct NotebookConfig {
+    /// Whose outputs merged code cells keep: `"local"`, `"remote"` or `"clear"`.
+    pub outputs: OutputPolicy,
+}
+
+/// `[csv]` — row-by-row merging of CSV and TSV files.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct CsvConfig {
+    /// Header of the column that identifies a row; the first column by default.
+    pub key_column: Option<String>,
+    /// Key columns for particular files, keyed by file name.
+    pub key_columns: BTreeMap<String, String>,
+}
+
+impl DataConfig {
+    /// The field identifying elements of the array at `path`, if configured.
+    #[cfg_attr(not(feature = "tree-sitter"), allow(dead_code))]
+    pub fn array_key(&self, path: &[String]) -> Option<&str> {
+        self.array_keys
+            .get(&path.join("."))
+            .or_else(|| self.array_keys.get(path.last()?))
+            .map(String::as_str)
+    }
+}
+
+impl Config {
+    /// Location of the config file, if one can be determined.
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("WEAVER_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        Some(config_dir.join("weaver").join("config.toml"))
+    }
+
+    /// The configured grammar language for a file, if its extension is claimed by one.
+    pub fn language_for_path(&self, path: &str) -> Option<String> {
+        let ext = Path::new(path).extension()?.to_string_lossy().to_string();
+        crate::merge::grammar::language_for_extension(&self.grammars, &ext).map(str::to_string)
+    }
+
+    /// Load the config file, falling back to defaults if it is missing or invalid.
+    pub fn load() -> Config {
+        let path = match Self::path() {
+            Some(p) if p.exists() => p,
+            _ => return Config::default(),
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Warning: Could not read config {}: {}", path.display(), e);
+                return Config::default();
+            }
+        };
+        toml::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Warning: Invalid config {}: {}", path.display(), e);
+            Config::default()
+        })
+    }
+}
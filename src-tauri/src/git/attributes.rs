@@ -0,0 +1,21 @@
+use std::process::Command;
+
+/// Look up a gitattribute for `path` via `git check-attr`.
+/// Returns `None` when git is unavailable or the attribute is unspecified.
+pub fn check_attr(path: &str, attr: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["check-attr", attr, "--", path])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // Output format: "<path>: <attr>: <value>"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value = stdout.lines().next()?.rsplit(": ").next()?.trim().to_string();
+    match value.as_str() {
+        "" | "unspecified" => None,
+        _ => Some(value),
+    }
+}
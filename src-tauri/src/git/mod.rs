@@ -1,2 +1,3 @@
+pub mod attributes;
 pub mod repo;
 pub mod diff;
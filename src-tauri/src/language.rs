@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use crate::config::Config;
+use crate::git;
+
+/// Suffixes editors and merge tools append to a file's real name.
+const TEMP_SUFFIXES: &[&str] = &["in", "orig", "bak", "tmp", "rej", "swp"];
+
+/// How many lines at each end of a file are searched for a modeline.
+const MODELINE_LINES: usize = 5;
+
+/// Normalize a language name from a modeline, gitattribute or `--language`
+/// to the ids used for grammars and highlighting.
+pub fn normalize_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    match name.as_str() {
+        "c++" => "cpp",
+        "c#" | "cs" => "csharp",
+        "js" | "node" => "javascript",
+        "ts" => "typescript",
+        "py" | "python3" => "python",
+        "rs" => "rust",
+        "golang" => "go",
+        "yml" => "yaml",
+        "md" => "markdown",
+        "sh" | "bash" | "zsh" | "shell-script" => "shell",
+        "make" => "makefile",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Language for a well-known extensionless file name.
+fn language_for_filename(name: &str) -> Option<&'static str> {
+    let lang = match name {
+        "Makefile" | "makefile" | "GNUmakefile" => "makefile",
+        "Dockerfile" | "Containerfile" => "dockerfile",
+        "Gemfile" | "Rakefile" | "Guardfile" | "Podfile" | "Vagrantfile" => "ruby",
+        "Jenkinsfile" => "groovy",
+        "CMakeLists.txt" => "cmake",
+        "Cargo.lock" | "Pipfile" => "toml",
+        ".bashrc" | ".bash_profile" | ".zshrc" | ".profile" | "PKGBUILD" => "shell",
+        _ => return None,
+    };
+    Some(lang)
+}
+
+/// Language for a file extension.
+fn language_for_extension(ext: &str) -> Option<&'static str> {
+    let lang = match ext.to_lowercase().as_str() {
+        "rs" => "rust",
+        "ts" | "tsx" => "typescript",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "py" | "pyi" => "python",
+        "go" => "go",
+        "java" => "java",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" => "cpp",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "md" => "markdown",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "sh" | "bash" | "zsh" => "shell",
+        "rb" => "ruby",
+        "cs" => "csharp",
+        "kt" | "kts" => "kotlin",
+        "php" => "php",
+        "sql" => "sql",
+        "xml" => "xml",
+        "dockerfile" => "dockerfile",
+        "mk" => "makefile",
+        _ => return None,
+    };
+    Some(lang)
+}
+
+/// The file name with any temp suffixes (`.orig`, `.in`, ...) removed.
+fn strip_temp_suffixes(name: &str) -> &str {
+    let mut name = name;
+    while let Some((stem, ext)) = name.rsplit_once('.') {
+        if stem.is_empty() || !TEMP_SUFFIXES.contains(&ext.to_lowercase().as_str()) {
+            break;
+        }
+        name = stem;
+    }
+    name
+}
+
+/// Language for a path from its file name, then its extension: configured
+/// grammars first, then the built-in table.
+fn language_for_path(config: &Config, path: &Path) -> Option<String> {
+    let name = strip_temp_suffixes(path.file_name()?.to_str()?);
+    if let Some(lang) = language_for_filename(name) {
+        return Some(lang.to_string());
+    }
+    let (_, ext) = name.rsplit_once('.')?;
+    config
+        .language_for_path(name)
+        .or_else(|| language_for_extension(ext).map(str::to_string))
+}
+
+/// Language from an interpreter in a `#!` line, e.g. `#!/usr/bin/env python3`.
+fn language_for_shebang(content: &str) -> Option<String> {
+    let line = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // Skip env's own options, e.g. `env -S node --flag`
+        program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+    }
+    let interpreter = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let lang = match interpreter {
+        "python" | "pypy" => "python",
+        "node" | "nodejs" | "deno" | "bun" => "javascript",
+        "ts-node" | "tsx" => "typescript",
+        "ruby" => "ruby",
+        "php" => "php",
+        "sh" | "bash" | "zsh" | "dash" | "ksh" | "ash" => "shell",
+        "perl" => "perl",
+        "lua" => "lua",
+        "make" => "makefile",
+        _ => return None,
+    };
+    Some(lang.to_string())
+}
+
+/// Language from a vim (`vim: set ft=python:`) or emacs (`-*- mode: ruby -*-`)
+/// modeline near the start or end of the file.
+fn language_for_modeline(content: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let head = lines.len().min(MODELINE_LINES);
+    let tail = lines.len().saturating_sub(MODELINE_LINES).max(head);
+    lines[..head]
+        .iter()
+        .chain(&lines[tail..])
+        .find_map(|line| emacs_modeline(line).or_else(|| vim_modeline(line)))
+        .map(normalize_name)
+}
+
+fn emacs_modeline(line: &str) -> Option<&str> {
+    let (_, rest) = line.split_once("-*-")?;
+    let (vars, _) = rest.split_once("-*-")?;
+    if !vars.contains(':') {
+        // `-*- python -*-`
+        return Some(vars.trim()).filter(|v| !v.is_empty());
+    }
+    vars.split(';').find_map(|var| {
+        let (key, value) = var.split_once(':')?;
+        key.trim().eq_ignore_ascii_case("mode").then(|| value.trim())
+    })
+}
+
+fn vim_modeline(line: &str) -> Option<&str> {
+    // The marker must start the line or follow whitespace, so `regex:` isn't one
+    let start = ["vim:", "vi:", "ex:"].iter().find_map(|marker| {
+        line.match_indices(marker)
+            .find(|(i, _)| line[..*i].ends_with(char::is_whitespace) || *i == 0)
+            .map(|(i, _)| i + marker.len())
+    })?;
+    line[start..]
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .find_map(|option| {
+            let (key, value) = option.split_once('=')?;
+            matches!(key, "ft" | "filetype" | "syntax" | "syn").then_some(value)
+        })
+        .filter(|v| !v.is_empty())
+}
+
+/// Detect the language of a merge. An explicit `--language` wins, then the merged
+/// path's `linguist-language` gitattribute, a modeline, the file names and
+/// extensions of the merged, local, remote and base paths in that order, and
+/// finally a shebang line. Falls back to `plaintext`.
+pub fn detect_language(
+    config: &Config,
+    explicit: Option<&str>,
+    paths: &[&Path],
+    contents: &[&str],
+) -> String {
+    if let Some(lang) = explicit {
+        return normalize_name(lang);
+    }
+    if let Some(merged) = paths.first().and_then(|p| p.to_str()) {
+        let attr = git::attributes::check_attr(merged, "linguist-language");
+        if let Some(lang) = attr.filter(|v| v != "set" && v != "unset") {
+            return normalize_name(&lang);
+        }
+    }
+    contents
+        .iter()
+        .find_map(|c| language_for_modeline(c))
+        .or_else(|| paths.iter().find_map(|p| language_for_path(config, p)))
+        .or_else(|| contents.iter().find_map(|c| language_for_shebang(c)))
+        .unwrap_or_else(|| "plaintext".to_string())
+}
@@ -1,5 +1,7 @@
 mod cli;
+mod config;
 mod git;
+mod language;
 mod merge;
 mod safety;
 mod state;
@@ -8,38 +10,32 @@ use std::fs;
 use std::path::Path;
 use tauri::AppHandle;
 
-use merge::hunk::{HunkStatus, MergeSession};
+use merge::hunk::{HunkStatus, MergeSession, MergeStrategy};
 use merge::three_way::three_way_merge;
 use merge::resolver;
 use state::AppState;
 
-/// Detect language from file extension.
-fn detect_language(path: &str) -> String {
-    let ext = Path::new(path)
-        .extension()
-        .map(|e| e.to_string_lossy().to_lowercase())
-        .unwrap_or_default();
-    match ext.as_str() {
-        "rs" => "rust",
-        "ts" | "tsx" => "typescript",
-        "js" | "jsx" => "javascript",
-        "py" => "python",
-        "go" => "go",
-        "java" => "java",
-        "c" | "h" => "c",
-        "cpp" | "cc" | "cxx" | "hpp" => "cpp",
-        "json" => "json",
-        "toml" => "toml",
-        "yaml" | "yml" => "yaml",
-        "md" => "markdown",
-        "html" | "htm" => "html",
-        "css" => "css",
-        "sh" | "bash" | "zsh" => "shell",
-        "sql" => "sql",
-        "xml" => "xml",
-        _ => "plaintext",
+/// Pick the merge strategy from the merged path's gitattributes `merge` driver,
+/// falling back to well-known file names.
+fn detect_strategy(path: &str) -> MergeStrategy {
+    let driver = git::attributes::check_attr(path, "merge");
+    if driver.as_deref() == Some("union") || merge::union::is_union_file(path) {
+        MergeStrategy::Union
+    } else if driver.as_deref() == Some("prose") {
+        MergeStrategy::Prose
+    } else if merge::changelog::is_changelog_file(path) {
+        MergeStrategy::Changelog
+    } else if merge::lockfile::lockfile_kind(path).is_some() {
+        MergeStrategy::Lockfile
+    } else if merge::notebook::is_notebook_file(path) {
+        MergeStrategy::Notebook
+    } else if merge::csv::is_csv_file(path) {
+        MergeStrategy::Csv
+    } else if merge::po::is_po_file(path) {
+        MergeStrategy::Po
+    } else {
+        MergeStrategy::Text
     }
-    .to_string()
 }
 
 /// Parse conflict markers from an already-merged file (fallback mode).
@@ -79,6 +75,27 @@ fn parse_conflict_markers(content: &str) -> Option<Vec<(String, String)>> {
     }
 }
 
+/// Replace the line merge's hunks for strategies that merge when the session is
+/// created: cells, rows, entries and sentences. Falls back to the line merge
+/// with a warning if the files can't be read that way.
+fn apply_strategy(session: &mut MergeSession, config: &config::Config) {
+    if session.strategy == MergeStrategy::Notebook && !merge::notebook::notebook_merge(session, &config.notebooks) {
+        eprintln!("Warning: Could not parse the notebooks, merging them as text");
+        session.strategy = MergeStrategy::Text;
+    }
+    if session.strategy == MergeStrategy::Csv && !merge::csv::csv_merge(session, &config.csv) {
+        eprintln!("Warning: Could not match up the table rows, merging them as text");
+        session.strategy = MergeStrategy::Text;
+    }
+    if session.strategy == MergeStrategy::Po && !merge::po::po_merge(session) {
+        eprintln!("Warning: Could not parse the translation catalogues, merging them as text");
+        session.strategy = MergeStrategy::Text;
+    }
+    if session.strategy == MergeStrategy::Prose {
+        merge::prose::prose_merge(session);
+    }
+}
+
 #[tauri::command]
 fn get_merge_session(state: tauri::State<'_, AppState>) -> Result<MergeSession, String> {
     let guard = state.session.lock();
@@ -102,6 +119,24 @@ fn save_result(content: String, state: tauri::State<'_, AppState>) -> Result<(),
     let guard = state.session.lock();
     let session = guard.as_ref().ok_or("No merge session")?;
     let path = Path::new(&session.merged_path);
+
+    // A notebook's result pane holds its cells, written back out as JSON
+    let content = match &session.notebook {
+        Some(notebook) => merge::notebook::write_notebook(notebook, &content)?,
+        None => content,
+    };
+
+    // A normalized merge is formatted again once it is conflict-free
+    let formatter = state.config.formatters.get(&session.language);
+    let content = match formatter {
+        Some(f) if session.normalized && parse_conflict_markers(&content).is_none() => {
+            merge::format::run_formatter(f, &content).unwrap_or_else(|e| {
+                eprintln!("Warning: Could not format result, saving it unformatted: {}", e);
+                content
+            })
+        }
+        _ => content,
+    };
     safety::safe_write(path, &content)
 }
 
@@ -109,6 +144,7 @@ fn save_result(content: String, state: tauri::State<'_, AppState>) -> Result<(),
 fn update_result_content(content: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
     let mut guard = state.session.lock();
     let session = guard.as_mut().ok_or("No merge session")?;
+    session.trees.edit_result(&session.result_content, &content);
     session.result_content = content;
     Ok(())
 }
@@ -125,7 +161,85 @@ fn auto_resolve(state: tauri::State<'_, AppState>) -> Result<MergeSession, Strin
     let mut guard = state.session.lock();
     let session = guard.as_mut().ok_or("No merge session")?;
     resolver::auto_resolve_non_conflicts(session);
-    merge::structural::structural_auto_resolve(session);
+    // Other strategies' hunks are rows, cells or entries, where numbers are data
+    if let Some(pick) = state.config.versions.auto_resolve.filter(|_| session.strategy == MergeStrategy::Text) {
+        merge::version::version_auto_resolve(session, pick);
+    }
+    match session.strategy {
+        MergeStrategy::Union => {
+            merge::union::union_auto_resolve(session);
+        }
+        MergeStrategy::Changelog => {
+            merge::changelog::changelog_auto_resolve(session);
+        }
+        MergeStrategy::Lockfile => {
+            merge::lockfile::lockfile_auto_resolve(session, &state.config.lockfiles);
+        }
+        // Cells, rows and entries were merged when the session was created
+        MergeStrategy::Notebook | MergeStrategy::Csv | MergeStrategy::Po => {}
+        // So were sentences, but conflicts in code blocks get the embedded language's merge
+        MergeStrategy::Text | MergeStrategy::Prose => {
+            merge::structural::structural_auto_resolve(session, &state.config.data);
+        }
+    }
+    Ok(session.clone())
+}
+
+#[tauri::command]
+fn preview_structural(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<merge::structural::HunkPreview>, String> {
+    let guard = state.session.lock();
+    let session = guard.as_ref().ok_or("No merge session")?;
+    // Other strategies' hunks come from their own mergers, as in `auto_resolve`
+    if !matches!(session.strategy, MergeStrategy::Text | MergeStrategy::Prose) {
+        return Ok(Vec::new());
+    }
+    Ok(merge::structural::preview_structural(session, &state.config.data))
+}
+
+#[tauri::command]
+fn apply_structural(
+    hunk_ids: Vec<usize>,
+    state: tauri::State<'_, AppState>,
+) -> Result<MergeSession, String> {
+    let mut guard = state.session.lock();
+    let session = guard.as_mut().ok_or("No merge session")?;
+    if matches!(session.strategy, MergeStrategy::Text | MergeStrategy::Prose) {
+        merge::structural::apply_structural(session, Some(&hunk_ids), &state.config.data);
+    }
+    Ok(session.clone())
+}
+
+#[tauri::command]
+fn validate_result(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<merge::structural::SyntaxError>, String> {
+    let guard = state.session.lock();
+    let session = guard.as_ref().ok_or("No merge session")?;
+    merge::structural::validate_result(session)
+        .ok_or_else(|| format!("No tree-sitter grammar for {}", session.language))
+}
+
+#[tauri::command]
+fn replay_renames(
+    renames: Vec<merge::rename::IdentifierRename>,
+    state: tauri::State<'_, AppState>,
+) -> Result<MergeSession, String> {
+    let mut guard = state.session.lock();
+    let session = guard.as_mut().ok_or("No merge session")?;
+    // A notebook's sides are its cell sources, not the files the strategy merges
+    if session.strategy == MergeStrategy::Notebook {
+        return Err("Cannot replay renames into notebook cells".to_string());
+    }
+    merge::rename::replay_identifier_renames(session, &renames, |session| {
+        let (hunks, result_content) =
+            three_way_merge(&session.base_content, &session.local_content, &session.remote_content);
+        session.version_conflicts = merge::version::find_version_conflicts(&hunks);
+        session.hunks = hunks;
+        session.result_content = result_content;
+        apply_strategy(session, &state.config);
+    })?;
     Ok(session.clone())
 }
 
@@ -136,7 +250,8 @@ fn register_git_mergetool() -> Result<String, String> {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let app_state = AppState::new();
+    let app_state = AppState::new(config::Config::load());
+    merge::grammar::load_grammars(&app_state.config.grammars);
 
     // Parse CLI args and initialize session
     if let Some(args) = cli::CliArgs::parse() {
@@ -157,7 +272,32 @@ pub fn run() {
             });
         let merged_content = fs::read_to_string(&args.merged).unwrap_or_default();
 
-        let language = detect_language(&args.merged.to_string_lossy());
+        let merged_path = args.merged.to_string_lossy();
+        let language = language::detect_language(
+            &app_state.config,
+            args.language.as_deref(),
+            &[args.merged.as_path(), args.local.as_path(), args.remote.as_path(), args.base.as_path()],
+            &[&local_content, &remote_content, &base_content],
+        );
+        let strategy = match detect_strategy(&merged_path) {
+            // Prose needs a base to merge sentences against
+            MergeStrategy::Prose if base_content.is_empty() => MergeStrategy::Text,
+            MergeStrategy::Text if merge::prose::is_prose_language(&language) && !base_content.is_empty() => {
+                MergeStrategy::Prose
+            }
+            strategy => strategy,
+        };
+
+        // Normalize formatting first if a formatter is configured for the language
+        let normalized = app_state
+            .config
+            .formatters
+            .get(&language)
+            .filter(|_| !base_content.is_empty())
+            .and_then(|f| merge::format::normalize(f, &base_content, &local_content, &remote_content));
+        let is_normalized = normalized.is_some();
+        let (base_content, local_content, remote_content) =
+            normalized.unwrap_or((base_content, local_content, remote_content));
 
         // Check if the merged file already has conflict markers (fallback mode)
         let (hunks, result_content) = if !base_content.is_empty() {
@@ -169,8 +309,9 @@ pub fn run() {
         } else {
             (Vec::new(), local_content.clone())
         };
+        let version_conflicts = merge::version::find_version_conflicts(&hunks);
 
-        let session = MergeSession {
+        let mut session = MergeSession {
             base_path: args.base.to_string_lossy().to_string(),
             local_path: args.local.to_string_lossy().to_string(),
             remote_path: args.remote.to_string_lossy().to_string(),
@@ -181,7 +322,17 @@ pub fn run() {
             result_content,
             hunks,
             language,
+            strategy,
+            version_conflicts,
+            renames: Vec::new(),
+            identifier_renames: Vec::new(),
+            normalized: is_normalized,
+            lockfile: None,
+            notebook: None,
+            trees: Default::default(),
         };
+        apply_strategy(&mut session, &app_state.config);
+        session.identifier_renames = merge::rename::detect_identifier_renames(&session);
 
         *app_state.session.lock() = Some(session);
     }
@@ -205,6 +356,10 @@ pub fn run() {
             update_result_content,
             abort_merge,
             auto_resolve,
+            preview_structural,
+            apply_structural,
+            validate_result,
+            replay_renames,
             register_git_mergetool,
         ])
         .run(tauri::generate_context!())
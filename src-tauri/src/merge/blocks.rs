@@ -0,0 +1,110 @@
+use super::three_way::split_lines;
+
+/// Line prefixes treated as comments, which attach to the block that follows them.
+const COMMENT_PREFIXES: &[&str] = &["#", "//", "/*", "*", "--", ";"];
+
+/// A block of lines found without a grammar: a line at the region's outermost
+/// indentation, the deeper-indented or bracketed lines after it, and the blank
+/// lines and comments before it.
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// The block's first line, trimmed.
+    pub header: String,
+    pub text: String,
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_trivia(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() || COMMENT_PREFIXES.iter().any(|p| trimmed.starts_with(p))
+}
+
+/// Net change in bracket depth over `text`, skipping quoted strings.
+pub fn bracket_delta(text: &str) -> isize {
+    let mut delta = 0;
+    for line in text.lines() {
+        let mut quote = None;
+        let mut escaped = false;
+        for c in line.chars() {
+            match quote {
+                Some(q) => {
+                    if escaped {
+                        escaped = false;
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == q {
+                        quote = None;
+                    }
+                }
+                None => match c {
+                    '"' | '\'' | '`' => quote = Some(c),
+                    '{' | '[' | '(' => delta += 1,
+                    '}' | ']' | ')' => delta -= 1,
+                    _ => {}
+                },
+            }
+        }
+    }
+    delta
+}
+
+/// Split hunk content into blocks. A block starts at each line at the smallest
+/// indentation in the content that isn't inside open brackets and doesn't close
+/// one; blank lines and comments go with the block after them, or with the last
+/// block when nothing follows.
+pub fn split_blocks(content: &str) -> Vec<Block> {
+    let lines = split_lines(content);
+    let outer = lines
+        .iter()
+        .filter(|l| !is_trivia(l))
+        .map(|l| indent_of(l))
+        .min()
+        .unwrap_or(0);
+
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut pending: Vec<&str> = Vec::new();
+    let mut depth: isize = 0;
+    for line in lines {
+        if is_trivia(line) {
+            pending.push(line);
+            continue;
+        }
+        let closes = line.trim_start().starts_with(['}', ']', ')']);
+        let starts_block = depth <= 0 && indent_of(line) == outer && !closes;
+        depth = (depth + bracket_delta(line)).max(0);
+
+        match blocks.last_mut() {
+            Some(last) if !starts_block => {
+                for l in pending.drain(..).chain([line]) {
+                    last.text.push('\n');
+                    last.text.push_str(l);
+                }
+            }
+            _ => {
+                pending.push(line);
+                blocks.push(Block {
+                    header: line.trim().to_string(),
+                    text: pending.join("\n"),
+                });
+                pending.clear();
+            }
+        }
+    }
+
+    if !pending.is_empty() {
+        match blocks.last_mut() {
+            Some(last) => {
+                last.text.push('\n');
+                last.text.push_str(&pending.join("\n"));
+            }
+            None => blocks.push(Block {
+                header: String::new(),
+                text: pending.join("\n"),
+            }),
+        }
+    }
+    blocks
+}
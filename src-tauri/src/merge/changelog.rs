@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::hunk::MergeSession;
+use super::resolver::auto_resolve_conflicts;
+use super::sequence::merge_sequences;
+use super::version::compare_versions;
+
+/// A heading and the entries (bullets, paragraphs, blank lines) below it.
+#[derive(Debug, Clone)]
+struct Section {
+    /// Heading lines from the outermost down to this section's own heading,
+    /// so `### Added` under different releases gets different keys.
+    key: String,
+    heading: Option<String>,
+    entries: Vec<String>,
+}
+
+/// Check whether a path is a keep-a-changelog style file.
+pub fn is_changelog_file(path: &str) -> bool {
+    let stem = Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let ext = Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    matches!(stem.as_str(), "changelog" | "changes") && matches!(ext.as_str(), "md" | "markdown" | "")
+}
+
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level > 0 && line[level..].starts_with(' ') {
+        Some(level)
+    } else {
+        None
+    }
+}
+
+fn is_bullet(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("- ") || trimmed.starts_with("* ") || trimmed.starts_with("+ ")
+}
+
+/// Split changelog text into sections. A bullet entry owns the indented lines that
+/// follow it; any other line is an entry of its own.
+fn parse_sections(text: &str) -> Vec<Section> {
+    let mut sections = vec![Section {
+        key: String::new(),
+        heading: None,
+        entries: Vec::new(),
+    }];
+    let mut heading_stack: Vec<(usize, String)> = Vec::new();
+
+    for line in text.lines() {
+        if let Some(level) = heading_level(line) {
+            while heading_stack.last().is_some_and(|(l, _)| *l >= level) {
+                heading_stack.pop();
+            }
+            heading_stack.push((level, line.trim_end().to_string()));
+            sections.push(Section {
+                key: heading_stack
+                    .iter()
+                    .map(|(_, h)| h.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                heading: Some(line.to_string()),
+                entries: Vec::new(),
+            });
+            continue;
+        }
+
+        let entries = &mut sections.last_mut().expect("preamble section").entries;
+        let continues_bullet = line.starts_with(char::is_whitespace)
+            && !line.trim().is_empty()
+            && !is_bullet(line)
+            && entries.last().is_some_and(|e| is_bullet(e));
+        match entries.last_mut() {
+            Some(entry) if continues_bullet => {
+                entry.push('\n');
+                entry.push_str(line);
+            }
+            _ => entries.push(line.to_string()),
+        }
+    }
+
+    sections
+}
+
+/// The version of a release heading like `## [1.2.0] - 2024-05-01` or
+/// `## v1.2.0`. `None` for other headings, such as `## [Unreleased]`.
+fn release_version(heading: &str) -> Option<&str> {
+    let text = heading.trim_start_matches('#').trim();
+    let text = match text.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(bracketed),
+        None => text,
+    };
+    let version = text.split_whitespace().next()?;
+    version
+        .trim_start_matches(['v', 'V'])
+        .starts_with(|c: char| c.is_ascii_digit())
+        .then_some(version)
+}
+
+/// Put releases the sides added next to each other in version order, newest
+/// first, rather than local's before remote's. Each release keeps its
+/// subsections with it.
+fn order_new_releases(keys: Vec<String>, base: &[Section]) -> Vec<String> {
+    fn flush(run: &mut Vec<(String, Vec<String>)>, ordered: &mut Vec<String>) {
+        run.sort_by(|(a, _), (b, _)| compare_versions(b, a));
+        ordered.extend(run.drain(..).flat_map(|(_, keys)| keys));
+    }
+    let parent = |key: &str| key.rsplit_once('\n').map_or("", |(parent, _)| parent).to_string();
+
+    let mut ordered = Vec::new();
+    // Consecutive new releases under the same heading, with their subsections
+    let mut run: Vec<(String, Vec<String>)> = Vec::new();
+    for key in keys {
+        if let Some((_, release)) = run.last_mut() {
+            if key.starts_with(&format!("{}\n", release[0])) {
+                release.push(key);
+                continue;
+            }
+        }
+        let is_new = !base.iter().any(|s| s.key == key);
+        match key.lines().last().and_then(release_version).filter(|_| is_new) {
+            Some(version) => {
+                if run.first().is_some_and(|(_, first)| parent(&first[0]) != parent(&key)) {
+                    flush(&mut run, &mut ordered);
+                }
+                run.push((version.to_string(), vec![key]));
+            }
+            None => {
+                flush(&mut run, &mut ordered);
+                ordered.push(key);
+            }
+        }
+    }
+    flush(&mut run, &mut ordered);
+    ordered
+}
+
+fn has_unique_keys(sections: &[Section]) -> bool {
+    let mut seen = HashSet::new();
+    sections.iter().all(|s| seen.insert(s.key.as_str()))
+}
+
+/// Merge three versions of a changelog region section by section.
+///
+/// Sections are matched by their heading path and entries are merged in order, so
+/// both sides adding bullets under the same heading keeps all of them. Releases
+/// added on both sides are listed newest first. Returns
+/// `None` when both sides edited or removed the same existing entry differently.
+pub fn merge_changelog(base: &str, local: &str, remote: &str) -> Option<String> {
+    let base_sections = parse_sections(base);
+    let local_sections = parse_sections(local);
+    let remote_sections = parse_sections(remote);
+
+    if !has_unique_keys(&base_sections)
+        || !has_unique_keys(&local_sections)
+        || !has_unique_keys(&remote_sections)
+    {
+        return None;
+    }
+
+    let keys = |sections: &[Section]| -> Vec<String> {
+        sections.iter().map(|s| s.key.clone()).collect()
+    };
+    let merged_keys = merge_sequences(
+        &keys(&base_sections),
+        &keys(&local_sections),
+        &keys(&remote_sections),
+    )?;
+    let merged_keys = order_new_releases(merged_keys, &base_sections);
+
+    let find = |sections: &[Section], key: &str| -> Option<Section> {
+        sections.iter().find(|s| s.key == key).cloned()
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    for key in &merged_keys {
+        let base_section = find(&base_sections, key);
+        let local_section = find(&local_sections, key);
+        let remote_section = find(&remote_sections, key);
+
+        let entries_of = |s: &Option<Section>| -> Vec<String> {
+            s.as_ref().map(|s| s.entries.clone()).unwrap_or_default()
+        };
+        let entries = merge_sequences(
+            &entries_of(&base_section),
+            &entries_of(&local_section),
+            &entries_of(&remote_section),
+        )?;
+
+        let heading = local_section
+            .or(remote_section)
+            .or(base_section)
+            .and_then(|s| s.heading);
+        if let Some(heading) = heading {
+            lines.push(heading);
+        }
+        lines.extend(entries);
+    }
+
+    Some(lines.join("\n"))
+}
+
+/// Resolve conflict hunks in a changelog by merging their sections and entries.
+pub fn changelog_auto_resolve(session: &mut MergeSession) -> usize {
+    auto_resolve_conflicts(session, |_, hunk| {
+        merge_changelog(&hunk.base_content, &hunk.local_content, &hunk.remote_content)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::hunk::HunkStatus;
+
+    const BASE: &str = "# Changelog\n\n## [Unreleased]\n\n### Added\n\n- Dark mode\n\n## [1.1.0] - 2024-03-01\n\n### Fixed\n\n- Crash on start\n";
+
+    #[test]
+    fn recognises_changelogs() {
+        assert!(is_changelog_file("CHANGELOG.md"));
+        assert!(is_changelog_file("docs/changes.markdown"));
+        assert!(is_changelog_file("CHANGELOG"));
+        assert!(!is_changelog_file("CHANGELOG.rs"));
+        assert!(!is_changelog_file("README.md"));
+    }
+
+    #[test]
+    fn keeps_entries_both_sides_added_under_a_heading() {
+        let local = BASE.replace("- Dark mode\n", "- Dark mode\n- Export to PDF\n");
+        let remote = BASE.replace("- Dark mode\n", "- Dark mode\n- Keyboard shortcuts\n  for every command\n");
+
+        let merged = merge_changelog(BASE, &local, &remote).unwrap();
+        assert_eq!(
+            merged,
+            BASE.replace(
+                "- Dark mode\n",
+                "- Dark mode\n- Export to PDF\n- Keyboard shortcuts\n  for every command\n"
+            )
+            .trim_end()
+        );
+    }
+
+    #[test]
+    fn same_headings_under_different_releases_stay_apart() {
+        // Each side adds a fix, one to the unreleased section, one to 1.1.0
+        let local = BASE.replace("- Crash on start\n", "- Crash on start\n- Slow search\n");
+        let remote = BASE.replace("- Dark mode\n", "- Dark mode\n\n### Fixed\n\n- Typo in menu\n");
+
+        let merged = merge_changelog(BASE, &local, &remote).unwrap();
+        assert_eq!(
+            merged,
+            "# Changelog\n\n## [Unreleased]\n\n### Added\n\n- Dark mode\n\n### Fixed\n\n- Typo in menu\n\n## [1.1.0] - 2024-03-01\n\n### Fixed\n\n- Crash on start\n- Slow search"
+        );
+    }
+
+    #[test]
+    fn new_releases_are_ordered_by_version() {
+        let release = |version: &str, entry: &str| {
+            format!("## [{version}] - 2024-06-01\n\n### Added\n\n- {entry}\n\n## [1.1.0]")
+        };
+        let local = BASE.replace("## [1.1.0]", &release("1.2.0", "Tabs"));
+        let remote = BASE.replace("## [1.1.0]", &release("1.10.0", "Themes"));
+
+        let merged = merge_changelog(BASE, &local, &remote).unwrap();
+        let headings: Vec<&str> = merged.lines().filter(|l| l.starts_with("## ")).collect();
+        assert_eq!(
+            headings,
+            ["## [Unreleased]", "## [1.10.0] - 2024-06-01", "## [1.2.0] - 2024-06-01", "## [1.1.0] - 2024-03-01"]
+        );
+        // Each release keeps its own entries
+        assert!(merged.contains("## [1.10.0] - 2024-06-01\n\n### Added\n\n- Themes\n\n## [1.2.0]"));
+        assert!(merged.contains("## [1.2.0] - 2024-06-01\n\n### Added\n\n- Tabs\n\n## [1.1.0]"));
+    }
+
+    #[test]
+    fn editing_the_same_entry_differently_conflicts() {
+        let local = BASE.replace("- Dark mode", "- Dark mode for the editor");
+        let remote = BASE.replace("- Dark mode", "- Dark and light modes");
+        assert_eq!(merge_changelog(BASE, &local, &remote), None);
+
+        let mut session = MergeSession::for_test(BASE, &local, &remote, "markdown");
+        assert_eq!(changelog_auto_resolve(&mut session), 0);
+        assert!(session.hunks.iter().any(|h| h.status == HunkStatus::Unresolved));
+    }
+}
@@ -0,0 +1,452 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::hunk::{HunkSource, HunkStatus, LineRange, MergeHunk, MergeSession};
+use super::sequence::{merge_order, merge_sequences};
+use super::three_way::split_lines;
+use super::version::find_version_conflicts;
+use crate::config::CsvConfig;
+
+/// Delimiters recognized in a `.csv` header line, most common first.
+const DELIMITERS: &[char] = &[',', ';', '\t', '|'];
+
+/// A field's value, and its text in the file including any quotes.
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    value: String,
+    raw: String,
+}
+
+/// A record and the lines it spans (more than one if a quoted field has newlines).
+#[derive(Debug, Clone)]
+struct Record {
+    fields: Vec<Field>,
+    lines: LineRange,
+}
+
+/// A parsed table: the header's column names, and rows keyed by their key column.
+struct Table {
+    header: Record,
+    columns: Vec<String>,
+    keys: Vec<String>,
+    rows: HashMap<String, Record>,
+}
+
+impl Table {
+    /// A row's cells by column name.
+    fn cells(&self, key: &str) -> Option<HashMap<&str, &Field>> {
+        let row = self.rows.get(key)?;
+        Some(self.columns.iter().map(String::as_str).zip(&row.fields).collect())
+    }
+}
+
+/// Check whether a path is a CSV or TSV file.
+pub fn is_csv_file(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| matches!(e.as_str(), "csv" | "tsv" | "tab"))
+}
+
+/// The delimiter for a file: tabs for `.tsv`, otherwise whichever common
+/// delimiter appears most in the first line.
+fn detect_delimiter(path: &str, content: &str) -> char {
+    let ext = Path::new(path).extension().map(|e| e.to_string_lossy().to_lowercase());
+    if matches!(ext.as_deref(), Some("tsv" | "tab")) {
+        return '\t';
+    }
+    let first = content.lines().next().unwrap_or_default();
+    DELIMITERS
+        .iter()
+        .copied()
+        .max_by_key(|d| (first.matches(*d).count(), std::cmp::Reverse(DELIMITERS.iter().position(|x| x == d))))
+        .filter(|d| first.contains(*d))
+        .unwrap_or(',')
+}
+
+/// Split CSV text into records. Quoted fields may contain the delimiter,
+/// newlines and doubled quotes. Blank lines are skipped. Returns `None` for an
+/// unterminated quote.
+fn parse_records(content: &str, delimiter: char) -> Option<Vec<Record>> {
+    let mut records = Vec::new();
+    let mut fields: Vec<Field> = Vec::new();
+    let mut field = Field { value: String::new(), raw: String::new() };
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.raw.push_str("\"\"");
+                field.value.push('"');
+            }
+            '"' => {
+                quoted = !quoted;
+                field.raw.push(c);
+            }
+            // CRLF line endings are written back as LF, like the line merge
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            c if c == delimiter && !quoted => fields.push(std::mem::replace(
+                &mut field,
+                Field { value: String::new(), raw: String::new() },
+            )),
+            '\n' if !quoted => {
+                fields.push(std::mem::replace(&mut field, Field { value: String::new(), raw: String::new() }));
+                let record = std::mem::take(&mut fields);
+                if !(record.len() == 1 && record[0].raw.trim().is_empty()) {
+                    records.push(Record { fields: record, lines: LineRange::new(start, line) });
+                }
+                line += 1;
+                start = line;
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.raw.push(c);
+                field.value.push(c);
+            }
+        }
+    }
+    if quoted {
+        return None;
+    }
+    if !field.raw.trim().is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push(Record { fields, lines: LineRange::new(start, line) });
+    }
+    Some(records)
+}
+
+/// Parse a side into a table keyed by `key_column`, or the first column if the
+/// header doesn't have it. Returns `None` if there's no header or a key repeats.
+fn parse_table(content: &str, delimiter: char, key_column: Option<&str>) -> Option<Table> {
+    let mut records = parse_records(content, delimiter)?.into_iter();
+    let header = records.next()?;
+    let columns: Vec<String> = header.fields.iter().map(|f| f.value.clone()).collect();
+    let key_index = key_column.and_then(|k| columns.iter().position(|c| c == k)).unwrap_or(0);
+
+    let mut keys = Vec::new();
+    let mut rows = HashMap::new();
+    for record in records {
+        let key = record.fields.get(key_index).map(|f| f.value.clone()).unwrap_or_default();
+        if rows.contains_key(&key) {
+            return None;
+        }
+        keys.push(key.clone());
+        rows.insert(key, record);
+    }
+    Some(Table { header, columns, keys, rows })
+}
+
+/// Join fields in the merged column order, taking each field's text as it was written.
+fn render(columns: &[String], cells: &HashMap<&str, Field>, delimiter: char) -> String {
+    columns
+        .iter()
+        .map(|c| cells.get(c.as_str()).map_or("", |f| f.raw.as_str()))
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+/// Merge a session's CSV or TSV files row by row. Rows are matched by the
+/// configured key column (the first column by default) and columns by header
+/// name; a row both sides edited is merged cell by cell and is a conflict only
+/// where both changed the same cell differently, or one side deleted the row.
+/// Replaces the line merge's hunks with one hunk per changed row, keeping each
+/// field's original quoting. Returns false, leaving the session alone, if a side
+/// can't be parsed as a table with unique keys.
+pub fn csv_merge(session: &mut MergeSession, config: &CsvConfig) -> bool {
+    let delimiter = detect_delimiter(&session.merged_path, &session.local_content);
+    let file_name = Path::new(&session.merged_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let key_column = config
+        .key_columns
+        .get(&file_name)
+        .or(config.key_column.as_ref())
+        .map(String::as_str);
+
+    let parse = |content: &str| parse_table(content, delimiter, key_column);
+    let (Some(base), Some(local), Some(remote)) = (
+        parse(&session.base_content),
+        parse(&session.local_content),
+        parse(&session.remote_content),
+    ) else {
+        return false;
+    };
+    let Some(columns) = merge_sequences(&base.columns, &local.columns, &remote.columns) else {
+        return false;
+    };
+    let column_set: HashSet<&str> = columns.iter().map(String::as_str).collect();
+    if column_set.len() != columns.len() {
+        return false;
+    }
+
+    let mut result: Vec<String> = Vec::new();
+    let mut hunks: Vec<MergeHunk> = Vec::new();
+    let lines_of = |table: &Table, key: &str| table.rows.get(key).map_or_else(LineRange::empty, |r| r.lines);
+    let text_of = |table: &Table, key: Option<&str>| -> Option<String> {
+        let fields: Vec<Field> = match key {
+            Some(key) => table.rows.get(key)?.fields.clone(),
+            None => table.header.fields.clone(),
+        };
+        let cells = table.columns.iter().map(String::as_str).zip(fields).collect();
+        Some(render(&table.columns, &cells, delimiter))
+    };
+
+    // The header, with columns either side added
+    let header_cells: HashMap<&str, Field> = columns
+        .iter()
+        .map(|c| (c.as_str(), Field { value: c.clone(), raw: c.clone() }))
+        .chain(
+            [&remote, &local]
+                .into_iter()
+                .flat_map(|t| t.columns.iter().map(String::as_str).zip(t.header.fields.iter().cloned())),
+        )
+        .collect();
+    let header = render(&columns, &header_cells, delimiter);
+    result.push(header.clone());
+    let side_headers = [text_of(&base, None), text_of(&local, None), text_of(&remote, None)].map(Option::unwrap_or_default);
+    if side_headers.iter().any(|h| *h != header) {
+        let source = match (side_headers[1] == header, side_headers[2] == header) {
+            (true, true) => HunkSource::Both,
+            (true, false) => HunkSource::Local,
+            (false, true) => HunkSource::Remote,
+            (false, false) => HunkSource::Conflict,
+        };
+        let [base_content, local_content, remote_content] = side_headers;
+        hunks.push(MergeHunk {
+            id: hunks.len(),
+            base_range: base.header.lines,
+            local_range: local.header.lines,
+            remote_range: remote.header.lines,
+            result_range: LineRange::new(1, 1),
+            source,
+            status: HunkStatus::AutoResolved,
+            local_content,
+            remote_content,
+            base_content,
+        });
+    }
+
+    for key in merge_order(&base.keys, &local.keys, &remote.keys) {
+        let (b, l, r) = (base.cells(&key), local.cells(&key), remote.cells(&key));
+        let (bv, lv, rv) = (values(&columns, &b), values(&columns, &l), values(&columns, &r));
+
+        let (text, source, status, local_text, remote_text) = if lv == rv {
+            (owned(&l), HunkSource::Both, HunkStatus::AutoResolved, None, None)
+        } else if bv == lv {
+            (owned(&r), HunkSource::Remote, HunkStatus::AutoResolved, None, None)
+        } else if bv == rv {
+            (owned(&l), HunkSource::Local, HunkStatus::AutoResolved, None, None)
+        } else if let (Some(lc), Some(rc)) = (&l, &r) {
+            // Both edited the row: merge it cell by cell
+            let mut merged: HashMap<&str, Field> = HashMap::new();
+            let mut local_side: HashMap<&str, Field> = HashMap::new();
+            let mut remote_side: HashMap<&str, Field> = HashMap::new();
+            let mut conflicted = false;
+            for column in &columns {
+                let column = column.as_str();
+                let (bc, lcv, rcv) = (cell_value(b.as_ref(), column), cell_value(Some(lc), column), cell_value(Some(rc), column));
+                let (lf, rf) = (lc.get(column).map(|f| (*f).clone()), rc.get(column).map(|f| (*f).clone()));
+                let pick = if lcv == rcv || bc == rcv {
+                    lf.clone()
+                } else if bc == lcv {
+                    rf.clone()
+                } else {
+                    conflicted = true;
+                    if let Some(f) = lf.clone() {
+                        local_side.insert(column, f);
+                    }
+                    if let Some(f) = rf.clone() {
+                        remote_side.insert(column, f);
+                    }
+                    continue;
+                };
+                if let Some(f) = pick {
+                    merged.insert(column, f.clone());
+                    local_side.insert(column, f.clone());
+                    remote_side.insert(column, f);
+                }
+            }
+            if conflicted {
+                (
+                    None,
+                    HunkSource::Conflict,
+                    HunkStatus::Unresolved,
+                    Some(render(&columns, &local_side, delimiter)),
+                    Some(render(&columns, &remote_side, delimiter)),
+                )
+            } else {
+                (Some(merged), HunkSource::Conflict, HunkStatus::AutoResolved, None, None)
+            }
+        } else {
+            // One side deleted a row the other edited
+            let render_side = |cells: &Option<HashMap<&str, &Field>>| {
+                owned(cells).map_or_else(String::new, |c| render(&columns, &c, delimiter))
+            };
+            (None, HunkSource::Conflict, HunkStatus::Unresolved, Some(render_side(&l)), Some(render_side(&r)))
+        };
+
+        let start = result.len();
+        let row_text = text.map(|cells| render(&columns, &cells, delimiter));
+        match (&row_text, &local_text, &remote_text) {
+            (Some(row), _, _) => result.extend(split_lines(row).into_iter().map(str::to_string)),
+            (None, Some(l), Some(r)) => {
+                result.push("<<<<<<< LOCAL".to_string());
+                result.extend(split_lines(l).into_iter().map(str::to_string));
+                result.push("=======".to_string());
+                result.extend(split_lines(r).into_iter().map(str::to_string));
+                result.push(">>>>>>> REMOTE".to_string());
+            }
+            _ => {}
+        }
+        if bv == lv && bv == rv {
+            continue;
+        }
+        let result_range = if result.len() > start {
+            LineRange::new(start + 1, result.len())
+        } else {
+            LineRange::empty()
+        };
+        let side_text = |table: &Table| text_of(table, Some(&key)).unwrap_or_default();
+        hunks.push(MergeHunk {
+            id: hunks.len(),
+            base_range: lines_of(&base, &key),
+            local_range: lines_of(&local, &key),
+            remote_range: lines_of(&remote, &key),
+            result_range,
+            source,
+            status,
+            local_content: local_text.unwrap_or_else(|| side_text(&local)),
+            remote_content: remote_text.unwrap_or_else(|| side_text(&remote)),
+            base_content: side_text(&base),
+        });
+    }
+
+    session.result_content = result.join("\n");
+    session.version_conflicts = find_version_conflicts(&hunks);
+    session.hunks = hunks;
+    true
+}
+
+/// Cell values in `columns`, to compare a row across sides.
+fn values<'a>(columns: &[String], cells: &Option<HashMap<&str, &'a Field>>) -> Option<Vec<&'a str>> {
+    cells.as_ref().map(|cells| {
+        columns
+            .iter()
+            .map(|c| cells.get(c.as_str()).map_or("", |f| f.value.as_str()))
+            .collect()
+    })
+}
+
+fn cell_value<'a>(cells: Option<&HashMap<&str, &'a Field>>, column: &str) -> Option<&'a str> {
+    cells.and_then(|c| c.get(column)).map(|f| f.value.as_str())
+}
+
+fn owned<'a>(cells: &Option<HashMap<&'a str, &Field>>) -> Option<HashMap<&'a str, Field>> {
+    cells.as_ref().map(|c| c.iter().map(|(k, f)| (*k, (*f).clone())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(base: &str, local: &str, remote: &str, config: &CsvConfig) -> MergeSession {
+        let mut session = MergeSession::for_test(base, local, remote, "plaintext");
+        assert!(csv_merge(&mut session, config));
+        session
+    }
+
+    fn keyed(column: &str) -> CsvConfig {
+        CsvConfig { key_column: Some(column.to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn columns_added_on_each_side_are_both_kept() {
+        let base = "id,name\n1,Ada\n2,Brian";
+        let local = "id,name,email\n1,Ada,ada@example.com\n2,Brian,brian@example.com";
+        let remote = "id,age,name\n1,36,Ada\n2,41,Brian";
+
+        let session = merge(base, local, remote, &CsvConfig::default());
+        assert_eq!(
+            session.result_content,
+            "id,age,name,email\n1,36,Ada,ada@example.com\n2,41,Brian,brian@example.com"
+        );
+        assert!(session.hunks.iter().all(|h| h.status == HunkStatus::AutoResolved));
+    }
+
+    #[test]
+    fn rows_are_matched_by_the_key_column() {
+        // Remote sorted the rows by name, and added one
+        let base = "name,sku,price\nWidget,A1,5\nGadget,B2,7";
+        let local = "name,sku,price\nWidget,A1,6\nGadget,B2,7";
+        let remote = "name,sku,price\nBolt,C3,1\nGadget,B2,7\nWidget,A1,5";
+
+        let session = merge(base, local, remote, &keyed("sku"));
+        assert_eq!(session.result_content, "name,sku,price\nBolt,C3,1\nGadget,B2,7\nWidget,A1,6");
+    }
+
+    #[test]
+    fn different_cells_of_a_row_merge() {
+        let base = "name,sku,price\nWidget,A1,5";
+        let local = "name,sku,price\nWidget,A1,6";
+        let remote = "name,sku,price\nBig widget,A1,5";
+
+        let session = merge(base, local, remote, &keyed("sku"));
+        assert_eq!(session.result_content, "name,sku,price\nBig widget,A1,6");
+        assert_eq!(session.hunks.len(), 1);
+        assert_eq!(session.hunks[0].status, HunkStatus::AutoResolved);
+    }
+
+    #[test]
+    fn same_cell_edited_differently_conflicts() {
+        let base = "name,sku,price\nWidget,A1,5";
+        let local = "name,sku,price\nBig widget,A1,6";
+        let remote = "name,sku,price\nWidget,A1,7";
+
+        let session = merge(base, local, remote, &keyed("sku"));
+        assert_eq!(
+            session.result_content,
+            "name,sku,price\n<<<<<<< LOCAL\nBig widget,A1,6\n=======\nBig widget,A1,7\n>>>>>>> REMOTE"
+        );
+        let hunk = &session.hunks[0];
+        assert_eq!(hunk.status, HunkStatus::Unresolved);
+        assert_eq!((hunk.local_content.as_str(), hunk.remote_content.as_str()), ("Big widget,A1,6", "Big widget,A1,7"));
+    }
+
+    #[test]
+    fn detects_the_delimiter() {
+        assert_eq!(detect_delimiter("prices.csv", "name;sku;price\nWidget;A1;5,50"), ';');
+        assert_eq!(detect_delimiter("prices.csv", "name|sku\nWidget|A1"), '|');
+        assert_eq!(detect_delimiter("prices.tsv", "name,sku"), '\t');
+        assert_eq!(detect_delimiter("prices.csv", "name"), ',');
+
+        let base = "name;sku;price\nWidget;A1;5,50";
+        let local = "name;sku;price\nWidget;A1;6,00";
+        let remote = "name;sku;price\nBig widget;A1;5,50";
+        let session = merge(base, local, remote, &keyed("sku"));
+        assert_eq!(session.result_content, "name;sku;price\nBig widget;A1;6,00");
+    }
+
+    #[test]
+    fn quoted_fields_keep_their_quoting() {
+        let base = "id,note,price\n1,\"Small, blue\",5\n2,\"Two\nlines\",7\n3,plain,9";
+        let local = "id,note,price\n1,\"Small, blue\",6\n2,\"Two\nlines\",8\n3,plain,9";
+        let remote = "id,note,price\n1,\"Small, \"\"navy\"\"\",5\n2,\"Two\nlines\",7\n3,plain,10";
+
+        let session = merge(base, local, remote, &CsvConfig::default());
+        assert_eq!(
+            session.result_content,
+            "id,note,price\n1,\"Small, \"\"navy\"\"\",6\n2,\"Two\nlines\",8\n3,plain,10"
+        );
+        // The multi-line row spans lines 3-4 of each side
+        let hunk = session.hunks.iter().find(|h| h.base_content.starts_with("2,")).unwrap();
+        assert_eq!((hunk.base_range.start, hunk.base_range.end), (3, 4));
+        assert_eq!((hunk.result_range.start, hunk.result_range.end), (3, 4));
+    }
+}
@@ -0,0 +1,787 @@
+use super::structural::{NodeOutcome, RefusalReason};
+
+/// A key the data merge changed or couldn't merge.
+#[derive(Debug, Clone)]
+pub struct KeyMerge {
+    /// What was merged: `key`, or `element` or `attribute` in markup.
+    pub kind: &'static str,
+    /// Dotted path of the key, e.g. `dependencies.serde`, with array elements
+    /// named by their key field, e.g. `jobs.build.steps.name=test`.
+    pub path: String,
+    /// Which side's value was kept, or why the key conflicts.
+    pub result: Result<NodeOutcome, RefusalReason>,
+    /// Lines (1-indexed) of the key in the merged document.
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// A JSON, TOML or YAML document merged key by key, or an XML or HTML one
+/// element by element. Conflicting keys keep local's value.
+#[derive(Debug, Clone)]
+pub struct DataMerge {
+    pub content: String,
+    pub keys: Vec<KeyMerge>,
+}
+
+/// Where a change sits in the merged document.
+#[derive(Debug, Clone, Copy)]
+pub enum Span {
+    /// A range of local's text.
+    Local(usize, usize),
+    /// The text of an edit.
+    Edit(usize),
+}
+
+/// Replace `start..end` of local's text.
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// Apply non-overlapping edits to `source`. `None` if any overlap.
+fn apply_edits(source: &str, edits: &[Edit]) -> Option<(String, Vec<usize>)> {
+    let mut order: Vec<usize> = (0..edits.len()).collect();
+    order.sort_by_key(|&i| (edits[i].start, edits[i].end));
+
+    let mut content = String::with_capacity(source.len());
+    let mut cursor = 0;
+    // Where each edit's text starts in the merged content
+    let mut starts = vec![0; edits.len()];
+    for i in order {
+        let edit = &edits[i];
+        if edit.start < cursor {
+            return None;
+        }
+        content.push_str(&source[cursor..edit.start]);
+        starts[i] = content.len();
+        content.push_str(&edit.text);
+        cursor = edit.end;
+    }
+    content.push_str(&source[cursor..]);
+    Some((content, starts))
+}
+
+/// Where byte `pos` of local's text ends up after the edits.
+fn map_offset(pos: usize, edits: &[Edit]) -> usize {
+    let shift: isize = edits
+        .iter()
+        .filter(|e| e.end <= pos && e.start < pos)
+        .map(|e| e.text.len() as isize - (e.end - e.start) as isize)
+        .sum();
+    (pos as isize + shift).max(0) as usize
+}
+
+fn line_of(content: &str, pos: usize) -> usize {
+    content[..pos.min(content.len())].matches('\n').count() + 1
+}
+
+/// Apply the edits to local's text and place each recorded key in the result.
+/// `None` if any edits overlap.
+pub fn apply_merge(
+    local: &str,
+    edits: &[Edit],
+    keys: Vec<(&'static str, String, Result<NodeOutcome, RefusalReason>, Span)>,
+) -> Option<DataMerge> {
+    let (content, starts) = apply_edits(local, edits)?;
+    let keys = keys
+        .into_iter()
+        .map(|(kind, path, result, span)| {
+            let (start, end) = match span {
+                Span::Local(start, end) => (map_offset(start, edits), map_offset(end, edits)),
+                Span::Edit(i) => {
+                    // Inserted entries start after the newline or separator
+                    let text = &edits[i].text;
+                    let skip = text.len() - text.trim_start_matches([',', '\n', ' ', '\t']).len();
+                    (starts[i] + skip, starts[i] + text.len())
+                }
+            };
+            KeyMerge {
+                kind,
+                path,
+                result,
+                start_line: line_of(&content, start),
+                end_line: line_of(&content, end.max(start)),
+            }
+        })
+        .collect();
+    Some(DataMerge { content, keys })
+}
+
+/// Whether a language is a data format the data merge handles.
+pub fn is_data_language(language: &str) -> bool {
+    matches!(language, "json" | "toml" | "yaml")
+}
+
+#[cfg(feature = "tree-sitter")]
+mod inner {
+    use std::collections::HashMap;
+    use tree_sitter::Node;
+    use super::{apply_merge, DataMerge, Edit, Span};
+    use crate::config::DataConfig;
+    use crate::merge::grammar::{grammar_name, session_language};
+    use crate::merge::hunk::MergeSession;
+    use crate::merge::structural::{NodeOutcome, RefusalReason};
+
+    /// How a container's entries are laid out.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Style {
+        /// One entry per line or run of lines: TOML tables, YAML block collections.
+        Lines,
+        /// Comma-separated between brackets: JSON, inline tables, flow collections.
+        Commas,
+    }
+
+    /// A map, or an array whose elements are identified by a configured field.
+    struct Container<'t> {
+        node: Node<'t>,
+        style: Style,
+        entries: Vec<Entry<'t>>,
+    }
+
+    struct Entry<'t> {
+        /// Unique within the container.
+        id: String,
+        /// The key as shown in paths.
+        name: String,
+        node: Node<'t>,
+        value: Option<Node<'t>>,
+        /// A TOML `[table]` or `[[array]]` rather than a `key = value` pair.
+        section: bool,
+    }
+
+    /// One side of the merge.
+    #[derive(Clone, Copy)]
+    struct Doc<'a> {
+        language: &'a str,
+        source: &'a str,
+        config: &'a DataConfig,
+    }
+
+    impl<'a> Doc<'a> {
+        fn text(&self, node: Node) -> &'a str {
+            &self.source[node.byte_range()]
+        }
+
+        /// Skip the YAML and JSON wrapper nodes around a value.
+        fn unwrap<'t>(&self, mut node: Node<'t>) -> Option<Node<'t>> {
+            while matches!(node.kind(), "document" | "stream" | "block_node" | "flow_node") {
+                if node.kind() == "document" && self.language == "toml" {
+                    break;
+                }
+                let mut cursor = node.walk();
+                let child = node.named_children(&mut cursor).find(|c| !c.kind().contains("comment"));
+                node = child?;
+            }
+            Some(node)
+        }
+
+        fn key_name(&self, node: Node) -> String {
+            self.text(node).trim().trim_matches(|c| c == '"' || c == '\'').to_string()
+        }
+
+        /// The container at `node`, if it is a map or a keyed array.
+        fn container<'t>(&self, node: Node<'t>, path: &[String]) -> Option<Container<'t>> {
+            let mut cursor = node.walk();
+            let children: Vec<Node<'t>> = node
+                .named_children(&mut cursor)
+                .filter(|c| !c.kind().contains("comment"))
+                .collect();
+            let pairs = |kind: &str| -> Vec<Entry<'t>> {
+                children
+                    .iter()
+                    .filter(|c| c.kind() == kind)
+                    .filter_map(|c| {
+                        let key = c.child_by_field_name("key")?;
+                        Some(self.entry(self.key_name(key), *c, c.child_by_field_name("value"), false))
+                    })
+                    .collect()
+            };
+
+            let (style, entries) = match (self.language, node.kind()) {
+                ("json", "object") => (Style::Commas, pairs("pair")),
+                ("yaml", "block_mapping") => (Style::Lines, pairs("block_mapping_pair")),
+                ("yaml", "flow_mapping") => (Style::Commas, pairs("flow_pair")),
+                ("toml", "document" | "table" | "table_array_element") => {
+                    (Style::Lines, self.toml_entries(&children, path))
+                }
+                ("toml", "inline_table") => (Style::Commas, self.toml_entries(&children, path)),
+                ("json" | "toml", "array") => (Style::Commas, self.keyed_items(&children, path)?),
+                ("yaml", "flow_sequence") => (Style::Commas, self.keyed_items(&children, path)?),
+                ("yaml", "block_sequence") => {
+                    let values: Vec<Node<'t>> = children.iter().filter_map(|c| c.named_child(0)).collect();
+                    let entries = self.keyed_items(&values, path)?;
+                    // Merge whole `- ` items, not just their values
+                    let entries = entries
+                        .into_iter()
+                        .map(|e| Entry { node: e.node.parent().unwrap_or(e.node), ..e })
+                        .collect();
+                    (Style::Lines, entries)
+                }
+                _ => return None,
+            };
+            Some(Container { node, style, entries: number_repeats(entries) })
+        }
+
+        fn entry<'t>(&self, name: String, node: Node<'t>, value: Option<Node<'t>>, section: bool) -> Entry<'t> {
+            Entry { id: name.clone(), name, node, value, section }
+        }
+
+        /// Pairs, `[tables]` and `[[array tables]]` of a TOML table. Array tables
+        /// are keyed by their configured field, and by position otherwise.
+        fn toml_entries<'t>(&self, children: &[Node<'t>], path: &[String]) -> Vec<Entry<'t>> {
+            children
+                .iter()
+                .filter_map(|c| {
+                    let key = c.named_child(0)?;
+                    let name = self.key_name(key);
+                    match c.kind() {
+                        "pair" => Some(self.entry(name, *c, c.named_child(c.named_child_count() - 1), false)),
+                        "table" => {
+                            let mut entry = self.entry(name, *c, Some(*c), true);
+                            entry.id = format!("[{}]", entry.name);
+                            Some(entry)
+                        }
+                        "table_array_element" => {
+                            let element_path = child_path(path, &name);
+                            let mut entry = self.entry(name, *c, Some(*c), true);
+                            entry.id = match self.element_key(*c, &element_path) {
+                                Some(key) => format!("[[{}]]{}", entry.name, key),
+                                None => format!("[[{}]]", entry.name),
+                            };
+                            Some(entry)
+                        }
+                        _ => None,
+                    }
+                })
+                .collect()
+        }
+
+        /// `field=value` for an array element with the field configured for `path`.
+        fn element_key(&self, element: Node, path: &[String]) -> Option<String> {
+            let field = self.config.array_key(path)?;
+            let item = self.container(self.unwrap(element)?, path)?;
+            let entry = item.entries.into_iter().find(|e| e.name == field)?;
+            Some(format!("{}={}", field, self.text(entry.value?).trim()))
+        }
+
+        /// Array elements keyed by their configured field. `None` when the array
+        /// has no key field, or an element lacks it or repeats it.
+        fn keyed_items<'t>(&self, items: &[Node<'t>], path: &[String]) -> Option<Vec<Entry<'t>>> {
+            let mut entries = Vec::new();
+            for item in items {
+                let key = self.element_key(*item, path)?;
+                if entries.iter().any(|e: &Entry| e.id == key) {
+                    return None;
+                }
+                entries.push(self.entry(key, *item, Some(*item), false));
+            }
+            Some(entries)
+        }
+    }
+
+    /// Tell apart repeated keys (positional `[[array tables]]`).
+    fn number_repeats(mut entries: Vec<Entry>) -> Vec<Entry> {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for entry in entries.iter_mut() {
+            let count = seen.entry(entry.id.clone()).or_insert(0);
+            if *count > 0 {
+                entry.id = format!("{}#{}", entry.id, count);
+            }
+            *count += 1;
+        }
+        entries
+    }
+
+    fn child_path(path: &[String], name: &str) -> Vec<String> {
+        let mut path = path.to_vec();
+        path.push(name.to_string());
+        path
+    }
+
+    fn line_start(source: &str, pos: usize) -> usize {
+        source[..pos].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn line_end(source: &str, pos: usize) -> usize {
+        source[pos..].find('\n').map_or(source.len(), |i| pos + i)
+    }
+
+    /// The whole lines an entry occupies, without trailing blank lines.
+    fn own_lines(source: &str, node: Node) -> (usize, usize) {
+        let text = &source[node.byte_range()];
+        let end = node.start_byte() + text.trim_end().len();
+        (line_start(source, node.start_byte()), line_end(source, end.max(node.start_byte())))
+    }
+
+    /// The lines of entry `i` together with the comments and blank lines between
+    /// it and the entry before it.
+    fn lead_lines(source: &str, container: &Container, i: usize) -> (usize, usize) {
+        let (start, end) = own_lines(source, container.entries[i].node);
+        if i == 0 {
+            return (start, end);
+        }
+        let (_, prev_end) = own_lines(source, container.entries[i - 1].node);
+        ((prev_end + 1).min(start), end)
+    }
+
+    struct Merger<'a> {
+        base: Doc<'a>,
+        local: Doc<'a>,
+        remote: Doc<'a>,
+        edits: Vec<Edit>,
+        keys: Vec<(&'static str, String, Result<NodeOutcome, RefusalReason>, Span)>,
+    }
+
+    impl Merger<'_> {
+        fn edit(&mut self, start: usize, end: usize, text: String) -> Span {
+            self.edits.push(Edit { start, end, text });
+            Span::Edit(self.edits.len() - 1)
+        }
+
+        fn record(&mut self, path: String, result: Result<NodeOutcome, RefusalReason>, span: Span) {
+            self.keys.push(("key", path, result, span));
+        }
+
+        fn local_span(&self, node: Node) -> Span {
+            let (start, end) = own_lines(self.local.source, node);
+            Span::Local(start, end)
+        }
+
+        /// Merge remote's changes to one container into local's copy of it.
+        fn merge_container(
+            &mut self,
+            base: Option<&Container>,
+            local: &Container,
+            remote: &Container,
+            path: &[String],
+        ) {
+            let find = |c: &Container, id: &str| c.entries.iter().position(|e| e.id == id);
+            let base_doc = self.base;
+            let base_text = |id: &str| base.and_then(|b| find(b, id).map(|i| base_doc.text(b.entries[i].node)));
+
+            // Remote's edits and insertions, in remote order
+            let mut anchor: Option<usize> = None;
+            let mut inserts: Vec<(Option<usize>, usize)> = Vec::new();
+            for (ri, r) in remote.entries.iter().enumerate() {
+                let entry_path = child_path(path, &r.name);
+                let remote_text = self.remote.text(r.node);
+                let Some(li) = find(local, &r.id) else {
+                    match base_text(&r.id) {
+                        // Remote added it, after any entries local added at the same point
+                        None => {
+                            let mut at = anchor;
+                            let next = at.map_or(0, |a| a + 1);
+                            for (li, l) in local.entries.iter().enumerate().skip(next) {
+                                let local_added = base.map_or(true, |b| find(b, &l.id).is_none())
+                                    && find(remote, &l.id).is_none();
+                                if !local_added {
+                                    break;
+                                }
+                                at = Some(li);
+                            }
+                            inserts.push((at, ri));
+                        }
+                        // Local deleted it
+                        Some(b) if b == remote_text => {
+                            let at = anchor.map_or(local.node.start_byte(), |a| local.entries[a].node.end_byte());
+                            self.record(entry_path.join("."), Ok(NodeOutcome::Deleted), Span::Local(at, at));
+                        }
+                        Some(_) => {
+                            let at = anchor.map_or(local.node.start_byte(), |a| local.entries[a].node.end_byte());
+                            self.record(entry_path.join("."), Err(RefusalReason::DeleteModify), Span::Local(at, at));
+                        }
+                    }
+                    continue;
+                };
+                anchor = Some(li);
+
+                let l = &local.entries[li];
+                let local_text = self.local.text(l.node);
+                let base_entry_text = base_text(&r.id);
+                let span = self.local_span(l.node);
+                if local_text == remote_text {
+                    if base_entry_text != Some(local_text) {
+                        self.record(entry_path.join("."), Ok(NodeOutcome::Both), span);
+                    }
+                } else if base_entry_text == Some(remote_text) {
+                    self.record(entry_path.join("."), Ok(NodeOutcome::Local), span);
+                } else if base_entry_text == Some(local_text) {
+                    let span = self.replace(local, li, remote, ri);
+                    self.record(entry_path.join("."), Ok(NodeOutcome::Remote), span);
+                } else {
+                    self.merge_values(base.and_then(|b| find(b, &r.id).map(|i| &b.entries[i])), l, r, &entry_path);
+                }
+            }
+
+            // Remote's deletions
+            let mut deleted = Vec::new();
+            if let Some(base) = base {
+                for b in base.entries.iter().filter(|b| find(remote, &b.id).is_none()) {
+                    let entry_path = child_path(path, &b.name).join(".");
+                    let Some(li) = find(local, &b.id) else { continue };
+                    let l = &local.entries[li];
+                    if self.local.text(l.node) == self.base.text(b.node) {
+                        deleted.push(li);
+                        let (start, _) = own_lines(self.local.source, l.node);
+                        self.record(entry_path, Ok(NodeOutcome::Deleted), Span::Local(start, start));
+                    } else {
+                        let span = self.local_span(l.node);
+                        self.record(entry_path, Err(RefusalReason::DeleteModify), span);
+                    }
+                }
+            }
+            deleted.sort_unstable();
+            self.delete(local, &deleted);
+
+            // Local's own additions
+            for l in local.entries.iter() {
+                let added = base.map_or(true, |b| find(b, &l.id).is_none()) && find(remote, &l.id).is_none();
+                if added {
+                    let span = self.local_span(l.node);
+                    self.record(child_path(path, &l.name).join("."), Ok(NodeOutcome::Local), span);
+                }
+            }
+
+            self.insert(local, remote, path, inserts);
+        }
+
+        /// Both sides changed an entry differently: merge maps key by key, and
+        /// conflict on anything else.
+        fn merge_values(&mut self, base: Option<&Entry>, local: &Entry, remote: &Entry, path: &[String]) {
+            let containers = (
+                base.and_then(|b| self.base.unwrap(b.value?)).and_then(|n| self.base.container(n, path)),
+                local.value.and_then(|n| self.local.unwrap(n)).and_then(|n| self.local.container(n, path)),
+                remote.value.and_then(|n| self.remote.unwrap(n)).and_then(|n| self.remote.container(n, path)),
+            );
+            match containers {
+                (base_container, Some(l), Some(r))
+                    if l.node.kind() == r.node.kind() && (base.is_none() || base_container.is_some()) =>
+                {
+                    self.merge_container(base_container.as_ref(), &l, &r, path);
+                }
+                _ => {
+                    let span = self.local_span(local.node);
+                    self.record(path.join("."), Err(RefusalReason::SameNodeChanged), span);
+                }
+            }
+        }
+
+        /// Replace local entry `li` with remote entry `ri`.
+        fn replace(&mut self, local: &Container, li: usize, remote: &Container, ri: usize) -> Span {
+            let (l, r) = (&local.entries[li], &remote.entries[ri]);
+            match local.style {
+                Style::Lines => {
+                    let (start, end) = own_lines(self.local.source, l.node);
+                    let (r_start, r_end) = own_lines(self.remote.source, r.node);
+                    let text = self.remote.source[r_start..r_end].to_string();
+                    self.edit(start, end, text)
+                }
+                Style::Commas => {
+                    let text = self.remote.text(r.node).to_string();
+                    self.edit(l.node.start_byte(), l.node.end_byte(), text)
+                }
+            }
+        }
+
+        /// Delete local entries (sorted indexes) along with their separators.
+        fn delete(&mut self, local: &Container, deleted: &[usize]) {
+            let source = self.local.source;
+            match local.style {
+                Style::Lines => {
+                    for &i in deleted {
+                        let (start, end) = lead_lines(source, local, i);
+                        if end < source.len() {
+                            self.edit(start, end + 1, String::new());
+                        } else {
+                            self.edit(start.saturating_sub(1), end, String::new());
+                        }
+                    }
+                }
+                Style::Commas => {
+                    // Each run of deleted entries goes with the separator after it,
+                    // or before it when it runs to the end
+                    let mut i = 0;
+                    while i < deleted.len() {
+                        let first = deleted[i];
+                        while i + 1 < deleted.len() && deleted[i + 1] == deleted[i] + 1 {
+                            i += 1;
+                        }
+                        let last = deleted[i];
+                        let entries = &local.entries;
+                        if let Some(next) = entries.get(last + 1) {
+                            self.edit(entries[first].node.start_byte(), next.node.start_byte(), String::new());
+                        } else if first > 0 {
+                            self.edit(entries[first - 1].node.end_byte(), entries[last].node.end_byte(), String::new());
+                        } else {
+                            self.edit(entries[first].node.start_byte(), entries[last].node.end_byte(), String::new());
+                        }
+                        i += 1;
+                    }
+                }
+            }
+        }
+
+        /// Separator between entries of a comma-separated container, e.g. `",\n  "`.
+        fn separator(doc: &Doc, container: &Container) -> Option<String> {
+            let [a, b, ..] = container.entries.as_slice() else { return None };
+            Some(doc.source[a.node.end_byte()..b.node.start_byte()].to_string())
+        }
+
+        /// Insert remote's new entries into local, each after the local entry that
+        /// precedes it in remote.
+        fn insert(&mut self, local: &Container, remote: &Container, path: &[String], inserts: Vec<(Option<usize>, usize)>) {
+            let source = self.local.source;
+            let mut groups: Vec<(Option<usize>, Vec<usize>)> = Vec::new();
+            for (anchor, ri) in inserts {
+                let anchor = self.toml_anchor(local, &remote.entries[ri], anchor);
+                match groups.last_mut() {
+                    Some((a, items)) if *a == anchor => items.push(ri),
+                    _ => groups.push((anchor, vec![ri])),
+                }
+            }
+
+            for (anchor, items) in groups {
+                let texts: Vec<String> = items
+                    .iter()
+                    .map(|&ri| match remote.style {
+                        Style::Lines => {
+                            let (start, end) = lead_lines(self.remote.source, remote, ri);
+                            self.remote.source[start..end].to_string()
+                        }
+                        Style::Commas => self.remote.text(remote.entries[ri].node).to_string(),
+                    })
+                    .collect();
+                let span = match (local.style, anchor) {
+                    (Style::Lines, Some(a)) => {
+                        let (_, end) = own_lines(source, local.entries[a].node);
+                        self.edit(end, end, format!("\n{}", texts.join("\n")))
+                    }
+                    (Style::Lines, None) => match local.entries.first() {
+                        Some(first) => {
+                            let (start, _) = own_lines(source, first.node);
+                            self.edit(start, start, format!("{}\n", texts.join("\n")))
+                        }
+                        None => {
+                            let end = local.node.end_byte();
+                            let newline = if source[..end].ends_with('\n') || end == 0 { "" } else { "\n" };
+                            self.edit(end, end, format!("{}{}", newline, texts.join("\n")))
+                        }
+                    },
+                    (Style::Commas, _) => {
+                        let sep = Self::separator(&self.local, local)
+                            .or_else(|| Self::separator(&self.remote, remote))
+                            .unwrap_or_else(|| ", ".to_string());
+                        match (anchor, local.entries.first()) {
+                            (Some(a), _) => {
+                                let end = local.entries[a].node.end_byte();
+                                self.edit(end, end, format!("{}{}", sep, texts.join(&sep)))
+                            }
+                            (None, Some(first)) => {
+                                let start = first.node.start_byte();
+                                self.edit(start, start, format!("{}{}", texts.join(&sep), sep))
+                            }
+                            // Right after the opening bracket
+                            (None, None) => {
+                                let at = local.node.child(0).map_or(local.node.start_byte(), |c| c.end_byte());
+                                self.edit(at, at, texts.join(&sep))
+                            }
+                        }
+                    }
+                };
+                for ri in items {
+                    let entry_path = child_path(path, &remote.entries[ri].name).join(".");
+                    self.record(entry_path, Ok(NodeOutcome::Remote), span);
+                }
+            }
+        }
+
+        /// In a TOML table, new pairs go after the last local pair and new
+        /// sections after the last local entry, or they'd change tables.
+        fn toml_anchor(&self, local: &Container, entry: &Entry, anchor: Option<usize>) -> Option<usize> {
+            if local.style != Style::Lines || self.local.language != "toml" {
+                return anchor;
+            }
+            let last_pair = local.entries.iter().rposition(|e| !e.section);
+            if entry.section {
+                anchor.max(last_pair)
+            } else if anchor.is_some_and(|a| local.entries[a].section) {
+                last_pair
+            } else {
+                anchor
+            }
+        }
+    }
+
+    /// Merge a JSON, TOML or YAML session key by key: local's text with remote's
+    /// changed, added and deleted keys applied, so untouched entries keep their
+    /// formatting and comments. Keys both sides changed are merged recursively
+    /// when both values are maps (or keyed arrays), and conflict otherwise.
+    /// `None` if a side doesn't parse or the merged document wouldn't.
+    pub fn merge_data(session: &MergeSession, config: &DataConfig) -> Option<DataMerge> {
+        let language = session_language(session)?;
+        let grammar = grammar_name(session);
+        let parse = |source: &str| session.trees.parse(grammar, &language, source).filter(|t| !t.root_node().has_error());
+        let (base_tree, local_tree, remote_tree) = (
+            parse(&session.base_content)?,
+            parse(&session.local_content)?,
+            parse(&session.remote_content)?,
+        );
+
+        let language_name = session.language.as_str();
+        let mut merger = Merger {
+            base: Doc { language: language_name, source: &session.base_content, config },
+            local: Doc { language: language_name, source: &session.local_content, config },
+            remote: Doc { language: language_name, source: &session.remote_content, config },
+            edits: Vec::new(),
+            keys: Vec::new(),
+        };
+        let base = merger.base.container(merger.base.unwrap(base_tree.root_node())?, &[])?;
+        let local = merger.local.container(merger.local.unwrap(local_tree.root_node())?, &[])?;
+        let remote = merger.remote.container(merger.remote.unwrap(remote_tree.root_node())?, &[])?;
+        if local.node.kind() != remote.node.kind() || base.node.kind() != local.node.kind() {
+            return None;
+        }
+        merger.merge_container(Some(&base), &local, &remote, &[]);
+
+        let merged = apply_merge(&session.local_content, &merger.edits, merger.keys)?;
+        parse(&merged.content)?;
+        Some(merged)
+    }
+}
+
+#[cfg(not(feature = "tree-sitter"))]
+mod inner {
+    use super::DataMerge;
+    use crate::config::DataConfig;
+    use crate::merge::hunk::MergeSession;
+
+    pub fn merge_data(_session: &MergeSession, _config: &DataConfig) -> Option<DataMerge> {
+        None
+    }
+}
+
+pub use inner::merge_data;
+
+#[cfg(all(test, feature = "tree-sitter"))]
+mod tests {
+    use super::*;
+    use crate::config::DataConfig;
+    use crate::merge::hunk::MergeSession;
+
+    fn merge(base: &str, local: &str, remote: &str, language: &str, array_keys: &[(&str, &str)]) -> DataMerge {
+        let mut config = DataConfig::default();
+        for (path, key) in array_keys {
+            config.array_keys.insert(path.to_string(), key.to_string());
+        }
+        let session = MergeSession::for_test(base, local, remote, language);
+        merge_data(&session, &config).expect("documents merge")
+    }
+
+    fn conflicts(merged: &DataMerge) -> Vec<&str> {
+        merged
+            .keys
+            .iter()
+            .filter(|k| k.result.is_err())
+            .map(|k| k.path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn json_takes_keys_added_on_both_sides() {
+        let base = "{\n  \"name\": \"w\",\n  \"deps\": {\n    \"a\": \"1\"\n  }\n}\n";
+        let local = "{\n  \"name\": \"w\",\n  \"deps\": {\n    \"a\": \"1\",\n    \"b\": \"2\"\n  }\n}\n";
+        let remote = "{\n  \"name\": \"w\",\n  \"deps\": {\n    \"a\": \"1\",\n    \"c\": \"3\"\n  }\n}\n";
+
+        let merged = merge(base, local, remote, "json", &[]);
+        assert_eq!(
+            merged.content,
+            "{\n  \"name\": \"w\",\n  \"deps\": {\n    \"a\": \"1\",\n    \"b\": \"2\",\n    \"c\": \"3\"\n  }\n}\n"
+        );
+        assert!(conflicts(&merged).is_empty());
+    }
+
+    #[test]
+    fn toml_takes_keys_added_on_both_sides_and_keeps_comments() {
+        let base = "[package]\nname = \"w\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\n# json\nserde_json = \"1\"  # pinned\n";
+        let local = "[package]\nname = \"w\"\nversion = \"0.1.0\"\n\n[dependencies]\nserde = \"1\"\n# json\nserde_json = \"1\"  # pinned\nsimilar = \"2\"\n";
+        let remote = "[package]\nname = \"w\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nserde = \"1\"\n# json\nserde_json = \"1\"  # pinned\n";
+
+        let merged = merge(base, local, remote, "toml", &[]);
+        assert_eq!(
+            merged.content,
+            "[package]\nname = \"w\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\nserde = \"1\"\n# json\nserde_json = \"1\"  # pinned\nsimilar = \"2\"\n"
+        );
+    }
+
+    #[test]
+    fn yaml_takes_keys_added_on_both_sides_and_keeps_comments() {
+        let base = "on: push\njobs:\n  build:\n    runs-on: ubuntu  # pinned\n";
+        let local = "on: push\njobs:\n  build:\n    runs-on: ubuntu  # pinned\n    timeout: 10\n";
+        let remote = "on: push\njobs:\n  build:\n    runs-on: ubuntu  # pinned\n\n    # for the cache\n    env: ci\n";
+
+        let merged = merge(base, local, remote, "yaml", &[]);
+        assert_eq!(
+            merged.content,
+            "on: push\njobs:\n  build:\n    runs-on: ubuntu  # pinned\n    timeout: 10\n\n    # for the cache\n    env: ci\n"
+        );
+    }
+
+    #[test]
+    fn json_keeps_each_sides_formatting() {
+        let base = "{\"a\": 1,   \"b\": [1, 2],\n \"c\": 3}";
+        let local = "{\"a\": 10,   \"b\": [1, 2],\n \"c\": 3}";
+        let remote = "{\"a\": 1,   \"b\": [1, 2],\n \"c\":    30}";
+
+        let merged = merge(base, local, remote, "json", &[]);
+        assert_eq!(merged.content, "{\"a\": 10,   \"b\": [1, 2],\n \"c\":    30}");
+    }
+
+    #[test]
+    fn keyed_arrays_merge_element_by_element() {
+        let base = "{\"xs\": [{\"id\": 1, \"v\": 1}, {\"id\": 2, \"v\": 2}]}";
+        let local = "{\"xs\": [{\"id\": 1, \"v\": 10}, {\"id\": 2, \"v\": 2}]}";
+        let remote = "{\"xs\": [{\"id\": 1, \"v\": 1}, {\"id\": 2, \"v\": 20}, {\"id\": 3, \"v\": 3}]}";
+
+        let merged = merge(base, local, remote, "json", &[("xs", "id")]);
+        assert_eq!(
+            merged.content,
+            "{\"xs\": [{\"id\": 1, \"v\": 10}, {\"id\": 2, \"v\": 20}, {\"id\": 3, \"v\": 3}]}"
+        );
+        assert!(conflicts(&merged).is_empty());
+        let paths: Vec<&str> = merged.keys.iter().map(|k| k.path.as_str()).collect();
+        assert_eq!(paths, ["xs.id=1", "xs.id=2", "xs.id=3"]);
+
+        // Without a key the array is one value both sides changed
+        let merged = merge(base, local, remote, "json", &[]);
+        assert_eq!(conflicts(&merged), ["xs"]);
+    }
+
+    #[test]
+    fn toml_array_keys_use_dotted_table_paths() {
+        let base = "[package.metadata]\nassets = [\n  { source = \"a\", dest = \"1\" },\n  { source = \"b\", dest = \"2\" },\n]\n";
+        let local = base.replace("dest = \"1\"", "dest = \"10\"");
+        let remote = base.replace("dest = \"2\"", "dest = \"20\"");
+
+        let merged = merge(base, &local, &remote, "toml", &[("package.metadata.assets", "source")]);
+        assert_eq!(merged.content, local.replace("dest = \"2\"", "dest = \"20\""));
+        let paths: Vec<&str> = merged.keys.iter().map(|k| k.path.as_str()).collect();
+        assert_eq!(paths, ["package.metadata.assets.source=\"a\"", "package.metadata.assets.source=\"b\""]);
+    }
+
+    #[test]
+    fn same_key_changed_both_ways_conflicts() {
+        let base = "[dependencies]\nserde = \"1\"\ntoml = \"0.8\"\n";
+        let local = "[dependencies]\nserde = \"1.1\"\ntoml = \"0.8\"\n";
+        let remote = "[dependencies]\nserde = \"1.2\"\ntoml = \"0.9\"\n";
+
+        let merged = merge(base, local, remote, "toml", &[]);
+        assert_eq!(conflicts(&merged), ["dependencies.serde"]);
+        // The conflicting key keeps local's value; the rest merges
+        assert_eq!(merged.content, "[dependencies]\nserde = \"1.1\"\ntoml = \"0.9\"\n");
+        let serde = merged.keys.iter().find(|k| k.path == "dependencies.serde").unwrap();
+        assert!(matches!(serde.result, Err(RefusalReason::SameNodeChanged)));
+        assert_eq!((serde.start_line, serde.end_line), (2, 2));
+    }
+}
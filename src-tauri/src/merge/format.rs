@@ -0,0 +1,138 @@
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::FormatterConfig;
+
+/// How long a formatter may run when its config sets no timeout.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a running formatter is checked on.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Read a pipe to the end on another thread.
+fn read_all(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<std::io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        pipe.read_to_end(&mut buf).map(|_| buf)
+    })
+}
+
+/// Run a formatter over `input`, feeding it on stdin and reading the formatted
+/// text from stdout. A formatter still running after its timeout is killed.
+pub fn run_formatter(config: &FormatterConfig, input: &str) -> Result<String, String> {
+    let mut child = Command::new(&config.command)
+        .args(&config.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", config.command, e))?;
+
+    // Write and read on other threads so a formatter that streams its output
+    // doesn't block on a full pipe while we wait for it
+    let mut stdin = child.stdin.take().ok_or("Formatter stdin unavailable")?;
+    let stdout = read_all(child.stdout.take().ok_or("Formatter stdout unavailable")?);
+    let stderr = read_all(child.stderr.take().ok_or("Formatter stderr unavailable")?);
+    let input = input.to_string();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let timeout = config.timeout.map_or(DEFAULT_TIMEOUT, Duration::from_secs);
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("{} timed out after {}s", config.command, timeout.as_secs()));
+            }
+            Err(e) => return Err(format!("Failed to run {}: {}", config.command, e)),
+        }
+    };
+
+    writer
+        .join()
+        .map_err(|_| "Formatter input thread panicked".to_string())?
+        .map_err(|e| format!("Failed to write to {}: {}", config.command, e))?;
+    let output = |reader: thread::JoinHandle<std::io::Result<Vec<u8>>>| {
+        reader
+            .join()
+            .map_err(|_| "Formatter output thread panicked".to_string())?
+            .map_err(|e| format!("Failed to read from {}: {}", config.command, e))
+    };
+    let (stdout, stderr) = (output(stdout)?, output(stderr)?);
+
+    if !status.success() {
+        return Err(format!(
+            "{} exited with {}: {}",
+            config.command,
+            status,
+            String::from_utf8_lossy(&stderr).trim()
+        ));
+    }
+    String::from_utf8(stdout).map_err(|e| format!("{} printed invalid UTF-8: {}", config.command, e))
+}
+
+/// Format base, local and remote so formatting differences don't reach the
+/// merge. Returns `None`, after a warning, if the formatter fails on any of them.
+pub fn normalize(
+    config: &FormatterConfig,
+    base: &str,
+    local: &str,
+    remote: &str,
+) -> Option<(String, String, String)> {
+    let format = |name: &str, content: &str| {
+        run_formatter(config, content)
+            .map_err(|e| eprintln!("Warning: Could not format {}, merging unformatted: {}", name, e))
+            .ok()
+    };
+    Some((format("base", base)?, format("local", local)?, format("remote", remote)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatter(command: &str, args: &[&str]) -> FormatterConfig {
+        FormatterConfig {
+            command: command.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            timeout: Some(1),
+        }
+    }
+
+    #[test]
+    fn formats_through_a_command() {
+        let upper = formatter("sh", &["-c", "tr a-z A-Z"]);
+        assert_eq!(run_formatter(&upper, "fn main() {}\n").unwrap(), "FN MAIN() {}\n");
+        assert_eq!(
+            normalize(&upper, "a", "b", "c"),
+            Some(("A".to_string(), "B".to_string(), "C".to_string()))
+        );
+    }
+
+    #[test]
+    fn failing_formatter_falls_back() {
+        let failing = formatter("false", &[]);
+        assert!(run_formatter(&failing, "x").is_err());
+        assert_eq!(normalize(&failing, "a", "b", "c"), None);
+    }
+
+    #[test]
+    fn missing_formatter_falls_back() {
+        let missing = formatter("weaver-no-such-formatter", &[]);
+        assert!(run_formatter(&missing, "x").unwrap_err().starts_with("Failed to run"));
+        assert_eq!(normalize(&missing, "a", "b", "c"), None);
+    }
+
+    #[test]
+    fn hanging_formatter_is_killed() {
+        let hanging = formatter("sleep", &["30"]);
+        let started = Instant::now();
+        assert!(run_formatter(&hanging, "x").unwrap_err().contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+
+use crate::config::GrammarConfig;
+
+#[cfg(feature = "tree-sitter")]
+mod inner {
+    use std::collections::{BTreeMap, HashMap};
+    use std::path::Path;
+    use std::sync::OnceLock;
+    use libloading::{Library, Symbol};
+    use tree_sitter::{Language, LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION};
+    use tree_sitter_language::LanguageFn;
+    use crate::config::GrammarConfig;
+    use crate::merge::hunk::MergeSession;
+
+    /// A grammar loaded from a shared library. The library is kept alive alongside
+    /// the language, whose parse tables live inside it.
+    struct LoadedGrammar {
+        language: Language,
+        _library: Library,
+    }
+
+    static LOADED: OnceLock<HashMap<String, LoadedGrammar>> = OnceLock::new();
+
+    /// The grammars compiled in through the `lang-*` features.
+    fn builtin_language(lang: &str) -> Option<Language> {
+        match lang {
+            #[cfg(feature = "lang-rust")]
+            "rust" => Some(tree_sitter_rust::LANGUAGE.into()),
+            #[cfg(feature = "lang-typescript")]
+            "typescript" => Some(tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()),
+            #[cfg(feature = "lang-typescript")]
+            "tsx" => Some(tree_sitter_typescript::LANGUAGE_TSX.into()),
+            // The JavaScript grammar parses JSX too
+            #[cfg(feature = "lang-javascript")]
+            "javascript" => Some(tree_sitter_javascript::LANGUAGE.into()),
+            #[cfg(feature = "lang-python")]
+            "python" => Some(tree_sitter_python::LANGUAGE.into()),
+            #[cfg(feature = "lang-go")]
+            "go" => Some(tree_sitter_go::LANGUAGE.into()),
+            #[cfg(feature = "lang-java")]
+            "java" => Some(tree_sitter_java::LANGUAGE.into()),
+            #[cfg(feature = "lang-c")]
+            "c" => Some(tree_sitter_c::LANGUAGE.into()),
+            #[cfg(feature = "lang-cpp")]
+            "cpp" => Some(tree_sitter_cpp::LANGUAGE.into()),
+            #[cfg(feature = "lang-json")]
+            "json" => Some(tree_sitter_json::LANGUAGE.into()),
+            #[cfg(feature = "lang-toml")]
+            "toml" => Some(tree_sitter_toml_ng::LANGUAGE.into()),
+            #[cfg(feature = "lang-yaml")]
+            "yaml" => Some(tree_sitter_yaml::LANGUAGE.into()),
+            #[cfg(feature = "lang-bash")]
+            "shell" => Some(tree_sitter_bash::LANGUAGE.into()),
+            #[cfg(feature = "lang-css")]
+            "css" => Some(tree_sitter_css::LANGUAGE.into()),
+            #[cfg(feature = "lang-html")]
+            "html" => Some(tree_sitter_html::LANGUAGE.into()),
+            #[cfg(feature = "lang-ruby")]
+            "ruby" => Some(tree_sitter_ruby::LANGUAGE.into()),
+            #[cfg(feature = "lang-csharp")]
+            "csharp" => Some(tree_sitter_c_sharp::LANGUAGE.into()),
+            #[cfg(feature = "lang-kotlin")]
+            "kotlin" => Some(tree_sitter_kotlin_ng::LANGUAGE.into()),
+            #[cfg(feature = "lang-php")]
+            "php" => Some(tree_sitter_php::LANGUAGE_PHP.into()),
+            _ => None,
+        }
+    }
+
+    /// Look up the grammar for a language: configured grammars first, then the
+    /// compiled-in ones.
+    pub fn get_language(lang: &str) -> Option<Language> {
+        LOADED
+            .get()
+            .and_then(|loaded| loaded.get(lang))
+            .map(|g| g.language.clone())
+            .or_else(|| builtin_language(lang))
+    }
+
+    /// The grammar (and merge profile) name for a session's file. Sessions carry the
+    /// editor's language id, which doesn't tell TSX apart from plain TypeScript, so
+    /// check the extension.
+    pub fn grammar_name(session: &MergeSession) -> &str {
+        let is_tsx = Path::new(&session.merged_path)
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("tsx"));
+        match session.language.as_str() {
+            "typescript" if is_tsx => "tsx",
+            lang => lang,
+        }
+    }
+
+    /// The grammar for a session's file.
+    pub fn session_language(session: &MergeSession) -> Option<Language> {
+        get_language(grammar_name(session))
+    }
+
+    fn load_grammar(name: &str, config: &GrammarConfig) -> Result<LoadedGrammar, String> {
+        let symbol = config
+            .symbol
+            .clone()
+            .unwrap_or_else(|| format!("tree_sitter_{}", name.replace('-', "_")));
+
+        // SAFETY: the library is one the user configured as a tree-sitter grammar,
+        // and `tree_sitter_<lang>` has the signature every generated parser exports.
+        let grammar = unsafe {
+            let library = Library::new(&config.path).map_err(|e| e.to_string())?;
+            let language_fn: Symbol<unsafe extern "C" fn() -> *const ()> = library
+                .get(symbol.as_bytes())
+                .map_err(|e| format!("symbol {}: {}", symbol, e))?;
+            let language = Language::new(LanguageFn::from_raw(*language_fn));
+            LoadedGrammar {
+                language,
+                _library: library,
+            }
+        };
+
+        let version = grammar.language.version();
+        if !(MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION).contains(&version) {
+            return Err(format!(
+                "ABI version {} is not supported (expected {}..={})",
+                version, MIN_COMPATIBLE_LANGUAGE_VERSION, LANGUAGE_VERSION
+            ));
+        }
+        Ok(grammar)
+    }
+
+    /// Load the grammars configured under `[grammars]`. Grammars that fail to load
+    /// are skipped with a warning. Only the first call has any effect.
+    pub fn load_grammars(configs: &BTreeMap<String, GrammarConfig>) {
+        let mut loaded = HashMap::new();
+        for (name, config) in configs {
+            match load_grammar(name, config) {
+                Ok(grammar) => {
+                    loaded.insert(name.clone(), grammar);
+                }
+                Err(e) => eprintln!(
+                    "Warning: Could not load grammar {} from {}: {}",
+                    name,
+                    config.path.display(),
+                    e
+                ),
+            }
+        }
+        let _ = LOADED.set(loaded);
+    }
+}
+
+#[cfg(not(feature = "tree-sitter"))]
+mod inner {
+    use std::collections::BTreeMap;
+    use crate::config::GrammarConfig;
+
+    pub fn load_grammars(configs: &BTreeMap<String, GrammarConfig>) {
+        if !configs.is_empty() {
+            eprintln!("Warning: Built without tree-sitter support, ignoring configured grammars");
+        }
+    }
+}
+
+#[cfg(feature = "tree-sitter")]
+pub use inner::{get_language, grammar_name, session_language};
+pub use inner::load_grammars;
+
+/// Find the configured grammar, if any, that claims a file extension.
+pub fn language_for_extension<'a>(
+    configs: &'a BTreeMap<String, GrammarConfig>,
+    ext: &str,
+) -> Option<&'a str> {
+    configs
+        .iter()
+        .find(|(_, config)| {
+            config
+                .extensions
+                .iter()
+                .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(ext))
+        })
+        .map(|(name, _)| name.as_str())
+}
@@ -1,5 +1,12 @@
 use serde::{Deserialize, Serialize};
 
+use super::lockfile::LockfileReport;
+use super::notebook::Notebook;
+use super::rename::IdentifierRename;
+use super::structural::DetectedRename;
+use super::trees::TreeCache;
+use super::version::VersionConflict;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum HunkStatus {
     Unresolved,
@@ -8,6 +15,16 @@ pub enum HunkStatus {
     AcceptedBoth,
     CustomEdit,
     AutoResolved,
+    /// Version-only conflict resolved by keeping the higher literal.
+    AcceptedHigherVersion,
+    /// Version-only conflict resolved by keeping the lower literal.
+    AcceptedLowerVersion,
+    /// Conflict where one side only reformatted, resolved by applying the other
+    /// side's edits to the reformatted text.
+    FormattingMerged,
+    /// Conflict in a language without a grammar, merged block by block with
+    /// blocks found from indentation and brackets.
+    HeuristicMerged,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -18,6 +35,27 @@ pub enum HunkSource {
     Conflict,
 }
 
+/// How the files in a session are merged beyond the plain line-based 3-way merge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergeStrategy {
+    /// Line-based merge with tree-sitter structural auto-resolve.
+    Text,
+    /// Order-insensitive line sets (`.gitignore`, `CODEOWNERS`, gitattributes `merge=union`).
+    Union,
+    /// Keep-a-changelog files, merged per heading and bullet entry.
+    Changelog,
+    /// `Cargo.lock`, `package-lock.json` and `pnpm-lock.yaml`, merged package by package.
+    Lockfile,
+    /// Jupyter notebooks, merged cell by cell and shown as cell sources.
+    Notebook,
+    /// CSV and TSV files, merged row by row on a key column.
+    Csv,
+    /// Markdown and `merge=prose` files, merged block by block and then sentence by sentence.
+    Prose,
+    /// Gettext `.po` catalogues, merged entry by entry on `msgctxt` and `msgid`.
+    Po,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LineRange {
@@ -75,4 +113,51 @@ pub struct MergeSession {
     pub result_content: String,
     pub hunks: Vec<MergeHunk>,
     pub language: String,
+    pub strategy: MergeStrategy,
+    pub version_conflicts: Vec<VersionConflict>,
+    /// Renames the structural merge detected in the hunks it resolved.
+    pub renames: Vec<DetectedRename>,
+    /// Identifiers one side renamed throughout the file, offered for replay.
+    pub identifier_renames: Vec<IdentifierRename>,
+    /// Whether base, local and remote were run through the language's configured
+    /// formatter before merging.
+    pub normalized: bool,
+    /// Set once a lockfile's conflicts have been auto-resolved.
+    pub lockfile: Option<LockfileReport>,
+    /// For a notebook, the merged cells' outputs and metadata, used to write the
+    /// result back out as `.ipynb`.
+    #[serde(skip)]
+    pub notebook: Option<Notebook>,
+    /// Parsed trees of the session's contents, reused across commands.
+    #[serde(skip)]
+    #[cfg_attr(not(feature = "tree-sitter"), allow(dead_code))]
+    pub trees: TreeCache,
+}
+
+#[cfg(test)]
+impl MergeSession {
+    /// A session for merging the given contents with the line-based merge.
+    pub fn for_test(base: &str, local: &str, remote: &str, language: &str) -> Self {
+        let (hunks, result_content) = super::three_way::three_way_merge(base, local, remote);
+        Self {
+            base_path: "base".to_string(),
+            local_path: "local".to_string(),
+            remote_path: "remote".to_string(),
+            merged_path: "merged".to_string(),
+            base_content: base.to_string(),
+            local_content: local.to_string(),
+            remote_content: remote.to_string(),
+            result_content,
+            hunks,
+            language: language.to_string(),
+            strategy: MergeStrategy::Text,
+            version_conflicts: Vec::new(),
+            renames: Vec::new(),
+            identifier_renames: Vec::new(),
+            normalized: false,
+            lockfile: None,
+            notebook: None,
+            trees: TreeCache::default(),
+        }
+    }
 }
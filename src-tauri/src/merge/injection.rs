@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, OnceLock};
+use parking_lot::Mutex;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Language, Query, QueryCursor, Tree};
+
+use super::profile::user_dir;
+use super::prose::fence_of;
+use crate::language::normalize_name;
+
+/// Injection queries for the bundled grammars, and for Markdown when its grammar
+/// is configured under `[grammars]`, keyed by language. Other hosts need a
+/// query in the user's profiles directory.
+const BUNDLED_INJECTIONS: &[(&str, &str)] = &[
+    ("html", include_str!("../../profiles/html.injections.scm")),
+    ("markdown", include_str!("../../profiles/markdown.injections.scm")),
+];
+
+/// A span of a file written in another language, like a `<script>` body or a
+/// fenced code block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Injection {
+    /// Grammar name of the embedded language.
+    pub language: String,
+    /// Byte range of the embedded code in the host file.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// The injection query for a host language: `<language>.injections.scm` from the
+/// user's profiles directory, otherwise the bundled one. `None` if there is none
+/// or it doesn't compile.
+fn injection_query(lang: &str, language: &Language) -> Option<Arc<Query>> {
+    static QUERIES: OnceLock<Mutex<HashMap<String, Option<Arc<Query>>>>> = OnceLock::new();
+    let mut queries = QUERIES.get_or_init(Default::default).lock();
+    if let Some(query) = queries.get(lang) {
+        return query.clone();
+    }
+
+    let file = format!("{}.injections.scm", lang);
+    let source = user_dir()
+        .and_then(|dir| fs::read_to_string(dir.join(&file)).ok())
+        .or_else(|| {
+            BUNDLED_INJECTIONS
+                .iter()
+                .find(|(name, _)| *name == lang)
+                .map(|(_, query)| query.to_string())
+        });
+    let query = source.and_then(|source| match Query::new(language, &source) {
+        Ok(query) => Some(Arc::new(query)),
+        Err(e) => {
+            eprintln!("Warning: Invalid injection query for {}: {}", lang, e);
+            None
+        }
+    });
+    queries.insert(lang.to_string(), query.clone());
+    query
+}
+
+/// Run a host language's injection query over a parsed file. The embedded
+/// language comes from an `@injection.language` capture or a
+/// `#set! injection.language` property; when several patterns capture the same
+/// node, the last one in the query wins.
+fn query_injections(lang: &str, language: &Language, tree: &Tree, source: &str) -> Vec<Injection> {
+    let Some(query) = injection_query(lang, language) else {
+        return Vec::new();
+    };
+    let capture_names = query.capture_names();
+    // Content node id -> (pattern index, injection)
+    let mut found: HashMap<usize, (usize, Injection)> = HashMap::new();
+
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+    while let Some(m) = matches.next() {
+        let mut content = None;
+        let mut name = query
+            .property_settings(m.pattern_index)
+            .iter()
+            .find(|p| &*p.key == "injection.language")
+            .and_then(|p| p.value.as_deref());
+        for capture in m.captures {
+            match capture_names[capture.index as usize] {
+                "injection.content" => content = Some(capture.node),
+                "injection.language" => name = Some(&source[capture.node.byte_range()]),
+                _ => {}
+            }
+        }
+        let (Some(content), Some(name)) = (content, name) else { continue };
+        let injection = Injection {
+            language: normalize_name(name),
+            start: content.start_byte(),
+            end: content.end_byte(),
+        };
+        match found.get(&content.id()) {
+            Some((pattern, _)) if *pattern > m.pattern_index => {}
+            _ => {
+                found.insert(content.id(), (m.pattern_index, injection));
+            }
+        }
+    }
+
+    let mut injections: Vec<Injection> = found.into_values().map(|(_, injection)| injection).collect();
+    injections.sort_by_key(|i| i.start);
+    injections
+}
+
+/// Fenced code blocks with a language in their info string, found line by line
+/// for Markdown without a grammar.
+fn fenced_injections(source: &str) -> Vec<Injection> {
+    let mut injections = Vec::new();
+    let mut open: Option<(&str, Option<String>, usize)> = None;
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        match &open {
+            None => {
+                if let Some(fence) = fence_of(line) {
+                    let info = line.trim_start()[fence.len()..].trim_start_matches(fence.chars().next().unwrap_or('`'));
+                    let name = info.split_whitespace().next().map(normalize_name);
+                    open = Some((fence, name, offset));
+                }
+            }
+            Some((fence, name, content_start)) => {
+                if line.trim_start().starts_with(fence) {
+                    if let Some(name) = name {
+                        injections.push(Injection {
+                            language: name.clone(),
+                            start: *content_start,
+                            end: start,
+                        });
+                    }
+                    open = None;
+                }
+            }
+        }
+    }
+    injections
+}
+
+/// The embedded regions of one side of a merge, from its host grammar's injection
+/// query, or from code fences for Markdown without a grammar.
+pub fn find_injections(lang: &str, parsed: Option<(&Language, &Tree)>, source: &str) -> Vec<Injection> {
+    match parsed {
+        Some((language, tree)) => query_injections(lang, language, tree, source),
+        None if lang == "markdown" => fenced_injections(source),
+        None => Vec::new(),
+    }
+}
+
+/// The host file with everything outside an embedded region blanked out, so the
+/// region parses on its own while keeping its line numbers and byte offsets.
+pub fn isolate(source: &str, injection: &Injection) -> String {
+    let mut isolated = String::with_capacity(source.len());
+    for (i, c) in source.char_indices() {
+        if c == '\n' || (injection.start..injection.end).contains(&i) {
+            isolated.push(c);
+        } else {
+            // One space per byte, so multibyte text doesn't shift the region
+            isolated.push_str(&" ".repeat(c.len_utf8()));
+        }
+    }
+    isolated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Markdown with text before its code that takes more bytes than characters.
+    const DOC: &str = "# Café ☕\n\nIntro.\n\n```rust\nfn a() -> i32 {\n    1\n}\n\nfn b() -> i32 {\n    2\n}\n```\n\nMiddle.\n\n```python\nx = 1\n```";
+
+    #[test]
+    fn finds_fenced_code_by_byte_offset() {
+        let injections = find_injections("markdown", None, DOC);
+        let languages: Vec<&str> = injections.iter().map(|i| i.language.as_str()).collect();
+        assert_eq!(languages, ["rust", "python"]);
+        assert_eq!(&DOC[injections[0].start..injections[0].end], "fn a() -> i32 {\n    1\n}\n\nfn b() -> i32 {\n    2\n}\n");
+        assert_eq!(&DOC[injections[1].start..injections[1].end], "x = 1\n");
+    }
+
+    #[test]
+    fn isolated_regions_keep_their_offsets() {
+        let injection = &find_injections("markdown", None, DOC)[0];
+        let isolated = isolate(DOC, injection);
+        assert_eq!(isolated.len(), DOC.len());
+        assert_eq!(isolated.lines().count(), DOC.lines().count());
+        assert_eq!(&isolated[injection.start..injection.end], &DOC[injection.start..injection.end]);
+        assert!(isolated[..injection.start].trim().is_empty());
+        assert!(isolated[injection.end..].trim().is_empty());
+    }
+
+    #[cfg(feature = "lang-rust")]
+    #[test]
+    fn conflicts_in_fenced_code_merge_with_its_grammar() {
+        use crate::config::DataConfig;
+        use crate::merge::hunk::{HunkStatus, MergeSession, MergeStrategy};
+        use crate::merge::prose::prose_merge;
+        use crate::merge::structural::apply_structural;
+
+        // Each side edits a different function of the Rust block, and local the Python block too
+        let local = DOC.replace("    1\n", "    10\n").replace("x = 1", "x = 2");
+        let remote = DOC.replace("    2\n", "    20\n");
+        let mut session = MergeSession::for_test(DOC, &local, &remote, "markdown");
+        session.strategy = MergeStrategy::Prose;
+        prose_merge(&mut session);
+        let unresolved = || session.hunks.iter().filter(|h| h.status == HunkStatus::Unresolved).count();
+        assert_eq!(unresolved(), 1);
+
+        assert_eq!(apply_structural(&mut session, None, &DataConfig::default()), 1);
+        assert_eq!(
+            session.result_content,
+            DOC.replace("    1\n", "    10\n").replace("    2\n", "    20\n").replace("x = 1", "x = 2")
+        );
+    }
+}
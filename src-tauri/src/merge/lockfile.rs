@@ -0,0 +1,839 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use super::hunk::{HunkSource, HunkStatus, LineRange, MergeHunk, MergeSession};
+use super::resolver::auto_resolve_conflicts;
+use super::three_way::split_lines;
+use super::version::compare_versions;
+use crate::config::LockfileConfig;
+
+/// Package fields in the order Cargo writes them; any others follow sorted.
+const CARGO_FIELDS: &[&str] = &["name", "version", "source", "checksum", "dependencies"];
+
+/// npm objects whose keys npm keeps sorted.
+const NPM_SORTED: &[&str] = &[
+    "packages",
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+    "requires",
+];
+
+/// pnpm maps whose keys pnpm keeps sorted, besides the top-level sections.
+const PNPM_SORTED: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+    "peerDependenciesMeta",
+];
+
+/// Lockfile formats merged package by package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockfileKind {
+    /// `Cargo.lock`
+    Cargo,
+    /// `package-lock.json` and `npm-shrinkwrap.json`
+    Npm,
+    /// `pnpm-lock.yaml`
+    Pnpm,
+}
+
+impl LockfileKind {
+    /// The command that rebuilds this lockfile from the manifests.
+    pub fn regenerate_command(self) -> &'static str {
+        match self {
+            LockfileKind::Cargo => "cargo update --workspace",
+            LockfileKind::Npm => "npm install --package-lock-only",
+            LockfileKind::Pnpm => "pnpm install --lockfile-only",
+        }
+    }
+}
+
+/// What the lockfile merge did, shown in the status bar.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockfileReport {
+    /// Packages both sides changed to different versions, resolved to the higher one.
+    pub warnings: Vec<String>,
+    /// Set when configured to regenerate instead of merging: the command to run
+    /// once the conflicts are resolved with local's lockfile.
+    pub regenerate: Option<String>,
+}
+
+/// The lockfile format of a path, from its file name.
+pub fn lockfile_kind(path: &str) -> Option<LockfileKind> {
+    match Path::new(path).file_name()?.to_str()? {
+        "Cargo.lock" => Some(LockfileKind::Cargo),
+        "package-lock.json" | "npm-shrinkwrap.json" => Some(LockfileKind::Npm),
+        "pnpm-lock.yaml" => Some(LockfileKind::Pnpm),
+        _ => None,
+    }
+}
+
+/// A parsed lockfile: maps of entries down to leaf values kept as text.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Leaf(String),
+    Map(Table),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Table {
+    entries: Vec<(String, Node)>,
+    /// Keys are written in sorted order.
+    sorted: bool,
+    /// Entries are packages: the same package at two versions is an upgrade,
+    /// and entries with a `version` field are kept or replaced whole.
+    packages: bool,
+    /// Entries are separated by blank lines (pnpm).
+    spaced: bool,
+}
+
+impl Table {
+    fn get(&self, key: &str) -> Option<&Node> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, n)| n)
+    }
+
+    fn version(&self) -> Option<&str> {
+        match self.get("version")? {
+            Node::Leaf(text) => leaf_version(text),
+            Node::Map(_) => None,
+        }
+    }
+}
+
+/// The version in a leaf's value: `"1.2.3"`, `"^1.2.3"`, `bar: 1.2.3(react@18.0.0)`.
+fn leaf_version(text: &str) -> Option<&str> {
+    let value = text.split_once(": ").map_or(text, |(_, v)| v);
+    let start = value.find(|c: char| c.is_ascii_digit())?;
+    let rest = &value[start..];
+    let end = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+')))
+        .unwrap_or(rest.len());
+    Some(&rest[..end])
+}
+
+/// Compare keys with digit runs compared as numbers, so `foo 0.10.0` sorts after `foo 0.9.0`.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(x), Some(y)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let a_end = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+            let b_end = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+            let (a_num, b_num) = (a[..a_end].trim_start_matches('0'), b[..b_end].trim_start_matches('0'));
+            let ordering = a_num.len().cmp(&b_num.len()).then(a_num.cmp(b_num));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (&a[a_end..], &b[b_end..]);
+        } else {
+            if x != y {
+                return x.cmp(&y);
+            }
+            (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+        }
+    }
+}
+
+/// Package name and version from a package entry's key, where the key has them.
+fn package_id(kind: LockfileKind, key: &str) -> Option<(&str, &str)> {
+    match kind {
+        // `name version` or `name version source`
+        LockfileKind::Cargo => {
+            let mut words = key.split(' ');
+            Some((words.next()?, words.next()?))
+        }
+        // `foo@1.2.3`, `'@scope/foo@1.2.3(react@18.0.0)'`, `/foo@1.2.3` or `/foo/1.2.3`
+        LockfileKind::Pnpm => {
+            let key = key.trim_matches(|c| c == '\'' || c == '"');
+            let key = key.split('(').next()?;
+            let key = key.strip_prefix('/').unwrap_or(key);
+            let (name, version) = match key.rsplit_once('@') {
+                Some((name, version)) if !name.is_empty() => (name, version),
+                _ => key.rsplit_once('/')?,
+            };
+            Some((name, version))
+        }
+        LockfileKind::Npm => None,
+    }
+}
+
+fn package_version(kind: LockfileKind, key: &str) -> &str {
+    package_id(kind, key).map_or("", |(_, version)| version)
+}
+
+struct Merger {
+    kind: LockfileKind,
+    warnings: Vec<String>,
+    /// Package name, dropped version and the version kept instead.
+    replaced: Vec<(String, String, String)>,
+}
+
+impl Merger {
+    fn merge_node(&mut self, path: &str, base: Option<&Node>, local: &Node, remote: &Node, package: bool) -> Node {
+        if local == remote || base == Some(remote) {
+            return local.clone();
+        }
+        if base == Some(local) {
+            return remote.clone();
+        }
+        match (local, remote) {
+            (Node::Map(l), Node::Map(r)) => {
+                // A package entry both sides moved to different versions is taken whole
+                if package && l.version().is_some() && r.version().is_some() && l.version() != r.version() {
+                    return self.pick(path, local, remote);
+                }
+                let b = match base {
+                    Some(Node::Map(b)) => Some(b),
+                    _ => None,
+                };
+                Node::Map(self.merge_table(path, b, l, r))
+            }
+            _ => self.pick(path, local, remote),
+        }
+    }
+
+    /// Both sides changed a value differently: keep the one with the higher version.
+    fn pick(&mut self, path: &str, local: &Node, remote: &Node) -> Node {
+        let version = |node: &Node| match node {
+            Node::Leaf(text) => leaf_version(text).map(str::to_string),
+            Node::Map(table) => table.version().map(str::to_string),
+        };
+        match (version(local), version(remote)) {
+            (Some(l), Some(r)) => {
+                let remote_higher = compare_versions(&r, &l) == Ordering::Greater;
+                let kept = if remote_higher { &r } else { &l };
+                self.warnings.push(format!("{}: local has {}, remote has {}; kept {}", path, l, r, kept));
+                if remote_higher { remote.clone() } else { local.clone() }
+            }
+            _ => {
+                self.warnings.push(format!("{}: both sides changed it; kept local", path));
+                local.clone()
+            }
+        }
+    }
+
+    fn merge_table(&mut self, path: &str, base: Option<&Table>, local: &Table, remote: &Table) -> Table {
+        let child = |key: &str| {
+            // npm's root package is keyed by the empty string
+            let key = if key.is_empty() { "\"\"" } else { key };
+            if path.is_empty() { key.to_string() } else { format!("{} > {}", path, key) }
+        };
+        let base_of = |key: &str| base.and_then(|b| b.get(key));
+
+        // Local's entries in order, then remote's additions. An entry one side
+        // deleted stays if the other side changed it.
+        let mut entries = Vec::new();
+        for (key, l) in &local.entries {
+            match (remote.get(key), base_of(key)) {
+                (Some(r), b) => {
+                    let merged = self.merge_node(&child(key), b, l, r, local.packages);
+                    entries.push((key.clone(), merged));
+                }
+                (None, Some(b)) if b == l => {}
+                (None, _) => entries.push((key.clone(), l.clone())),
+            }
+        }
+        for (key, r) in &remote.entries {
+            if local.get(key).is_some() || base_of(key) == Some(r) {
+                continue;
+            }
+            entries.push((key.clone(), r.clone()));
+        }
+
+        if local.packages {
+            self.collapse_upgrades(base, local, remote, &mut entries);
+        }
+        if local.sorted {
+            entries.sort_by(|(a, _), (b, _)| natural_cmp(a, b));
+        }
+        Table { entries, ..*local }
+    }
+
+    /// Where both sides replaced the same package with different versions, keep
+    /// only the higher one.
+    fn collapse_upgrades(
+        &mut self,
+        base: Option<&Table>,
+        local: &Table,
+        remote: &Table,
+        entries: &mut Vec<(String, Node)>,
+    ) {
+        let kind = self.kind;
+        let in_base = |key: &str| base.is_some_and(|b| b.get(key).is_some());
+
+        // Package name -> keys only local added, keys only remote added
+        let mut added: BTreeMap<&str, (Vec<&str>, Vec<&str>)> = BTreeMap::new();
+        for (key, _) in entries.iter() {
+            let Some((name, _)) = package_id(kind, key) else { continue };
+            if in_base(key) {
+                continue;
+            }
+            match (local.get(key).is_some(), remote.get(key).is_some()) {
+                (true, false) => added.entry(name).or_default().0.push(key),
+                (false, true) => added.entry(name).or_default().1.push(key),
+                _ => {}
+            }
+        }
+
+        let mut dropped: Vec<String> = Vec::new();
+        for (name, (local_keys, remote_keys)) in added {
+            let replaced_in_both = base.is_some_and(|b| {
+                b.entries.iter().any(|(key, _)| {
+                    package_id(kind, key).is_some_and(|(n, _)| n == name)
+                        && local.get(key).is_none()
+                        && remote.get(key).is_none()
+                })
+            });
+            if local_keys.is_empty() || remote_keys.is_empty() || !replaced_in_both {
+                continue;
+            }
+            let version = |key| package_version(kind, key);
+            let candidates: Vec<&str> = local_keys.iter().chain(&remote_keys).copied().collect();
+            let Some(kept) = candidates
+                .iter()
+                .copied()
+                .max_by(|a, b| compare_versions(version(a), version(b)))
+            else {
+                continue;
+            };
+            let (local_versions, remote_versions) = (
+                local_keys.iter().map(|k| version(k)).collect::<Vec<_>>().join(", "),
+                remote_keys.iter().map(|k| version(k)).collect::<Vec<_>>().join(", "),
+            );
+            self.warnings.push(format!(
+                "{}: local has {}, remote has {}; kept {}",
+                name,
+                local_versions,
+                remote_versions,
+                version(kept)
+            ));
+            for key in candidates.into_iter().filter(|k| *k != kept) {
+                self.replaced.push((name.to_string(), version(key).to_string(), version(kept).to_string()));
+                dropped.push(key.to_string());
+            }
+        }
+        entries.retain(|(key, _)| !dropped.contains(key));
+    }
+}
+
+/// Cargo.lock: `version`, `[[package]]` entries keyed by name, version and
+/// source, and the old `[metadata]` checksum table.
+mod cargo {
+    use std::collections::HashMap;
+
+    use super::{natural_cmp, Node, Table, CARGO_FIELDS};
+
+    fn quoted(text: &str) -> String {
+        toml::Value::String(text.to_string()).to_string()
+    }
+
+    pub fn parse(content: &str) -> Option<Node> {
+        let doc: toml::Table = content.parse().ok()?;
+        let mut root = Table::default();
+        for (key, value) in doc {
+            let node = match key.as_str() {
+                "version" => Node::Leaf(value.to_string()),
+                "package" => {
+                    let mut packages = Table { sorted: true, packages: true, ..Table::default() };
+                    for package in value.as_array()? {
+                        let package = package.as_table()?;
+                        let id = ["name", "version", "source"]
+                            .iter()
+                            .filter_map(|f| package.get(*f)?.as_str())
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        let mut fields = Table::default();
+                        for (field, value) in package {
+                            let node = match (field.as_str(), value.as_array()) {
+                                ("dependencies", Some(deps)) => {
+                                    let entries = deps
+                                        .iter()
+                                        .map(|d| Some((d.as_str()?.to_string(), Node::Leaf(String::new()))))
+                                        .collect::<Option<Vec<_>>>()?;
+                                    Node::Map(Table { entries, sorted: true, ..Table::default() })
+                                }
+                                _ => Node::Leaf(value.to_string()),
+                            };
+                            fields.entries.push((field.clone(), node));
+                        }
+                        packages.entries.push((id, Node::Map(fields)));
+                    }
+                    Node::Map(packages)
+                }
+                "metadata" => {
+                    let entries = value
+                        .as_table()?
+                        .iter()
+                        .map(|(k, v)| (k.clone(), Node::Leaf(v.to_string())))
+                        .collect();
+                    Node::Map(Table { entries, sorted: true, ..Table::default() })
+                }
+                // Anything else (`[patch.unused]`, ...) is left to the line merge
+                _ => return None,
+            };
+            root.entries.push((key, node));
+        }
+        Some(Node::Map(root))
+    }
+
+    /// Point dependencies on dropped versions at the kept ones, and drop the
+    /// version from dependencies on a package that now has only one.
+    pub fn rewrite(root: &mut Table, replaced: &[(String, String, String)]) {
+        let Some((_, Node::Map(packages))) = root.entries.iter_mut().find(|(k, _)| k == "package") else {
+            return;
+        };
+        let mut counts = HashMap::new();
+        for (key, _) in &packages.entries {
+            *counts.entry(key.split(' ').next().unwrap_or_default().to_string()).or_insert(0) += 1;
+        }
+        for (_, package) in packages.entries.iter_mut() {
+            let Node::Map(fields) = package else { continue };
+            let Some((_, Node::Map(deps))) = fields.entries.iter_mut().find(|(k, _)| k == "dependencies") else {
+                continue;
+            };
+            for (dep, _) in deps.entries.iter_mut() {
+                let mut words = dep.splitn(3, ' ');
+                let name = words.next().unwrap_or_default().to_string();
+                let version = words.next();
+                if counts.get(&name) == Some(&1) {
+                    *dep = name;
+                } else if let Some((_, _, kept)) =
+                    replaced.iter().find(|(n, dropped, _)| *n == name && Some(dropped.as_str()) == version)
+                {
+                    *dep = format!("{} {}", name, kept);
+                }
+            }
+            deps.entries.sort_by(|(a, _), (b, _)| natural_cmp(a, b));
+            deps.entries.dedup_by(|(a, _), (b, _)| a == b);
+        }
+    }
+
+    pub fn write(root: &Table, local: &str) -> String {
+        let mut out: Vec<String> = local
+            .lines()
+            .take_while(|l| l.starts_with('#'))
+            .map(str::to_string)
+            .collect();
+        if let Some(Node::Leaf(version)) = root.get("version") {
+            out.push(format!("version = {}", version));
+        }
+        if let Some(Node::Map(packages)) = root.get("package") {
+            for (_, package) in &packages.entries {
+                let Node::Map(fields) = package else { continue };
+                out.push(String::new());
+                out.push("[[package]]".to_string());
+                let mut ordered: Vec<&(String, Node)> = fields.entries.iter().collect();
+                ordered.sort_by_key(|(k, _)| {
+                    (CARGO_FIELDS.iter().position(|f| f == k).unwrap_or(CARGO_FIELDS.len()), k.clone())
+                });
+                for (field, node) in ordered {
+                    match node {
+                        Node::Leaf(value) => out.push(format!("{} = {}", field, value)),
+                        Node::Map(deps) => {
+                            out.push(format!("{} = [", field));
+                            out.extend(deps.entries.iter().map(|(d, _)| format!(" {},", quoted(d))));
+                            out.push("]".to_string());
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(Node::Map(metadata)) = root.get("metadata") {
+            out.push(String::new());
+            out.push("[metadata]".to_string());
+            for (key, node) in &metadata.entries {
+                if let Node::Leaf(value) = node {
+                    out.push(format!("{} = {}", quoted(key), value));
+                }
+            }
+        }
+        out.join("\n")
+    }
+}
+
+/// package-lock.json: JSON objects, with `packages` (and v1's nested
+/// `dependencies`) keyed by install path.
+mod npm {
+    use serde_json::{Map, Value};
+
+    use super::{Node, Table, NPM_SORTED};
+
+    fn to_node(value: Value, key: &str) -> Node {
+        match value {
+            Value::Object(object) => {
+                let sorted = NPM_SORTED.contains(&key);
+                let entries = object.into_iter().map(|(k, v)| {
+                    let node = to_node(v, &k);
+                    (k, node)
+                });
+                Node::Map(Table {
+                    entries: entries.collect(),
+                    sorted,
+                    packages: key == "packages" || key == "dependencies",
+                    spaced: false,
+                })
+            }
+            other => Node::Leaf(other.to_string()),
+        }
+    }
+
+    fn to_value(node: &Node) -> Option<Value> {
+        match node {
+            Node::Leaf(text) => serde_json::from_str(text).ok(),
+            Node::Map(table) => {
+                let mut object = Map::new();
+                for (key, node) in &table.entries {
+                    object.insert(key.clone(), to_value(node)?);
+                }
+                Some(Value::Object(object))
+            }
+        }
+    }
+
+    pub fn parse(content: &str) -> Option<Node> {
+        let value: Value = serde_json::from_str(content).ok()?;
+        value.is_object().then(|| to_node(value, ""))
+    }
+
+    pub fn write(root: &Table) -> Option<String> {
+        serde_json::to_string_pretty(&to_value(&Node::Map(root.clone()))?).ok()
+    }
+}
+
+/// pnpm-lock.yaml: block mappings by indentation, one key per line, with
+/// `importers`, `packages` and `snapshots` entries separated by blank lines.
+mod pnpm {
+    use super::{Node, Table, PNPM_SORTED};
+
+    fn indent_of(line: &str) -> usize {
+        line.len() - line.trim_start().len()
+    }
+
+    fn key_of(line: &str) -> &str {
+        line.split_once(": ").map_or(line, |(k, _)| k)
+    }
+
+    fn parse_table(lines: &[&str], i: &mut usize, indent: usize, key: &str, depth: usize) -> Option<Table> {
+        let mut table = Table {
+            sorted: depth == 1 || PNPM_SORTED.contains(&key),
+            packages: depth == 1 && (key == "packages" || key == "snapshots"),
+            ..Table::default()
+        };
+        let mut blank = false;
+        while *i < lines.len() {
+            let line = lines[*i];
+            if line.trim().is_empty() {
+                blank = true;
+                *i += 1;
+                continue;
+            }
+            let line_indent = indent_of(line);
+            if line_indent < indent {
+                break;
+            }
+            if line_indent > indent {
+                // Multi-line scalars and the like aren't merged
+                return None;
+            }
+            table.spaced |= blank;
+            blank = false;
+            let text = line.trim();
+            *i += 1;
+
+            let next_indent = lines[*i..].iter().find(|l| !l.trim().is_empty()).map(|l| indent_of(l));
+            match text.strip_suffix(':') {
+                Some(child_key) if next_indent.is_some_and(|n| n > indent) => {
+                    let child = parse_table(lines, i, next_indent?, child_key, depth + 1)?;
+                    table.entries.push((child_key.to_string(), Node::Map(child)));
+                }
+                _ => table.entries.push((key_of(text).to_string(), Node::Leaf(text.to_string()))),
+            }
+        }
+        Some(table)
+    }
+
+    pub fn parse(content: &str) -> Option<Node> {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut i = 0;
+        let root = parse_table(&lines, &mut i, 0, "", 0)?;
+        (i == lines.len()).then_some(Node::Map(root))
+    }
+
+    /// Point references to dropped versions (`bar: 1.0.0` in a snapshot,
+    /// `version: 1.0.0` under an importer's `bar:`) at the kept ones.
+    pub fn rewrite(table: &mut Table, key: &str, replaced: &[(String, String, String)]) {
+        for (child_key, node) in table.entries.iter_mut() {
+            match node {
+                Node::Map(child) => rewrite(child, child_key, replaced),
+                Node::Leaf(text) => {
+                    let name = if child_key == "version" { key } else { child_key.as_str() };
+                    let name = name.trim_matches(|c| c == '\'' || c == '"');
+                    let Some((_, value)) = text.split_once(": ") else { continue };
+                    let found = replaced.iter().find(|(n, dropped, _)| {
+                        n == name && value.strip_prefix(dropped.as_str()).is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '.'))
+                    });
+                    if let Some((_, dropped, kept)) = found {
+                        *text = format!("{}: {}{}", child_key, kept, &value[dropped.len()..]);
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_table(table: &Table, indent: usize, root: bool, out: &mut Vec<String>) {
+        let pad = " ".repeat(indent);
+        for (i, (key, node)) in table.entries.iter().enumerate() {
+            if table.spaced && !(root && i == 0) {
+                out.push(String::new());
+            }
+            match node {
+                Node::Leaf(text) => out.push(format!("{}{}", pad, text)),
+                Node::Map(child) => {
+                    out.push(format!("{}{}:", pad, key));
+                    write_table(child, indent + 2, false, out);
+                }
+            }
+        }
+    }
+
+    pub fn write(root: &Table) -> String {
+        let mut out = Vec::new();
+        write_table(root, 0, true, &mut out);
+        out.join("\n")
+    }
+}
+
+/// Merge three versions of a lockfile package by package. Entries either side
+/// added are kept and entries either side removed are dropped; where both
+/// sides changed the same package differently the higher version wins, with a
+/// warning. Entries are written sorted. Returns the merged text and the
+/// warnings, or `None` if a side can't be parsed.
+pub fn merge_lockfile(kind: LockfileKind, base: &str, local: &str, remote: &str) -> Option<(String, Vec<String>)> {
+    let parse = |content: &str| match kind {
+        LockfileKind::Cargo => cargo::parse(content),
+        LockfileKind::Npm => npm::parse(content),
+        LockfileKind::Pnpm => pnpm::parse(content),
+    };
+    let (base_root, local_root, remote_root) = (parse(base)?, parse(local)?, parse(remote)?);
+
+    let mut merger = Merger { kind, warnings: Vec::new(), replaced: Vec::new() };
+    let Node::Map(mut merged) = merger.merge_node("", Some(&base_root), &local_root, &remote_root, false) else {
+        return None;
+    };
+    let content = match kind {
+        LockfileKind::Cargo => {
+            cargo::rewrite(&mut merged, &merger.replaced);
+            cargo::write(&merged, local)
+        }
+        LockfileKind::Npm => npm::write(&merged)?,
+        LockfileKind::Pnpm => {
+            pnpm::rewrite(&mut merged, "", &merger.replaced);
+            pnpm::write(&merged)
+        }
+    };
+    let mut warnings: Vec<String> = Vec::new();
+    for warning in merger.warnings {
+        // pnpm lists a package under both `packages` and `snapshots`
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+    Some((content, warnings))
+}
+
+/// A line range covering all of `content`.
+fn whole(content: &str) -> LineRange {
+    match split_lines(content).len() {
+        0 => LineRange::empty(),
+        n => LineRange::new(1, n),
+    }
+}
+
+/// Resolve a lockfile's conflicts. By default the whole file is merged package
+/// by package and replaces the line merge's hunks with a single resolved one;
+/// when configured to regenerate, conflicts take local's side and the report
+/// names the command that rebuilds the lockfile. Returns the number of
+/// conflicts resolved.
+pub fn lockfile_auto_resolve(session: &mut MergeSession, config: &LockfileConfig) -> usize {
+    let Some(kind) = lockfile_kind(&session.merged_path) else {
+        return 0;
+    };
+    let conflicts = session
+        .hunks
+        .iter()
+        .filter(|h| h.source == HunkSource::Conflict && h.status == HunkStatus::Unresolved)
+        .count();
+    if conflicts == 0 {
+        return 0;
+    }
+
+    if config.regenerate {
+        session.lockfile = Some(LockfileReport {
+            warnings: Vec::new(),
+            regenerate: Some(kind.regenerate_command().to_string()),
+        });
+        return auto_resolve_conflicts(session, |_, hunk| Some(hunk.local_content.clone()));
+    }
+
+    let Some((content, warnings)) =
+        merge_lockfile(kind, &session.base_content, &session.local_content, &session.remote_content)
+    else {
+        eprintln!("Warning: Could not parse {} as a lockfile, leaving the line merge", session.merged_path);
+        return 0;
+    };
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    session.hunks = vec![MergeHunk {
+        id: 0,
+        base_range: whole(&session.base_content),
+        local_range: whole(&session.local_content),
+        remote_range: whole(&session.remote_content),
+        result_range: whole(&content),
+        source: HunkSource::Conflict,
+        status: HunkStatus::AutoResolved,
+        local_content: session.local_content.clone(),
+        remote_content: session.remote_content.clone(),
+        base_content: session.base_content.clone(),
+    }];
+    session.result_content = content;
+    session.version_conflicts.clear();
+    session.lockfile = Some(LockfileReport { warnings, regenerate: None });
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO_HEADER: &str =
+        "# This file is automatically @generated by Cargo.\n# It is not intended for manual editing.\nversion = 4\n";
+
+    fn cargo_lock(packages: &[String]) -> String {
+        let mut lock = CARGO_HEADER.to_string();
+        for package in packages {
+            lock.push('\n');
+            lock.push_str(package);
+            lock.push('\n');
+        }
+        lock
+    }
+
+    fn crate_package(name: &str, version: &str) -> String {
+        format!(
+            "[[package]]\nname = \"{name}\"\nversion = \"{version}\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\nchecksum = \"{name}-{version}\""
+        )
+    }
+
+    fn app_package(deps: &[&str]) -> String {
+        let deps: String = deps.iter().map(|d| format!(" \"{d}\",\n")).collect();
+        format!("[[package]]\nname = \"app\"\nversion = \"0.1.0\"\ndependencies = [\n{deps}]")
+    }
+
+    /// Merge both ways round, checking the result doesn't depend on which side
+    /// is which.
+    fn merge(kind: LockfileKind, base: &str, local: &str, remote: &str) -> (String, Vec<String>) {
+        let merged = merge_lockfile(kind, base, local, remote).expect("lockfiles parse");
+        let swapped = merge_lockfile(kind, base, remote, local).expect("lockfiles parse");
+        assert_eq!(merged.0, swapped.0);
+        merged
+    }
+
+    #[test]
+    fn cargo_lock_merges_package_by_package() {
+        let base = cargo_lock(&[app_package(&["serde"]), crate_package("serde", "1.0.100")]);
+        let local = cargo_lock(&[
+            app_package(&["serde", "zeta"]),
+            crate_package("serde", "1.0.150"),
+            crate_package("zeta", "0.1.0"),
+        ]);
+        let remote = cargo_lock(&[
+            app_package(&["anyhow", "serde"]),
+            crate_package("anyhow", "1.0.0"),
+            crate_package("serde", "1.0.160"),
+        ]);
+
+        let (content, warnings) = merge(LockfileKind::Cargo, &base, &local, &remote);
+        let expected = cargo_lock(&[
+            crate_package("anyhow", "1.0.0"),
+            app_package(&["anyhow", "serde", "zeta"]),
+            crate_package("serde", "1.0.160"),
+            crate_package("zeta", "0.1.0"),
+        ]);
+        assert_eq!(content, expected.trim_end());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("local has 1.0.150, remote has 1.0.160; kept 1.0.160"));
+    }
+
+    #[test]
+    fn package_lock_merges_package_by_package() {
+        let lock = |deps: &str, packages: &str| {
+            format!(
+                "{{\n  \"name\": \"app\",\n  \"lockfileVersion\": 3,\n  \"packages\": {{\n    \"\": {{\n      \"dependencies\": {{\n{deps}\n      }}\n    }},\n{packages}\n  }}\n}}"
+            )
+        };
+        let package = |name: &str, version: &str| {
+            format!(
+                "    \"node_modules/{name}\": {{\n      \"version\": \"{version}\",\n      \"resolved\": \"https://r/{name}-{version}.tgz\"\n    }}"
+            )
+        };
+        let base = lock("        \"a\": \"^1.0.0\"", &package("a", "1.0.0"));
+        let local = lock(
+            "        \"a\": \"^1.0.0\",\n        \"z\": \"^2.0.0\"",
+            &[package("a", "1.1.0"), package("z", "2.0.0")].join(",\n"),
+        );
+        let remote = lock(
+            "        \"a\": \"^1.0.0\",\n        \"b\": \"^3.0.0\"",
+            &[package("a", "1.2.0"), package("b", "3.0.0")].join(",\n"),
+        );
+
+        let (content, warnings) = merge(LockfileKind::Npm, &base, &local, &remote);
+        let expected = lock(
+            "        \"a\": \"^1.0.0\",\n        \"b\": \"^3.0.0\",\n        \"z\": \"^2.0.0\"",
+            &[package("a", "1.2.0"), package("b", "3.0.0"), package("z", "2.0.0")].join(",\n"),
+        );
+        assert_eq!(content, expected);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("local has 1.1.0, remote has 1.2.0; kept 1.2.0"));
+    }
+
+    #[test]
+    fn pnpm_lock_merges_package_by_package() {
+        let lock = |importers: &[(&str, &str)], packages: &[(&str, &str)]| {
+            let mut lock = "lockfileVersion: '9.0'\n\nimporters:\n\n  .:\n    dependencies:\n".to_string();
+            for (name, version) in importers {
+                lock.push_str(&format!("      {name}:\n        specifier: ^{version}\n        version: {version}\n"));
+            }
+            lock.push_str("\npackages:\n");
+            for (name, version) in packages {
+                lock.push_str(&format!("\n  {name}@{version}:\n    resolution: {{integrity: sha-{name}-{version}}}\n"));
+            }
+            lock.push_str("\nsnapshots:\n");
+            for (name, version) in packages {
+                lock.push_str(&format!("\n  {name}@{version}: {{}}\n"));
+            }
+            lock
+        };
+        let base = lock(&[("a", "1.0.0")], &[("a", "1.0.0")]);
+        let local = lock(&[("a", "1.1.0"), ("z", "2.0.0")], &[("a", "1.1.0"), ("z", "2.0.0")]);
+        let remote = lock(&[("a", "1.2.0"), ("b", "3.0.0")], &[("a", "1.2.0"), ("b", "3.0.0")]);
+
+        let (content, warnings) = merge(LockfileKind::Pnpm, &base, &local, &remote);
+        let expected = lock(
+            &[("a", "1.2.0"), ("b", "3.0.0"), ("z", "2.0.0")],
+            &[("a", "1.2.0"), ("b", "3.0.0"), ("z", "2.0.0")],
+        );
+        assert_eq!(content, expected.trim_end());
+        assert!(!warnings.is_empty());
+        assert!(warnings.iter().all(|w| w.contains("kept 1.2.0")));
+    }
+}
@@ -0,0 +1,686 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::data::{apply_merge, DataMerge, Edit, Span};
+use super::hunk::MergeSession;
+use super::structural::{NodeOutcome, RefusalReason};
+
+/// Attributes that identify an element among its siblings, in order of
+/// preference. Matched by local name, so `android:name` counts.
+const KEY_ATTRIBUTES: &[&str] = &["id", "name", "key"];
+
+/// HTML elements that have no content or end tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// HTML elements whose content is raw text rather than markup.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Whether a language is markup the element merge handles.
+pub fn is_markup_language(language: &str) -> bool {
+    matches!(language, "xml" | "html")
+}
+
+#[derive(Debug, Clone)]
+struct Attribute {
+    name: String,
+    value: String,
+    /// `name="value"` in the source.
+    span: Range<usize>,
+}
+
+/// An element, or a comment, processing instruction or doctype (with a `tag`
+/// starting with `#`), and where its parts are in the source.
+#[derive(Debug, Clone)]
+struct Element {
+    tag: String,
+    /// Unique among its siblings: the tag plus an identifying attribute or,
+    /// for Maven-style entries, the `groupId` and `artifactId` children.
+    id: String,
+    attributes: Vec<Attribute>,
+    /// End of the tag name, where new attributes go.
+    name_end: usize,
+    /// The start tag.
+    open: Range<usize>,
+    /// Between the start and end tags, if it has both.
+    inner: Option<Range<usize>>,
+    span: Range<usize>,
+    children: Vec<Element>,
+    /// Whether text sits directly inside it, so its content is merged whole.
+    text: bool,
+}
+
+impl Element {
+    fn leaf(tag: &str, span: Range<usize>, text: bool) -> Self {
+        Element {
+            tag: tag.to_string(),
+            id: String::new(),
+            attributes: Vec::new(),
+            name_end: span.start,
+            open: span.clone(),
+            inner: None,
+            span,
+            children: Vec::new(),
+            text,
+        }
+    }
+
+    /// The element's name in paths.
+    fn name(&self) -> &str {
+        self.tag.strip_prefix('#').unwrap_or(&self.id)
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+    html: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Move past the next `end`, returning the span from the current position.
+    fn skip_past(&mut self, end: &str) -> Option<Range<usize>> {
+        let start = self.pos;
+        self.pos += self.rest().find(end)? + end.len();
+        Some(start..self.pos)
+    }
+
+    fn same_tag(&self, a: &str, b: &str) -> bool {
+        if self.html {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    }
+
+    /// Parse nodes up to the end tag of `parent`, or to the end of the source
+    /// for the document. Returns the child nodes and whether there was text
+    /// among them.
+    fn content(&mut self, parent: Option<&str>) -> Option<(Vec<Element>, bool)> {
+        let mut children = Vec::new();
+        let mut text = false;
+        loop {
+            let Some(lt) = self.rest().find('<') else {
+                text |= !self.rest().trim().is_empty();
+                self.pos = self.source.len();
+                return parent.is_none().then_some((children, text));
+            };
+            text |= !self.rest()[..lt].trim().is_empty();
+            self.pos += lt;
+            let rest = self.rest();
+            if rest.starts_with("<!--") {
+                children.push(Element::leaf("#comment", self.skip_past("-->")?, false));
+            } else if rest.starts_with("<![CDATA[") {
+                self.skip_past("]]>")?;
+                text = true;
+            } else if rest.starts_with("<!") {
+                children.push(Element::leaf("#doctype", self.skip_past(">")?, false));
+            } else if rest.starts_with("<?") {
+                children.push(Element::leaf("#pi", self.skip_past("?>")?, false));
+            } else if let Some(close) = rest.strip_prefix("</") {
+                let name = &close[..close.find(|c: char| c.is_whitespace() || c == '>')?];
+                return parent.filter(|p| self.same_tag(p, name)).map(|_| (children, text));
+            } else {
+                children.push(self.element()?);
+            }
+        }
+    }
+
+    fn element(&mut self) -> Option<Element> {
+        let start = self.pos;
+        self.pos += 1;
+        let rest = self.rest();
+        let name_len = rest.find(|c: char| c.is_whitespace() || c == '/' || c == '>')?;
+        if name_len == 0 {
+            return None;
+        }
+        let tag = rest[..name_len].to_string();
+        self.pos += name_len;
+        let name_end = self.pos;
+
+        let mut attributes = Vec::new();
+        let self_closing = loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                break true;
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                break false;
+            }
+            let attr_start = self.pos;
+            let len = rest.find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')?;
+            if len == 0 {
+                return None;
+            }
+            let name = rest[..len].to_string();
+            self.pos += len;
+            let name_end = self.pos;
+            self.skip_whitespace();
+            let value = if self.rest().starts_with('=') {
+                self.pos += 1;
+                self.skip_whitespace();
+                let rest = self.rest();
+                match rest.chars().next()? {
+                    q @ ('"' | '\'') => {
+                        let len = rest[1..].find(q)?;
+                        self.pos += len + 2;
+                        rest[1..len + 1].to_string()
+                    }
+                    _ => {
+                        let len = rest.find(|c: char| c.is_whitespace() || c == '>')?;
+                        self.pos += len;
+                        rest[..len].to_string()
+                    }
+                }
+            } else {
+                // A bare attribute, as HTML allows
+                self.pos = name_end;
+                String::new()
+            };
+            attributes.push(Attribute { name, value, span: attr_start..self.pos });
+        };
+        let open = start..self.pos;
+
+        let lower = tag.to_ascii_lowercase();
+        let void = self.html && VOID_ELEMENTS.contains(&lower.as_str());
+        let (inner, children, text) = if self_closing || void {
+            (None, Vec::new(), false)
+        } else {
+            let inner_start = self.pos;
+            let (children, text) = if self.html && RAW_TEXT_ELEMENTS.contains(&lower.as_str()) {
+                self.pos += self.rest().to_ascii_lowercase().find(&format!("</{}", lower))?;
+                (Vec::new(), true)
+            } else {
+                self.content(Some(&tag))?
+            };
+            let inner = inner_start..self.pos;
+            self.skip_past(">")?;
+            (Some(inner), children, text)
+        };
+
+        Some(Element {
+            tag,
+            id: String::new(),
+            attributes,
+            name_end,
+            open,
+            inner,
+            span: start..self.pos,
+            children,
+            text,
+        })
+    }
+}
+
+/// Give each element an id unique among its siblings.
+fn identify(source: &str, children: &mut [Element]) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for child in children.iter_mut() {
+        identify(source, &mut child.children);
+        let id = identity(source, child);
+        let count = seen.entry(id.clone()).or_insert(0);
+        child.id = if *count > 0 { format!("{}#{}", id, count) } else { id };
+        *count += 1;
+    }
+}
+
+fn identity(source: &str, element: &Element) -> String {
+    if element.tag.starts_with('#') {
+        return format!("{} {}", element.tag, &source[element.span.clone()]);
+    }
+    let key = KEY_ATTRIBUTES
+        .iter()
+        .find_map(|key| element.attributes.iter().find(|a| local_name(&a.name) == *key));
+    if let Some(attr) = key {
+        return format!("{}[{}={}]", element.tag, attr.name, attr.value);
+    }
+    let child_text = |tag: &str| {
+        let child = element.children.iter().find(|c| c.tag == tag)?;
+        Some(source[child.inner.clone()?].trim())
+    };
+    match child_text("artifactId") {
+        Some(artifact) => format!("{}[{}:{}]", element.tag, child_text("groupId").unwrap_or_default(), artifact),
+        None => element.tag.clone(),
+    }
+}
+
+/// Parse a document into a root element spanning all of it.
+fn parse_document(source: &str, html: bool) -> Option<Element> {
+    let mut parser = Parser { source, pos: 0, html };
+    let (mut children, text) = parser.content(None)?;
+    identify(source, &mut children);
+    let span = 0..source.len();
+    Some(Element {
+        inner: Some(span.clone()),
+        children,
+        ..Element::leaf("", span, text)
+    })
+}
+
+/// Where child `i` starts, counting the line break and indentation before it.
+fn lead_start(source: &str, parent: &Element, i: usize) -> usize {
+    let start = parent.children[i].span.start;
+    let floor = match i {
+        0 => parent.inner.as_ref().map_or(start, |inner| inner.start),
+        _ => parent.children[i - 1].span.end,
+    };
+    let at = floor + source[floor..start].trim_end_matches([' ', '\t']).len();
+    let before = &source[floor..at];
+    if before.ends_with("\r\n") {
+        at - 2
+    } else if before.ends_with('\n') {
+        at - 1
+    } else {
+        at
+    }
+}
+
+fn child_path(path: &str, element: &Element) -> String {
+    if path.is_empty() {
+        element.name().to_string()
+    } else {
+        format!("{}/{}", path, element.name())
+    }
+}
+
+struct Merger<'a> {
+    base: &'a str,
+    local: &'a str,
+    remote: &'a str,
+    edits: Vec<Edit>,
+    keys: Vec<(&'static str, String, Result<NodeOutcome, RefusalReason>, Span)>,
+}
+
+impl Merger<'_> {
+    fn edit(&mut self, start: usize, end: usize, text: String) -> Span {
+        self.edits.push(Edit { start, end, text });
+        Span::Edit(self.edits.len() - 1)
+    }
+
+    fn record(&mut self, kind: &'static str, path: String, result: Result<NodeOutcome, RefusalReason>, span: Span) {
+        self.keys.push((kind, path, result, span));
+    }
+
+    /// Merge remote's changes to an element's children into local's copy.
+    fn merge_children(&mut self, base: Option<&Element>, local: &Element, remote: &Element, path: &str) {
+        let (base_source, local_source, remote_source) = (self.base, self.local, self.remote);
+        let find = |e: &Element, id: &str| e.children.iter().position(|c| c.id == id);
+        let base_child = |id: &str| base.and_then(|b| find(b, id).map(|i| &b.children[i]));
+        let Some(inner) = local.inner.clone() else { return };
+
+        // Remote's edits and insertions, in remote order
+        let mut anchor: Option<usize> = None;
+        let mut inserts: Vec<(Option<usize>, usize)> = Vec::new();
+        for (ri, r) in remote.children.iter().enumerate() {
+            let remote_text = &remote_source[r.span.clone()];
+            let base_text = base_child(&r.id).map(|b| &base_source[b.span.clone()]);
+            let Some(li) = find(local, &r.id) else {
+                let at = anchor.map_or(inner.start, |a| local.children[a].span.end);
+                match base_text {
+                    // Remote added it, after any children local added at the same point
+                    None => {
+                        let mut at = anchor;
+                        for (li, l) in local.children.iter().enumerate().skip(at.map_or(0, |a| a + 1)) {
+                            if base_child(&l.id).is_some() || find(remote, &l.id).is_some() {
+                                break;
+                            }
+                            at = Some(li);
+                        }
+                        inserts.push((at, ri));
+                    }
+                    // Local deleted it
+                    Some(b) if b == remote_text => {
+                        self.record("element", child_path(path, r), Ok(NodeOutcome::Deleted), Span::Local(at, at));
+                    }
+                    Some(_) => {
+                        self.record("element", child_path(path, r), Err(RefusalReason::DeleteModify), Span::Local(at, at));
+                    }
+                }
+                continue;
+            };
+            anchor = Some(li);
+
+            let l = &local.children[li];
+            let local_text = &local_source[l.span.clone()];
+            let span = Span::Local(l.span.start, l.span.end);
+            if local_text == remote_text {
+                if base_text != Some(local_text) {
+                    self.record("element", child_path(path, l), Ok(NodeOutcome::Both), span);
+                }
+            } else if base_text == Some(remote_text) {
+                self.record("element", child_path(path, l), Ok(NodeOutcome::Local), span);
+            } else if base_text == Some(local_text) {
+                let span = self.edit(l.span.start, l.span.end, remote_text.to_string());
+                self.record("element", child_path(path, l), Ok(NodeOutcome::Remote), span);
+            } else {
+                self.merge_element(base_child(&r.id), l, r, &child_path(path, l));
+            }
+        }
+
+        // Remote's deletions
+        if let Some(base) = base {
+            for b in base.children.iter().filter(|b| find(remote, &b.id).is_none()) {
+                let Some(li) = find(local, &b.id) else { continue };
+                let l = &local.children[li];
+                if local_source[l.span.clone()] == base_source[b.span.clone()] {
+                    let start = lead_start(local_source, local, li);
+                    self.edit(start, l.span.end, String::new());
+                    self.record("element", child_path(path, b), Ok(NodeOutcome::Deleted), Span::Local(start, start));
+                } else {
+                    let span = Span::Local(l.span.start, l.span.end);
+                    self.record("element", child_path(path, b), Err(RefusalReason::DeleteModify), span);
+                }
+            }
+        }
+
+        // Local's own additions
+        for l in local.children.iter() {
+            if base_child(&l.id).is_none() && find(remote, &l.id).is_none() {
+                let span = Span::Local(l.span.start, l.span.end);
+                self.record("element", child_path(path, l), Ok(NodeOutcome::Local), span);
+            }
+        }
+
+        for (anchor, ri) in inserts {
+            let r = &remote.children[ri];
+            let at = anchor.map_or(inner.start, |a| local.children[a].span.end);
+            let text = remote_source[lead_start(remote_source, remote, ri)..r.span.end].to_string();
+            let span = self.edit(at, at, text);
+            self.record("element", child_path(path, r), Ok(NodeOutcome::Remote), span);
+        }
+    }
+
+    /// Both sides changed an element differently: merge its attributes and its
+    /// children separately, and conflict if both changed its text.
+    fn merge_element(&mut self, base: Option<&Element>, local: &Element, remote: &Element, path: &str) {
+        self.merge_attributes(base, local, remote, path);
+
+        let inner = |source: &str, e: &Element| e.inner.clone().map(|range| source[range].to_string());
+        let (local_inner, remote_inner) = (inner(self.local, local), inner(self.remote, remote));
+        let base_inner = base.map(|b| inner(self.base, b));
+        if local_inner == remote_inner || base_inner.as_ref() == Some(&remote_inner) {
+            return;
+        }
+        if base_inner.as_ref() == Some(&local_inner) {
+            if let (Some(range), Some(text)) = (local.inner.clone(), remote_inner) {
+                let span = self.edit(range.start, range.end, text);
+                self.record("element", path.to_string(), Ok(NodeOutcome::Remote), span);
+                return;
+            }
+        } else if !local.text
+            && !remote.text
+            && local.inner.is_some()
+            && remote.inner.is_some()
+            && base.map_or(true, |b| !b.text && b.inner.is_some())
+        {
+            self.merge_children(base, local, remote, path);
+            return;
+        }
+        let span = Span::Local(local.span.start, local.span.end);
+        self.record("element", path.to_string(), Err(RefusalReason::SameNodeChanged), span);
+    }
+
+    /// Merge the attributes of an element both sides changed, name by name.
+    fn merge_attributes(&mut self, base: Option<&Element>, local: &Element, remote: &Element, path: &str) {
+        let find = |e: &Element, name: &str| e.attributes.iter().find(|a| a.name == name).cloned();
+        let value = |e: Option<&Element>, name: &str| e.and_then(|e| find(e, name)).map(|a| a.value);
+        let tag_span = Span::Local(local.open.start, local.open.end);
+
+        for r in &remote.attributes {
+            let attr_path = format!("{}@{}", path, r.name);
+            let base_value = value(base, &r.name);
+            match find(local, &r.name) {
+                Some(l) if l.value == r.value => {
+                    if base_value.as_ref() != Some(&l.value) {
+                        self.record("attribute", attr_path, Ok(NodeOutcome::Both), tag_span);
+                    }
+                }
+                Some(_) if base_value.as_ref() == Some(&r.value) => {
+                    self.record("attribute", attr_path, Ok(NodeOutcome::Local), tag_span);
+                }
+                Some(l) if base_value.as_ref() == Some(&l.value) => {
+                    let span = self.edit(l.span.start, l.span.end, self.remote[r.span.clone()].to_string());
+                    self.record("attribute", attr_path, Ok(NodeOutcome::Remote), span);
+                }
+                Some(_) => self.record("attribute", attr_path, Err(RefusalReason::SameNodeChanged), tag_span),
+                None => match base_value {
+                    None => {
+                        let at = local.attributes.last().map_or(local.name_end, |a| a.span.end);
+                        let span = self.edit(at, at, format!(" {}", &self.remote[r.span.clone()]));
+                        self.record("attribute", attr_path, Ok(NodeOutcome::Remote), span);
+                    }
+                    Some(b) if b == r.value => self.record("attribute", attr_path, Ok(NodeOutcome::Deleted), tag_span),
+                    Some(_) => self.record("attribute", attr_path, Err(RefusalReason::DeleteModify), tag_span),
+                },
+            }
+        }
+
+        for l in local.attributes.iter().filter(|l| find(remote, &l.name).is_none()) {
+            let attr_path = format!("{}@{}", path, l.name);
+            match value(base, &l.name) {
+                None => self.record("attribute", attr_path, Ok(NodeOutcome::Local), tag_span),
+                Some(b) if b == l.value => {
+                    let start = self.local[..l.span.start].trim_end().len();
+                    self.edit(start, l.span.end, String::new());
+                    self.record("attribute", attr_path, Ok(NodeOutcome::Deleted), tag_span);
+                }
+                Some(_) => self.record("attribute", attr_path, Err(RefusalReason::DeleteModify), tag_span),
+            }
+        }
+    }
+}
+
+/// Merge an XML or HTML session element by element: local's text with
+/// remote's changed, added and deleted elements applied. Elements are matched
+/// by tag and an `id`, `name` or `key` attribute; where both sides changed one,
+/// its attributes and children are merged separately. `None` if a side isn't
+/// well-formed enough to parse, or the merged document wouldn't be.
+pub fn merge_markup(session: &MergeSession) -> Option<DataMerge> {
+    let html = session.language == "html";
+    let base = parse_document(&session.base_content, html)?;
+    let local = parse_document(&session.local_content, html)?;
+    let remote = parse_document(&session.remote_content, html)?;
+
+    let mut merger = Merger {
+        base: &session.base_content,
+        local: &session.local_content,
+        remote: &session.remote_content,
+        edits: Vec::new(),
+        keys: Vec::new(),
+    };
+    merger.merge_children(Some(&base), &local, &remote, "");
+
+    let merged = apply_merge(&session.local_content, &merger.edits, merger.keys)?;
+    parse_document(&merged.content, html)?;
+    Some(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(base: &str, local: &str, remote: &str, language: &str) -> DataMerge {
+        let session = MergeSession::for_test(base, local, remote, language);
+        merge_markup(&session).expect("documents merge")
+    }
+
+    fn ids(element: &Element) -> Vec<&str> {
+        element.children.iter().filter(|c| !c.tag.starts_with('#')).map(|c| c.id.as_str()).collect()
+    }
+
+    fn conflicts(merged: &DataMerge) -> Vec<&str> {
+        merged
+            .keys
+            .iter()
+            .filter(|k| k.result.is_err())
+            .map(|k| k.path.as_str())
+            .collect()
+    }
+
+    /// Parse a document and merge it with itself, which must give it back as is.
+    fn round_trip(source: &str, language: &str) -> Element {
+        let document = parse_document(source, language == "html").expect("document parses");
+        let merged = merge(source, source, source, language);
+        assert_eq!(merged.content, source);
+        assert!(merged.keys.is_empty());
+        document
+    }
+
+    const STRINGS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- App strings -->
+<resources xmlns:tools="http://schemas.android.com/tools">
+    <string name="app_name">Weaver</string>
+    <string name="greeting" tools:ignore="MissingTranslation">Hello</string>
+    <plurals name="files">
+        <item quantity="one">%d file</item>
+        <item quantity="other">%d files</item>
+    </plurals>
+</resources>
+"#;
+
+    #[test]
+    fn android_resources_round_trip_and_merge_by_name() {
+        let document = round_trip(STRINGS, "xml");
+        let resources = document.children.iter().find(|c| c.tag == "resources").unwrap();
+        assert_eq!(
+            ids(resources),
+            ["string[name=app_name]", "string[name=greeting]", "plurals[name=files]"]
+        );
+
+        let local = STRINGS.replace(">Hello<", ">Hello there<");
+        let remote = STRINGS.replace(
+            "    <plurals",
+            "    <string name=\"farewell\">Bye</string>\n    <plurals",
+        );
+        let merged = merge(STRINGS, &local, &remote, "xml");
+        assert_eq!(
+            merged.content,
+            local.replace("    <plurals", "    <string name=\"farewell\">Bye</string>\n    <plurals")
+        );
+        assert!(conflicts(&merged).is_empty());
+    }
+
+    const POM: &str = r#"<project>
+  <dependencies>
+    <dependency>
+      <groupId>org.example</groupId>
+      <artifactId>core</artifactId>
+      <version>1.0</version>
+    </dependency>
+    <dependency>
+      <groupId>org.example</groupId>
+      <artifactId>util</artifactId>
+      <version>2.0</version>
+    </dependency>
+  </dependencies>
+</project>
+"#;
+
+    #[test]
+    fn maven_dependencies_match_on_group_and_artifact() {
+        let document = round_trip(POM, "xml");
+        let dependencies = &document.children[0].children[0];
+        assert_eq!(
+            ids(dependencies),
+            ["dependency[org.example:core]", "dependency[org.example:util]"]
+        );
+
+        // Local bumps one dependency, remote the other
+        let local = POM.replace("<version>1.0</version>", "<version>1.1</version>");
+        let remote = POM.replace("<version>2.0</version>", "<version>2.5</version>");
+        let merged = merge(POM, &local, &remote, "xml");
+        assert_eq!(merged.content, local.replace("<version>2.0</version>", "<version>2.5</version>"));
+
+        // Both bump the same one differently
+        let remote = POM.replace("<version>1.0</version>", "<version>1.2</version>");
+        let merged = merge(POM, &local, &remote, "xml");
+        assert_eq!(
+            conflicts(&merged),
+            ["project/dependencies/dependency[org.example:core]/version"]
+        );
+        assert_eq!(merged.content, local);
+    }
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <script>if (a < b && c > d) { render("<p>"); }</script>
+</head>
+<body>
+  <img src="logo.png" alt=logo>
+  <br>
+  <input id="q" disabled>
+  <p id="intro">Hello</p>
+</body>
+</html>
+"#;
+
+    #[test]
+    fn html_template_with_scripts_and_void_elements() {
+        let document = round_trip(PAGE, "html");
+        let html = document.children.iter().find(|c| c.tag == "html").unwrap();
+        let head = &html.children[0];
+        assert_eq!(ids(head), ["meta", "script"]);
+        assert!(head.children[1].children.is_empty());
+        let body = &html.children[1];
+        assert_eq!(ids(body), ["img", "br", "input[id=q]", "p[id=intro]"]);
+        assert!(body.children[0].inner.is_none());
+
+        let local = PAGE.replace("render(\"<p>\")", "render(\"<div>\")");
+        let remote = PAGE.replace(">Hello<", ">Welcome<");
+        let merged = merge(PAGE, &local, &remote, "html");
+        assert_eq!(merged.content, local.replace(">Hello<", ">Welcome<"));
+    }
+
+    #[test]
+    fn attributes_merge_name_by_name() {
+        let base = "<ui>\n  <button id=\"ok\" class=\"btn\" title=\"OK\" hidden=\"false\">OK</button>\n</ui>\n";
+        let local = "<ui>\n  <button id=\"ok\" class=\"btn primary\" title=\"OK\" hidden=\"false\">OK</button>\n</ui>\n";
+        let remote = "<ui>\n  <button id=\"ok\" class=\"btn\" title=\"Confirm\" data-test=\"ok\">OK</button>\n</ui>\n";
+
+        let merged = merge(base, local, remote, "xml");
+        assert_eq!(
+            merged.content,
+            "<ui>\n  <button id=\"ok\" class=\"btn primary\" title=\"Confirm\" data-test=\"ok\">OK</button>\n</ui>\n"
+        );
+        assert!(conflicts(&merged).is_empty());
+
+        // Both change the same attribute
+        let remote = remote.replace("class=\"btn\"", "class=\"btn secondary\"");
+        let merged = merge(base, local, &remote, "xml");
+        assert_eq!(conflicts(&merged), ["ui/button[id=ok]@class"]);
+    }
+
+    #[test]
+    fn reordered_siblings_keep_local_order() {
+        let base = "<list>\n  <item key=\"a\">1</item>\n  <item key=\"b\">2</item>\n  <item key=\"c\">3</item>\n</list>\n";
+        let local = "<list>\n  <item key=\"c\">3</item>\n  <item key=\"a\">1</item>\n  <item key=\"b\">2</item>\n</list>\n";
+        let remote = "<list>\n  <item key=\"a\">1</item>\n  <item key=\"b\">20</item>\n  <item key=\"c\">3</item>\n  <item key=\"d\">4</item>\n</list>\n";
+
+        // Remote's new item follows the one it follows in remote
+        let merged = merge(base, local, remote, "xml");
+        assert_eq!(
+            merged.content,
+            "<list>\n  <item key=\"c\">3</item>\n  <item key=\"d\">4</item>\n  <item key=\"a\">1</item>\n  <item key=\"b\">20</item>\n</list>\n"
+        );
+        assert!(conflicts(&merged).is_empty());
+    }
+}
@@ -2,3 +2,25 @@ pub mod hunk;
 pub mod three_way;
 pub mod resolver;
 pub mod structural;
+pub mod union;
+pub mod sequence;
+pub mod changelog;
+pub mod version;
+pub mod grammar;
+pub mod rename;
+pub mod trees;
+pub mod format;
+pub mod blocks;
+pub mod data;
+pub mod markup;
+pub mod lockfile;
+pub mod notebook;
+pub mod csv;
+pub mod prose;
+pub mod po;
+#[cfg(feature = "tree-sitter")]
+pub mod profile;
+#[cfg(feature = "tree-sitter")]
+pub mod injection;
+#[cfg(feature = "tree-sitter")]
+pub mod tokens;
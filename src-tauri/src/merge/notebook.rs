@@ -0,0 +1,588 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use serde_json::ser::PrettyFormatter;
+use serde_json::{json, Map, Value};
+
+use super::hunk::{HunkSource, HunkStatus, LineRange, MergeHunk, MergeSession};
+use super::sequence::merge_order;
+use super::three_way::{split_lines, three_way_merge};
+use super::version::find_version_conflicts;
+use crate::config::NotebookConfig;
+
+/// Start of the line that opens each cell in the merge view: `# %% [code] id=...`.
+const CELL_MARKER: &str = "# %% [";
+
+/// Which side's outputs and execution counts a merged code cell keeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputPolicy {
+    /// Local's, or remote's for a cell only remote has.
+    #[default]
+    Local,
+    /// Remote's, or local's for a cell only local has.
+    Remote,
+    /// None: outputs are emptied and execution counts reset.
+    Clear,
+}
+
+/// The merged notebook apart from cell sources, used to write the result pane's
+/// cells back out as `.ipynb` JSON.
+#[derive(Debug, Clone, Default)]
+pub struct Notebook {
+    /// Top-level fields other than `cells`.
+    top: Map<String, Value>,
+    /// Merged cell objects by id, with outputs picked by the policy.
+    cells: HashMap<String, Value>,
+    /// Whether cells carry an `id` field (nbformat 4.5 and later).
+    ids: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Cell {
+    id: String,
+    kind: String,
+    source: String,
+    value: Value,
+}
+
+impl Cell {
+    /// The cell as shown in the merge view: its marker line, then its source.
+    fn block(&self) -> String {
+        let marker = format!("{}{}] id={}", CELL_MARKER, self.kind, self.id);
+        if self.source.is_empty() {
+            marker
+        } else {
+            format!("{}\n{}", marker, self.source)
+        }
+    }
+}
+
+/// Check whether a path is a Jupyter notebook.
+pub fn is_notebook_file(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("ipynb"))
+}
+
+fn source_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(lines) => lines.iter().map(|l| l.as_str()).collect(),
+        _ => None,
+    }
+}
+
+/// Source text as nbformat stores it: lines keeping their newlines.
+fn source_lines(source: &str) -> Value {
+    Value::Array(
+        source
+            .split_inclusive('\n')
+            .map(|l| Value::String(l.to_string()))
+            .collect(),
+    )
+}
+
+/// Parse a notebook into its top-level fields and cells. An empty file is a
+/// notebook without cells.
+fn parse_notebook(content: &str) -> Option<(Map<String, Value>, Vec<Cell>)> {
+    if content.trim().is_empty() {
+        return Some((Map::new(), Vec::new()));
+    }
+    let Value::Object(mut top) = serde_json::from_str(content).ok()? else {
+        return None;
+    };
+    let cells = match top.remove("cells")? {
+        Value::Array(cells) => cells,
+        _ => return None,
+    };
+    let cells = cells
+        .into_iter()
+        .map(|value| {
+            Some(Cell {
+                id: value.get("id").and_then(Value::as_str).unwrap_or_default().to_string(),
+                kind: value.get("cell_type")?.as_str()?.to_string(),
+                source: source_text(value.get("source")?)?,
+                value,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some((top, cells))
+}
+
+/// Give cells without an id (notebooks older than nbformat 4.5) one: base cells
+/// by position, and a side's cells the id of the base cell with the same source,
+/// or of the unclaimed base cell of the same kind at the same position.
+fn assign_ids(base: &mut [Cell], sides: [(&mut [Cell], &str); 2]) {
+    for (i, cell) in base.iter_mut().enumerate().filter(|(_, c)| c.id.is_empty()) {
+        cell.id = format!("cell-{}", i + 1);
+    }
+    for (cells, side) in sides {
+        let mut claimed: HashSet<String> = cells.iter().map(|c| c.id.clone()).collect();
+        for (i, cell) in cells.iter_mut().enumerate().filter(|(_, c)| c.id.is_empty()) {
+            let same_source = base
+                .iter()
+                .find(|b| !claimed.contains(&b.id) && b.source == cell.source && b.kind == cell.kind);
+            let same_position = base
+                .get(i)
+                .filter(|b| !claimed.contains(&b.id) && b.kind == cell.kind);
+            let id = same_source
+                .or(same_position)
+                .map_or_else(|| format!("{}-{}", side, i + 1), |b| b.id.clone());
+            claimed.insert(id.clone());
+            cell.id = id;
+        }
+    }
+}
+
+/// Put back a cell one side deleted but the other edited, after the nearest
+/// base cell before it that the side kept.
+fn restore(ids: &mut Vec<String>, id: &str, base: &[String]) {
+    let base_pos = base.iter().position(|b| b == id).unwrap_or(0);
+    let at = base[..base_pos]
+        .iter()
+        .rev()
+        .find_map(|b| ids.iter().position(|i| i == b))
+        .map_or(0, |p| p + 1);
+    ids.insert(at, id.to_string());
+}
+
+/// The merged cell object: local's cell (or remote's if local doesn't have it)
+/// with remote's metadata if only remote changed it, and outputs per the policy.
+fn merged_cell(base: Option<&Cell>, local: Option<&Cell>, remote: Option<&Cell>, policy: OutputPolicy) -> Option<Value> {
+    let mut value = local.or(remote)?.value.clone();
+    let metadata = |c: Option<&Cell>| c.and_then(|c| c.value.get("metadata")).cloned();
+    if let (Some(_), Some(remote_metadata)) = (local, metadata(remote)) {
+        if metadata(local) == metadata(base) {
+            value["metadata"] = remote_metadata;
+        }
+    }
+    if value.get("cell_type").and_then(Value::as_str) == Some("code") {
+        let outputs_from = match policy {
+            OutputPolicy::Local => local.or(remote),
+            OutputPolicy::Remote => remote.or(local),
+            OutputPolicy::Clear => None,
+        };
+        value["outputs"] = outputs_from
+            .and_then(|c| c.value.get("outputs").cloned())
+            .unwrap_or_else(|| json!([]));
+        value["execution_count"] = outputs_from
+            .and_then(|c| c.value.get("execution_count").cloned())
+            .unwrap_or(Value::Null);
+    }
+    Some(value)
+}
+
+/// Each cell's line range in a side's merge view text.
+fn layout(cells: &[Cell]) -> (String, HashMap<String, LineRange>) {
+    let mut lines = 0;
+    let mut ranges = HashMap::new();
+    let blocks: Vec<String> = cells
+        .iter()
+        .map(|cell| {
+            let block = cell.block();
+            let count = split_lines(&block).len();
+            ranges.insert(cell.id.clone(), LineRange::new(lines + 1, lines + count));
+            lines += count;
+            block
+        })
+        .collect();
+    (blocks.join("\n"), ranges)
+}
+
+/// Merge a session's notebooks cell by cell. Cells are matched by id and
+/// ordered by merging the three cell sequences; a cell both sides edited has its
+/// source merged line by line, and is a conflict if that leaves conflicts or one
+/// side deleted it. Base, local, remote and the result become the notebooks'
+/// cells as `# %% [<type>] id=<id>` blocks followed by the source, with one
+/// hunk per changed cell. Returns false, leaving the session alone, if a side
+/// isn't a notebook.
+pub fn notebook_merge(session: &mut MergeSession, config: &NotebookConfig) -> bool {
+    let (
+        Some((base_top, mut base_cells)),
+        Some((local_top, mut local_cells)),
+        Some((remote_top, mut remote_cells)),
+    ) = (
+        parse_notebook(&session.base_content),
+        parse_notebook(&session.local_content),
+        parse_notebook(&session.remote_content),
+    )
+    else {
+        return false;
+    };
+    let ids = local_cells.iter().chain(&remote_cells).all(|c| !c.id.is_empty());
+    assign_ids(&mut base_cells, [(&mut local_cells, "local"), (&mut remote_cells, "remote")]);
+
+    let id_list = |cells: &[Cell]| -> Vec<String> { cells.iter().map(|c| c.id.clone()).collect() };
+    let (base_ids, mut local_ids, mut remote_ids) = (id_list(&base_cells), id_list(&local_cells), id_list(&remote_cells));
+    let find = |cells: &'_ [Cell], id: &str| cells.iter().position(|c| c.id == id);
+
+    // A cell deleted on one side but edited on the other stays, as a conflict
+    for base in &base_cells {
+        let (l, r) = (find(&local_cells, &base.id), find(&remote_cells, &base.id));
+        match (l, r) {
+            (None, Some(r)) if remote_cells[r].block() != base.block() => restore(&mut local_ids, &base.id, &base_ids),
+            (Some(l), None) if local_cells[l].block() != base.block() => restore(&mut remote_ids, &base.id, &base_ids),
+            _ => {}
+        }
+    }
+    let order = merge_order(&base_ids, &local_ids, &remote_ids);
+
+    let (base_text, base_ranges) = layout(&base_cells);
+    let (local_text, local_ranges) = layout(&local_cells);
+    let (remote_text, remote_ranges) = layout(&remote_cells);
+
+    let mut result: Vec<String> = Vec::new();
+    let mut hunks: Vec<MergeHunk> = Vec::new();
+    let mut notebook_cells = HashMap::new();
+    for id in &order {
+        let cell = |cells: &'_ [Cell]| find(cells, id).map(|i| cells[i].clone());
+        let (b, l, r) = (cell(&base_cells), cell(&local_cells), cell(&remote_cells));
+        let block = |c: &Option<Cell>| c.as_ref().map(Cell::block);
+        let (b_block, l_block, r_block) = (block(&b), block(&l), block(&r));
+
+        let (lines, source, status): (Option<String>, HunkSource, HunkStatus) = if l_block == r_block {
+            (l_block.clone(), HunkSource::Both, HunkStatus::AutoResolved)
+        } else if b_block == l_block {
+            (r_block.clone(), HunkSource::Remote, HunkStatus::AutoResolved)
+        } else if b_block == r_block {
+            (l_block.clone(), HunkSource::Local, HunkStatus::AutoResolved)
+        } else {
+            // Both edited the cell: merge its source if both kept it as the same kind
+            let merged = match (&l, &r) {
+                (Some(lc), Some(rc)) if lc.kind == rc.kind => {
+                    let base_source = b.as_ref().map_or("", |c| c.source.as_str());
+                    let (source_hunks, merged_source) = three_way_merge(base_source, &lc.source, &rc.source);
+                    let clean = source_hunks.iter().all(|h| h.source != HunkSource::Conflict);
+                    clean.then(|| Cell { source: merged_source, ..lc.clone() }.block())
+                }
+                _ => None,
+            };
+            match merged {
+                Some(text) => (Some(text), HunkSource::Conflict, HunkStatus::AutoResolved),
+                None => {
+                    let text = format!(
+                        "<<<<<<< LOCAL\n{}\n=======\n{}\n>>>>>>> REMOTE",
+                        l_block.clone().unwrap_or_default(),
+                        r_block.clone().unwrap_or_default()
+                    );
+                    (Some(text), HunkSource::Conflict, HunkStatus::Unresolved)
+                }
+            }
+        };
+
+        let start = result.len();
+        if let Some(text) = &lines {
+            result.extend(split_lines(text).into_iter().map(str::to_string));
+        }
+        if b_block != l_block || b_block != r_block {
+            let range = |ranges: &HashMap<String, LineRange>| ranges.get(id).copied().unwrap_or_else(LineRange::empty);
+            hunks.push(MergeHunk {
+                id: hunks.len(),
+                base_range: range(&base_ranges),
+                local_range: range(&local_ranges),
+                remote_range: range(&remote_ranges),
+                result_range: if result.len() > start {
+                    LineRange::new(start + 1, result.len())
+                } else {
+                    LineRange::empty()
+                },
+                source,
+                status,
+                local_content: l_block.unwrap_or_default(),
+                remote_content: r_block.unwrap_or_default(),
+                base_content: b_block.unwrap_or_default(),
+            });
+        }
+        if let Some(value) = merged_cell(b.as_ref(), l.as_ref(), r.as_ref(), config.outputs) {
+            notebook_cells.insert(id.clone(), value);
+        }
+    }
+
+    // Top-level fields: local's, with remote's metadata if only remote changed it
+    let mut top = if local_top.is_empty() { remote_top.clone() } else { local_top.clone() };
+    if local_top.get("metadata") == base_top.get("metadata") {
+        if let Some(metadata) = remote_top.get("metadata") {
+            top.insert("metadata".to_string(), metadata.clone());
+        }
+    }
+    for field in ["nbformat", "nbformat_minor"] {
+        let version = |t: &Map<String, Value>| t.get(field).and_then(Value::as_u64);
+        if let Some(max) = version(&local_top).max(version(&remote_top)) {
+            top.insert(field.to_string(), json!(max));
+        }
+    }
+
+    session.base_content = base_text;
+    session.local_content = local_text;
+    session.remote_content = remote_text;
+    session.result_content = result.join("\n");
+    session.version_conflicts = find_version_conflicts(&hunks);
+    session.hunks = hunks;
+    session.notebook = Some(Notebook { top, cells: notebook_cells, ids });
+    true
+}
+
+/// Turn the result pane's cell blocks back into notebook JSON, formatted the
+/// way Jupyter writes it. Cells keep the outputs and metadata picked during
+/// the merge; cells added in the result pane start out empty.
+pub fn write_notebook(notebook: &Notebook, content: &str) -> Result<String, String> {
+    // (type, id, source lines) for each block
+    let mut blocks: Vec<(String, Option<String>, Vec<&str>)> = Vec::new();
+    for line in content.lines() {
+        if let Some(marker) = line.strip_prefix(CELL_MARKER) {
+            if let Some((kind, rest)) = marker.split_once(']') {
+                let id = rest.trim().strip_prefix("id=").map(str::to_string);
+                blocks.push((kind.to_string(), id, Vec::new()));
+                continue;
+            }
+        }
+        match blocks.last_mut() {
+            Some((_, _, lines)) => lines.push(line),
+            // Text before the first marker becomes a code cell of its own
+            None if !line.trim().is_empty() => blocks.push(("code".to_string(), None, vec![line])),
+            None => {}
+        }
+    }
+
+    let mut used: HashSet<String> = HashSet::new();
+    let mut cells = Vec::new();
+    for (n, (kind, id, lines)) in blocks.into_iter().enumerate() {
+        let mut value = id
+            .as_ref()
+            .filter(|id| !used.contains(*id))
+            .and_then(|id| notebook.cells.get(id).cloned())
+            .unwrap_or_else(|| json!({ "cell_type": kind, "metadata": {}, "source": [] }));
+        if notebook.ids {
+            // A repeated id, e.g. after taking both sides of a conflict, gets a fresh one
+            let id = id.unwrap_or_else(|| format!("cell-{}", n + 1));
+            let mut fresh = id.clone();
+            let mut copy = 1;
+            while used.contains(&fresh) {
+                copy += 1;
+                fresh = format!("{}-{}", id, copy);
+            }
+            used.insert(fresh.clone());
+            value["id"] = Value::String(fresh);
+        }
+
+        let object = value.as_object_mut().ok_or("Invalid notebook cell")?;
+        object.insert("cell_type".to_string(), Value::String(kind.clone()));
+        if kind == "code" {
+            object.entry("execution_count").or_insert(Value::Null);
+            object.entry("outputs").or_insert_with(|| json!([]));
+        } else {
+            object.remove("execution_count");
+            object.remove("outputs");
+        }
+        object.insert("source".to_string(), source_lines(&lines.join("\n")));
+        cells.push(value);
+    }
+
+    // Jupyter writes keys sorted, one space per indent level, and a final newline
+    let mut top = notebook.top.clone();
+    top.insert("cells".to_string(), Value::Array(cells));
+    top.sort_keys();
+    let mut out = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, PrettyFormatter::with_indent(b" "));
+    Value::Object(top)
+        .serialize(&mut serializer)
+        .map_err(|e| format!("Failed to write notebook: {}", e))?;
+    let mut text = String::from_utf8(out).map_err(|e| format!("Failed to write notebook: {}", e))?;
+    text.push('\n');
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A notebook as Jupyter writes it.
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "id": "intro",
+   "metadata": {},
+   "source": [
+    "# Title\n",
+    "Some text"
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "id": "calc",
+   "metadata": {
+    "tags": [
+     "setup"
+    ]
+   },
+   "outputs": [
+    {
+     "name": "stdout",
+     "output_type": "stream",
+     "text": [
+      "2\n"
+     ]
+    }
+   ],
+   "source": [
+    "x = 1\n",
+    "print(x + 1)"
+   ]
+  }
+ ],
+ "metadata": {
+  "kernelspec": {
+   "name": "python3"
+  }
+ },
+ "nbformat": 4,
+ "nbformat_minor": 5
+}
+"##;
+
+    fn notebook(cells: Vec<Value>) -> String {
+        let notebook = json!({ "cells": cells, "metadata": {}, "nbformat": 4, "nbformat_minor": 4 });
+        serde_json::to_string_pretty(&notebook).unwrap()
+    }
+
+    fn markdown(id: Option<&str>, source: &str) -> Value {
+        let mut cell = json!({ "cell_type": "markdown", "metadata": {}, "source": source });
+        if let Some(id) = id {
+            cell["id"] = json!(id);
+        }
+        cell
+    }
+
+    fn code(id: Option<&str>, source: &str, output: &str) -> Value {
+        let mut cell = json!({
+            "cell_type": "code",
+            "execution_count": 1,
+            "metadata": {},
+            "outputs": [{ "name": "stdout", "output_type": "stream", "text": [output] }],
+            "source": source,
+        });
+        if let Some(id) = id {
+            cell["id"] = json!(id);
+        }
+        cell
+    }
+
+    fn merge(base: &str, local: &str, remote: &str, outputs: OutputPolicy) -> MergeSession {
+        let mut session = MergeSession::for_test(base, local, remote, "plaintext");
+        assert!(notebook_merge(&mut session, &NotebookConfig { outputs }));
+        session
+    }
+
+    fn written(session: &MergeSession) -> Value {
+        let text = write_notebook(session.notebook.as_ref().unwrap(), &session.result_content).unwrap();
+        serde_json::from_str(&text).unwrap()
+    }
+
+    #[test]
+    fn unchanged_notebook_writes_back_identically() {
+        let session = merge(NOTEBOOK, NOTEBOOK, NOTEBOOK, OutputPolicy::Local);
+        assert_eq!(
+            session.result_content,
+            "# %% [markdown] id=intro\n# Title\nSome text\n# %% [code] id=calc\nx = 1\nprint(x + 1)"
+        );
+        assert!(session.hunks.is_empty());
+        assert_eq!(write_notebook(session.notebook.as_ref().unwrap(), &session.result_content).unwrap(), NOTEBOOK);
+    }
+
+    #[test]
+    fn cells_edited_on_both_sides_merge() {
+        let base = notebook(vec![markdown(Some("a"), "# Title"), code(Some("b"), "x = 1\ny = 2\nprint(x)", "1")]);
+        let local = notebook(vec![markdown(Some("a"), "# Title\nIntro"), code(Some("b"), "x = 10\ny = 2\nprint(x)", "10")]);
+        let remote = notebook(vec![
+            code(Some("c"), "import os", ""),
+            markdown(Some("a"), "# Title"),
+            code(Some("b"), "x = 1\ny = 2\nprint(x, y)", "1 2"),
+        ]);
+
+        let session = merge(&base, &local, &remote, OutputPolicy::Local);
+        assert_eq!(
+            session.result_content,
+            "# %% [code] id=c\nimport os\n# %% [markdown] id=a\n# Title\nIntro\n# %% [code] id=b\nx = 10\ny = 2\nprint(x, y)"
+        );
+        assert!(session.hunks.iter().all(|h| h.status != HunkStatus::Unresolved));
+    }
+
+    #[test]
+    fn cell_deleted_on_one_side_and_edited_on_the_other_conflicts() {
+        let base = notebook(vec![markdown(Some("a"), "# Title"), code(Some("b"), "x = 1", "1"), code(Some("c"), "y = 2", "2")]);
+        let local = notebook(vec![markdown(Some("a"), "# Title"), code(Some("c"), "y = 2", "2")]);
+        let remote = notebook(vec![markdown(Some("a"), "# Title"), code(Some("b"), "x = 5", "5"), code(Some("c"), "y = 2", "2")]);
+
+        let mut session = merge(&base, &local, &remote, OutputPolicy::Local);
+        assert_eq!(session.hunks.len(), 1);
+        let hunk = session.hunks[0].clone();
+        assert_eq!(hunk.status, HunkStatus::Unresolved);
+        assert_eq!((hunk.local_content.as_str(), hunk.remote_content.as_str()), ("", "# %% [code] id=b\nx = 5"));
+
+        // Keeping the edit puts the cell back where it was
+        super::super::resolver::resolve_hunk(&mut session, hunk.id, HunkStatus::AcceptedRemote);
+        let cells = written(&session)["cells"].clone();
+        let ids: Vec<&str> = cells.as_array().unwrap().iter().map(|c| c["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+        assert_eq!(cells[1]["source"], json!(["x = 5"]));
+    }
+
+    #[test]
+    fn outputs_follow_the_policy() {
+        // Local edits the cell, remote only reran it
+        let base = notebook(vec![code(Some("a"), "x = 1", "1")]);
+        let local = notebook(vec![code(Some("a"), "x = 10", "10")]);
+        let mut rerun = code(Some("a"), "x = 1", "1 (rerun)");
+        rerun["execution_count"] = json!(7);
+        let remote = notebook(vec![rerun]);
+
+        for (policy, text, count) in [
+            (OutputPolicy::Local, Some("10"), json!(1)),
+            (OutputPolicy::Remote, Some("1 (rerun)"), json!(7)),
+            (OutputPolicy::Clear, None, Value::Null),
+        ] {
+            let cell = written(&merge(&base, &local, &remote, policy))["cells"][0].clone();
+            assert_eq!(cell["source"], json!(["x = 10"]));
+            assert_eq!(cell["outputs"][0]["text"][0].as_str(), text, "{policy:?}");
+            assert_eq!(cell["execution_count"], count, "{policy:?}");
+        }
+    }
+
+    #[test]
+    fn cells_without_ids_are_matched_by_source_and_position() {
+        let base = notebook(vec![markdown(None, "# Title"), code(None, "x = 1", "1")]);
+        let local = notebook(vec![markdown(None, "# Title"), code(None, "x = 2", "2")]);
+        let remote = notebook(vec![code(None, "import os", ""), markdown(None, "# Title"), code(None, "x = 1", "1")]);
+
+        let session = merge(&base, &local, &remote, OutputPolicy::Local);
+        assert_eq!(
+            session.result_content,
+            "# %% [code] id=remote-1\nimport os\n# %% [markdown] id=cell-1\n# Title\n# %% [code] id=cell-2\nx = 2"
+        );
+        // Notebooks older than nbformat 4.5 are written without ids
+        let cells = written(&session)["cells"].clone();
+        assert_eq!(cells.as_array().unwrap().len(), 3);
+        assert!(cells.as_array().unwrap().iter().all(|c| c.get("id").is_none()));
+        assert_eq!(cells[2]["outputs"][0]["text"][0], "2");
+    }
+
+    #[test]
+    fn written_cells_get_unique_ids() {
+        let session = merge(NOTEBOOK, NOTEBOOK, NOTEBOOK, OutputPolicy::Local);
+        // Text before the first marker, and a cell taken from both sides
+        let content = "import os\n# %% [code] id=calc\nx = 1\n# %% [code] id=calc\nx = 2";
+        let text = write_notebook(session.notebook.as_ref().unwrap(), content).unwrap();
+        let notebook: Value = serde_json::from_str(&text).unwrap();
+
+        let cells = notebook["cells"].as_array().unwrap();
+        let ids: Vec<&str> = cells.iter().map(|c| c["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["cell-1", "calc", "calc-2"]);
+        assert_eq!(cells[0]["source"], json!(["import os"]));
+        assert_eq!(cells[1]["metadata"]["tags"], json!(["setup"]));
+        assert_eq!(cells[2]["source"], json!(["x = 2"]));
+        assert_eq!(cells[2]["outputs"], json!([]));
+    }
+}
@@ -0,0 +1,569 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::hunk::{HunkSource, HunkStatus, LineRange, MergeHunk, MergeSession};
+use super::sequence::{merge_order, merge_sequences};
+use super::three_way::split_lines;
+use super::version::find_version_conflicts;
+
+/// Separates `msgctxt` from `msgid` in an entry's key, as in compiled catalogues.
+const CONTEXT_SEPARATOR: char = '\u{4}';
+
+/// Width gettext wraps `#:` reference lines at.
+const WRAP_WIDTH: usize = 79;
+
+/// One catalogue entry, split into the parts that are merged separately. Each
+/// part keeps its lines as written.
+#[derive(Debug, Clone)]
+struct Entry {
+    /// `msgctxt` and `msgid`, with obsolete (`#~`) entries kept apart.
+    key: String,
+    /// Translator (`# `) and extracted (`#.`) comments.
+    comments: Vec<String>,
+    references: Vec<String>,
+    reference_lines: Vec<String>,
+    flags: Vec<String>,
+    flag_lines: Vec<String>,
+    /// Previous-string (`#|`) comments.
+    previous: Vec<String>,
+    /// `msgctxt`, `msgid` and `msgid_plural` lines, or all of an obsolete entry.
+    source: Vec<String>,
+    /// `msgstr` lines.
+    translation: Vec<String>,
+    lines: LineRange,
+}
+
+impl Entry {
+    fn text(&self) -> String {
+        [
+            &self.comments,
+            &self.reference_lines,
+            &self.flag_lines,
+            &self.previous,
+            &self.source,
+            &self.translation,
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+
+    fn is_fuzzy(&self) -> bool {
+        self.flags.iter().any(|f| f == "fuzzy")
+    }
+
+    fn is_header(&self) -> bool {
+        self.key.is_empty()
+    }
+}
+
+/// A parsed catalogue: entries in file order, keyed by msgctxt and msgid.
+struct Catalog {
+    keys: Vec<String>,
+    entries: HashMap<String, Entry>,
+}
+
+/// Check whether a path is a gettext catalogue or template.
+pub fn is_po_file(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| matches!(e.as_str(), "po" | "pot"))
+}
+
+/// The contents of a quoted string line, escapes and all.
+fn quoted(line: &str) -> Option<&str> {
+    let start = line.find('"')?;
+    let end = line.rfind('"')?;
+    (end > start).then(|| &line[start + 1..end])
+}
+
+/// Parse the lines of one entry. `None` if a line isn't a comment, keyword or
+/// string, or there's no `msgid`.
+fn parse_entry(lines: &[&str], range: LineRange) -> Option<Entry> {
+    #[derive(PartialEq)]
+    enum Field {
+        Context,
+        Id,
+        Other,
+    }
+
+    let mut entry = Entry {
+        key: String::new(),
+        comments: Vec::new(),
+        references: Vec::new(),
+        reference_lines: Vec::new(),
+        flags: Vec::new(),
+        flag_lines: Vec::new(),
+        previous: Vec::new(),
+        source: Vec::new(),
+        translation: Vec::new(),
+        lines: range,
+    };
+    let (mut context, mut id): (Option<String>, Option<String>) = (None, None);
+    let mut obsolete = false;
+    let mut in_translation = false;
+    let mut field = Field::Other;
+
+    for &raw in lines {
+        let line = match raw.strip_prefix("#~") {
+            Some(rest) if !rest.starts_with('|') => {
+                obsolete = true;
+                entry.source.push(raw.to_string());
+                rest.trim_start()
+            }
+            _ if obsolete => {
+                entry.source.push(raw.to_string());
+                continue;
+            }
+            _ => raw,
+        };
+        if obsolete {
+            // Only the key matters for an obsolete entry, which is merged whole
+        } else if let Some(rest) = line.strip_prefix("#:") {
+            entry.references.extend(rest.split_whitespace().map(str::to_string));
+            entry.reference_lines.push(raw.to_string());
+            continue;
+        } else if let Some(rest) = line.strip_prefix("#,") {
+            entry.flags.extend(rest.split(',').map(str::trim).filter(|f| !f.is_empty()).map(str::to_string));
+            entry.flag_lines.push(raw.to_string());
+            continue;
+        } else if line.starts_with("#|") {
+            entry.previous.push(raw.to_string());
+            continue;
+        } else if line.starts_with('#') {
+            entry.comments.push(raw.to_string());
+            continue;
+        }
+
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("msgctxt") {
+            field = Field::Context;
+            context = Some(quoted(trimmed)?.to_string());
+        } else if trimmed.starts_with("msgid_plural") {
+            field = Field::Other;
+        } else if trimmed.starts_with("msgid") {
+            field = Field::Id;
+            id = Some(quoted(trimmed)?.to_string());
+        } else if trimmed.starts_with("msgstr") {
+            field = Field::Other;
+            in_translation = true;
+        } else if trimmed.starts_with('"') {
+            let text = quoted(trimmed)?;
+            match field {
+                Field::Context => context.get_or_insert_with(String::new).push_str(text),
+                Field::Id => id.get_or_insert_with(String::new).push_str(text),
+                Field::Other => {}
+            }
+        } else if !obsolete {
+            return None;
+        }
+        if obsolete {
+            continue;
+        }
+        if in_translation {
+            entry.translation.push(raw.to_string());
+        } else {
+            entry.source.push(raw.to_string());
+        }
+    }
+
+    let id = id?;
+    let key = match context {
+        Some(context) => format!("{}{}{}", context, CONTEXT_SEPARATOR, id),
+        None => id,
+    };
+    entry.key = if obsolete { format!("#~ {}", key) } else { key };
+    Some(entry)
+}
+
+/// Parse a catalogue into entries, which are separated by blank lines.
+/// `None` if an entry doesn't parse or two share a key.
+fn parse_catalog(content: &str) -> Option<Catalog> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut keys = Vec::new();
+    let mut entries = HashMap::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].trim().is_empty() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < lines.len() && !lines[i].trim().is_empty() {
+            i += 1;
+        }
+        let entry = parse_entry(&lines[start..i], LineRange::new(start + 1, i))?;
+        if entries.contains_key(&entry.key) {
+            return None;
+        }
+        keys.push(entry.key.clone());
+        entries.insert(entry.key.clone(), entry);
+    }
+    Some(Catalog { keys, entries })
+}
+
+/// 3-way merge of one part of an entry: one side's change wins, and where both
+/// changed it, the changes are combined line by line. `None` if they can't be.
+fn merge_lines(base: Option<&Vec<String>>, local: &Vec<String>, remote: &Vec<String>) -> Option<Vec<String>> {
+    if local == remote || base == Some(remote) {
+        Some(local.clone())
+    } else if base == Some(local) {
+        Some(remote.clone())
+    } else {
+        merge_sequences(base.map_or(&[][..], Vec::as_slice), local, remote)
+    }
+}
+
+/// Merge sets such as references or flags: local's items, less those remote
+/// removed, then remote's additions.
+fn merge_set(base: Option<&Vec<String>>, local: &[String], remote: &[String]) -> Vec<String> {
+    let base: HashSet<&String> = base.map(|b| b.iter().collect()).unwrap_or_default();
+    let mut merged: Vec<String> = local
+        .iter()
+        .filter(|item| !base.contains(item) || remote.contains(item))
+        .cloned()
+        .collect();
+    merged.extend(remote.iter().filter(|item| !base.contains(item) && !local.contains(item)).cloned());
+    merged
+}
+
+/// `#:` lines for merged references, wrapped the way gettext writes them.
+fn reference_lines(references: &[String]) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for reference in references {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + reference.len() <= WRAP_WIDTH => {
+                line.push(' ');
+                line.push_str(reference);
+            }
+            _ => lines.push(format!("#: {}", reference)),
+        }
+    }
+    lines
+}
+
+/// Merge a header's `msgstr` field by field. Where both sides changed a field,
+/// the later date wins for `*-Date` fields and local's value otherwise.
+fn merge_header(base: Option<&Vec<String>>, local: &[String], remote: &[String]) -> Option<Vec<String>> {
+    let fields = |lines: &[String]| -> Option<(Vec<String>, HashMap<String, String>)> {
+        let (first, rest) = lines.split_first()?;
+        if quoted(first) != Some("") {
+            return None;
+        }
+        let mut names = Vec::new();
+        let mut values = HashMap::new();
+        for line in rest {
+            let text = quoted(line)?;
+            let name = text.split(':').next().unwrap_or(text).to_string();
+            names.push(name.clone());
+            values.insert(name, line.clone());
+        }
+        Some((names, values))
+    };
+    let (local_names, local_values) = fields(local)?;
+    let (remote_names, remote_values) = fields(remote)?;
+    let (base_names, base_values) = match base {
+        Some(base) => fields(base)?,
+        None => (Vec::new(), HashMap::new()),
+    };
+
+    let mut merged = vec![local[0].clone()];
+    for name in merge_order(&base_names, &local_names, &remote_names) {
+        let (b, l, r) = (base_values.get(&name), local_values.get(&name), remote_values.get(&name));
+        let line = if l == r || b == r {
+            l
+        } else if b == l {
+            r
+        } else if name.ends_with("-Date") {
+            l.max(r)
+        } else {
+            l
+        };
+        merged.extend(line.cloned());
+    }
+    Some(merged)
+}
+
+/// Merge an entry both sides changed. References and flags always merge; the
+/// translation conflicts if both sides changed it differently, unless one
+/// side's is fuzzy and the other's isn't, and comments, previous strings and
+/// source lines conflict if both sides' changes can't be combined. Returns the
+/// merged entry, or local's and remote's versions of the parts that conflict
+/// over the otherwise merged entry.
+fn merge_entry(base: Option<&Entry>, local: &Entry, remote: &Entry) -> Result<Entry, Box<(Entry, Entry)>> {
+    if local.key.starts_with("#~") {
+        return Ok(local.clone());
+    }
+    let mut merged = local.clone();
+    let comments = merge_lines(base.map(|b| &b.comments), &local.comments, &remote.comments);
+    let previous = merge_lines(base.map(|b| &b.previous), &local.previous, &remote.previous);
+    let source = merge_lines(base.map(|b| &b.source), &local.source, &remote.source);
+
+    merged.references = merge_set(base.map(|b| &b.references), &local.references, &remote.references);
+    merged.reference_lines = if merged.references == local.references {
+        local.reference_lines.clone()
+    } else if merged.references == remote.references {
+        remote.reference_lines.clone()
+    } else {
+        reference_lines(&merged.references)
+    };
+
+    let base_translation = base.map(|b| &b.translation);
+    let mut unfuzzied = false;
+    let translation = if local.translation == remote.translation || base_translation == Some(&remote.translation) {
+        Some(local.translation.clone())
+    } else if base_translation == Some(&local.translation) {
+        Some(remote.translation.clone())
+    } else if local.is_header() {
+        Some(merge_header(base_translation, &local.translation, &remote.translation).unwrap_or_else(|| local.translation.clone()))
+    } else if local.is_fuzzy() != remote.is_fuzzy() {
+        // Prefer a finished translation to a fuzzy one
+        unfuzzied = true;
+        Some(if local.is_fuzzy() { &remote.translation } else { &local.translation }.clone())
+    } else {
+        None
+    };
+
+    merged.flags = merge_set(base.map(|b| &b.flags), &local.flags, &remote.flags);
+    if unfuzzied {
+        merged.flags.retain(|f| f != "fuzzy");
+    }
+    merged.flag_lines = if merged.flags == local.flags {
+        local.flag_lines.clone()
+    } else if merged.flags == remote.flags {
+        remote.flag_lines.clone()
+    } else if merged.flags.is_empty() {
+        Vec::new()
+    } else {
+        vec![format!("#, {}", merged.flags.join(", "))]
+    };
+
+    match (translation, comments, previous, source) {
+        (Some(translation), Some(comments), Some(previous), Some(source)) => Ok(Entry {
+            comments,
+            previous,
+            source,
+            translation,
+            ..merged
+        }),
+        (translation, comments, previous, source) => {
+            // Each side keeps its own version of whatever didn't merge
+            let side = |entry: &Entry| Entry {
+                comments: comments.clone().unwrap_or_else(|| entry.comments.clone()),
+                previous: previous.clone().unwrap_or_else(|| entry.previous.clone()),
+                source: source.clone().unwrap_or_else(|| entry.source.clone()),
+                translation: translation.clone().unwrap_or_else(|| entry.translation.clone()),
+                ..merged.clone()
+            };
+            Err(Box::new((side(local), side(remote))))
+        }
+    }
+}
+
+/// The merged entry order, with entries a side deleted put back after the entry
+/// before them in base, so each deletion is weighed against the other side.
+fn entry_order(base: &[String], local: &[String], remote: &[String]) -> Vec<String> {
+    let mut order = merge_order(base, local, remote);
+    for (i, key) in base.iter().enumerate() {
+        if !order.contains(key) {
+            let before = i.checked_sub(1).and_then(|p| order.iter().position(|k| *k == base[p]));
+            order.insert(before.map_or(0, |p| p + 1), key.clone());
+        }
+    }
+    order
+}
+
+/// Merge a session's `.po` catalogues entry by entry. Entries are matched by
+/// `msgctxt` and `msgid`; new entries from either side are kept, and an entry
+/// both sides changed has its comments, references, flags and translation
+/// merged separately. Only translating the same message differently (or
+/// translating one the other side deleted), or changing its comments in ways
+/// that can't be combined, conflicts. Replaces the line merge's hunks with one
+/// hunk per changed entry. Returns false, leaving the session alone, if a side
+/// doesn't parse.
+pub fn po_merge(session: &mut MergeSession) -> bool {
+    let (Some(base), Some(local), Some(remote)) = (
+        parse_catalog(&session.base_content),
+        parse_catalog(&session.local_content),
+        parse_catalog(&session.remote_content),
+    ) else {
+        return false;
+    };
+
+    let mut result: Vec<String> = Vec::new();
+    let mut hunks: Vec<MergeHunk> = Vec::new();
+    for key in entry_order(&base.keys, &local.keys, &remote.keys) {
+        let (b, l, r) = (base.entries.get(&key), local.entries.get(&key), remote.entries.get(&key));
+        let text = |entry: Option<&Entry>| entry.map(Entry::text);
+        let (bt, lt, rt) = (text(b), text(l), text(r));
+
+        let (merged, source, sides) = if lt == rt {
+            (lt.clone(), HunkSource::Both, None)
+        } else if bt == lt {
+            (rt.clone(), HunkSource::Remote, None)
+        } else if bt == rt {
+            (lt.clone(), HunkSource::Local, None)
+        } else {
+            match (l, r) {
+                (Some(l), Some(r)) => match merge_entry(b, l, r) {
+                    Ok(entry) => (Some(entry.text()), HunkSource::Conflict, None),
+                    Err(sides) => (None, HunkSource::Conflict, Some((sides.0.text(), sides.1.text()))),
+                },
+                // Deleted on one side: only a changed translation keeps it
+                (Some(kept), None) | (None, Some(kept)) => {
+                    if b.is_some_and(|b| b.translation == kept.translation) {
+                        (None, HunkSource::Conflict, None)
+                    } else {
+                        (None, HunkSource::Conflict, Some((lt.clone().unwrap_or_default(), rt.clone().unwrap_or_default())))
+                    }
+                }
+                (None, None) => continue,
+            }
+        };
+
+        let start = result.len();
+        let push = |result: &mut Vec<String>, text: &str| {
+            if !result.is_empty() {
+                result.push(String::new());
+            }
+            result.extend(split_lines(text).into_iter().map(str::to_string));
+        };
+        match (&merged, &sides) {
+            (Some(text), _) => push(&mut result, text),
+            (None, Some((local_text, remote_text))) => {
+                push(&mut result, "<<<<<<< LOCAL");
+                result.extend(split_lines(local_text).into_iter().map(str::to_string));
+                result.push("=======".to_string());
+                result.extend(split_lines(remote_text).into_iter().map(str::to_string));
+                result.push(">>>>>>> REMOTE".to_string());
+            }
+            (None, None) => {}
+        }
+        if bt == lt && bt == rt {
+            continue;
+        }
+
+        // The entry's own lines, after the blank line that separates it
+        let first = if start > 0 && result.len() > start { start + 2 } else { start + 1 };
+        let result_range = if result.len() > start {
+            LineRange::new(first, result.len())
+        } else {
+            LineRange::empty()
+        };
+        let lines_of = |entry: Option<&Entry>| entry.map_or_else(LineRange::empty, |e| e.lines);
+        let status = if merged.is_none() && sides.is_some() {
+            HunkStatus::Unresolved
+        } else {
+            HunkStatus::AutoResolved
+        };
+        let (local_content, remote_content) = sides.unwrap_or((lt.unwrap_or_default(), rt.unwrap_or_default()));
+        hunks.push(MergeHunk {
+            id: hunks.len(),
+            base_range: lines_of(b),
+            local_range: lines_of(l),
+            remote_range: lines_of(r),
+            result_range,
+            source,
+            status,
+            local_content,
+            remote_content,
+            base_content: bt.unwrap_or_default(),
+        });
+    }
+
+    session.result_content = result.join("\n");
+    session.version_conflicts = find_version_conflicts(&hunks);
+    session.hunks = hunks;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "msgid \"\"\nmsgstr \"\"\n\"Language: fr\\n\"\n";
+
+    fn merge(base: &str, local: &str, remote: &str) -> MergeSession {
+        let mut session = MergeSession::for_test(base, local, remote, "po");
+        assert!(po_merge(&mut session));
+        session
+    }
+
+    #[test]
+    fn header_keeps_the_later_revision_date() {
+        let base = "msgid \"\"\nmsgstr \"\"\n\"PO-Revision-Date: 2024-01-01 10:00+0000\\n\"\n\"Last-Translator: A\\n\"\n";
+        let local = "msgid \"\"\nmsgstr \"\"\n\"PO-Revision-Date: 2024-03-01 10:00+0000\\n\"\n\"Last-Translator: B\\n\"\n";
+        let remote = "msgid \"\"\nmsgstr \"\"\n\"PO-Revision-Date: 2024-02-01 10:00+0000\\n\"\n\"Last-Translator: C\\n\"\n";
+
+        let session = merge(base, local, remote);
+        assert_eq!(
+            session.result_content,
+            "msgid \"\"\nmsgstr \"\"\n\"PO-Revision-Date: 2024-03-01 10:00+0000\\n\"\n\"Last-Translator: B\\n\""
+        );
+        assert!(session.hunks.iter().all(|h| h.status == HunkStatus::AutoResolved));
+
+        // Whichever side has it
+        let session = merge(base, remote, local);
+        assert!(session.result_content.contains("2024-03-01"));
+    }
+
+    #[test]
+    fn finished_translation_replaces_a_fuzzy_one() {
+        let base = format!("{HEADER}\n#, fuzzy\nmsgid \"Bye\"\nmsgstr \"Au revoir?\"\n");
+        let local = format!("{HEADER}\n#, fuzzy\nmsgid \"Bye\"\nmsgstr \"Au revoir !\"\n");
+        let remote = format!("{HEADER}\nmsgid \"Bye\"\nmsgstr \"Au revoir\"\n");
+
+        let expected = format!("{HEADER}\nmsgid \"Bye\"\nmsgstr \"Au revoir\"");
+        for (local, remote) in [(&local, &remote), (&remote, &local)] {
+            let session = merge(&base, local, remote);
+            assert_eq!(session.result_content, expected);
+            assert!(session.hunks.iter().all(|h| h.status == HunkStatus::AutoResolved));
+        }
+    }
+
+    #[test]
+    fn different_finished_translations_conflict() {
+        let base = format!("{HEADER}\nmsgid \"Bye\"\nmsgstr \"\"\n");
+        let local = format!("{HEADER}\nmsgid \"Bye\"\nmsgstr \"Au revoir\"\n");
+        let remote = format!("{HEADER}\nmsgid \"Bye\"\nmsgstr \"Salut\"\n");
+
+        let session = merge(&base, &local, &remote);
+        let hunk = session.hunks.iter().find(|h| h.status == HunkStatus::Unresolved).unwrap();
+        assert_eq!(hunk.local_content, "msgid \"Bye\"\nmsgstr \"Au revoir\"");
+        assert_eq!(hunk.remote_content, "msgid \"Bye\"\nmsgstr \"Salut\"");
+    }
+
+    #[test]
+    fn merged_references_wrap_at_the_gettext_width() {
+        let base = format!("{HEADER}\n#: src/file_a.c:100\nmsgid \"Hello\"\nmsgstr \"Bonjour\"\n");
+        let local = format!("{HEADER}\n#: src/file_a.c:100 src/file_b.c:100 src/file_c.c:100\nmsgid \"Hello\"\nmsgstr \"Bonjour\"\n");
+        let remote = format!(
+            "{HEADER}\n#: src/file_a.c:100 src/file_d.c:100 src/file_e.c:100\n#: src/file_f.c:100\nmsgid \"Hello\"\nmsgstr \"Salut\"\n"
+        );
+
+        let session = merge(&base, &local, &remote);
+        assert_eq!(
+            session.result_content,
+            format!(
+                "{HEADER}\n#: src/file_a.c:100 src/file_b.c:100 src/file_c.c:100 src/file_d.c:100\n#: src/file_e.c:100 src/file_f.c:100\nmsgid \"Hello\"\nmsgstr \"Salut\""
+            )
+        );
+        assert!(reference_lines(&vec!["src/file_a.c:100".to_string(); 8]).iter().all(|l| l.len() <= WRAP_WIDTH));
+    }
+
+    #[test]
+    fn comment_edits_that_cannot_be_combined_conflict() {
+        let base = format!("{HEADER}\n# Greeting\nmsgid \"Hello\"\nmsgstr \"Bonjour\"\n");
+        let local = base.replace("# Greeting", "# Greeting on the home page");
+        let remote = base.replace("# Greeting", "# Greeting, keep short").replace("Bonjour\"", "Salut\"");
+
+        let session = merge(&base, &local, &remote);
+        let hunk = session.hunks.iter().find(|h| h.status == HunkStatus::Unresolved).unwrap();
+        // The translation is merged on both sides of the conflict
+        assert_eq!(hunk.local_content, "# Greeting on the home page\nmsgid \"Hello\"\nmsgstr \"Salut\"");
+        assert_eq!(hunk.remote_content, "# Greeting, keep short\nmsgid \"Hello\"\nmsgstr \"Salut\"");
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Language, Node, Query, QueryCursor, Tree};
+
+use crate::config::Config;
+
+/// A `manifest.toml` entry describing one language's profile.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct ProfileEntry {
+    /// Query file, `<language>.scm` by default.
+    query: Option<String>,
+    /// Profiles whose queries are prepended to this one.
+    inherits: Vec<String>,
+}
+
+const BUNDLED_MANIFEST: &str = include_str!("../../profiles/manifest.toml");
+
+const BUNDLED_QUERIES: &[(&str, &str)] = &[
+    ("rust.scm", include_str!("../../profiles/rust.scm")),
+    ("typescript.scm", include_str!("../../profiles/typescript.scm")),
+    ("javascript.scm", include_str!("../../profiles/javascript.scm")),
+    ("python.scm", include_str!("../../profiles/python.scm")),
+    ("go.scm", include_str!("../../profiles/go.scm")),
+    ("java.scm", include_str!("../../profiles/java.scm")),
+    ("c.scm", include_str!("../../profiles/c.scm")),
+    ("cpp.scm", include_str!("../../profiles/cpp.scm")),
+    ("json.scm", include_str!("../../profiles/json.scm")),
+    ("toml.scm", include_str!("../../profiles/toml.scm")),
+    ("yaml.scm", include_str!("../../profiles/yaml.scm")),
+    ("bash.scm", include_str!("../../profiles/bash.scm")),
+    ("css.scm", include_str!("../../profiles/css.scm")),
+    ("html.scm", include_str!("../../profiles/html.scm")),
+    ("ruby.scm", include_str!("../../profiles/ruby.scm")),
+    ("csharp.scm", include_str!("../../profiles/csharp.scm")),
+    ("kotlin.scm", include_str!("../../profiles/kotlin.scm")),
+    ("php.scm", include_str!("../../profiles/php.scm")),
+];
+
+/// Profiles nest through `inherits` at most this deep, which also stops cycles.
+const MAX_INHERIT_DEPTH: usize = 8;
+
+/// Directory holding the user's profiles, next to the config file.
+pub fn user_dir() -> Option<PathBuf> {
+    Some(Config::path()?.parent()?.join("profiles"))
+}
+
+fn parse_manifest(content: &str, origin: &str) -> BTreeMap<String, ProfileEntry> {
+    toml::from_str(content).unwrap_or_else(|e| {
+        eprintln!("Warning: Invalid profile manifest {}: {}", origin, e);
+        BTreeMap::new()
+    })
+}
+
+fn manifest_entry(lang: &str) -> Option<ProfileEntry> {
+    let user_dir = user_dir();
+    if let Some(dir) = &user_dir {
+        let path = dir.join("manifest.toml");
+        if let Ok(content) = fs::read_to_string(&path) {
+            let manifest = parse_manifest(&content, &path.display().to_string());
+            if let Some(entry) = manifest.get(lang) {
+                return Some(entry.clone());
+            }
+        }
+    }
+
+    if let Some(entry) = parse_manifest(BUNDLED_MANIFEST, "(bundled)").remove(lang) {
+        return Some(entry);
+    }
+
+    // A bare `<language>.scm` in the user directory needs no manifest entry
+    user_dir
+        .filter(|dir| dir.join(format!("{}.scm", lang)).exists())
+        .map(|_| ProfileEntry::default())
+}
+
+/// Assemble the query text for a language, inherited profiles first.
+fn query_source(lang: &str, depth: usize) -> Option<String> {
+    if depth > MAX_INHERIT_DEPTH {
+        eprintln!("Warning: Merge profile {} inherits too deeply", lang);
+        return None;
+    }
+    let entry = manifest_entry(lang)?;
+    let file = entry.query.unwrap_or_else(|| format!("{}.scm", lang));
+
+    let own = user_dir()
+        .and_then(|dir| fs::read_to_string(dir.join(&file)).ok())
+        .or_else(|| {
+            BUNDLED_QUERIES
+                .iter()
+                .find(|(name, _)| *name == file)
+                .map(|(_, query)| query.to_string())
+        });
+    let own = match own {
+        Some(q) => q,
+        None => {
+            eprintln!("Warning: Merge profile {} not found for {}", file, lang);
+            return None;
+        }
+    };
+
+    let mut source = String::new();
+    for parent in &entry.inherits {
+        match query_source(parent, depth + 1) {
+            Some(parent_source) => source.push_str(&parent_source),
+            None => {
+                eprintln!("Warning: Merge profile {} inherits missing profile {}", lang, parent);
+                return None;
+            }
+        }
+        source.push('\n');
+    }
+    source.push_str(&own);
+    Some(source)
+}
+
+/// A language's merge profile: a query whose captures mark units, their names,
+/// and the containers whose units are merged one by one.
+pub struct Profile {
+    query: Query,
+}
+
+/// What a profile says about the nodes of one parsed tree, keyed by node id.
+#[derive(Debug, Default)]
+pub struct Roles {
+    containers: HashSet<usize>,
+    unordered: HashSet<usize>,
+    /// Identity of each unit, `None` for units without a @name.
+    units: HashMap<usize, Option<String>>,
+}
+
+impl Profile {
+    /// Load the profile for a language, or `None` if there is none or its query
+    /// doesn't compile against the grammar. Profiles are compiled once per run.
+    pub fn load(lang: &str, language: &Language) -> Option<Arc<Profile>> {
+        static PROFILES: OnceLock<Mutex<HashMap<String, Option<Arc<Profile>>>>> = OnceLock::new();
+        let mut profiles = PROFILES.get_or_init(Default::default).lock();
+        if let Some(profile) = profiles.get(lang) {
+            return profile.clone();
+        }
+
+        let profile = query_source(lang, 0).and_then(|source| match Query::new(language, &source) {
+            Ok(query) => Some(Arc::new(Profile { query })),
+            Err(e) => {
+                eprintln!("Warning: Invalid merge profile for {}: {}", lang, e);
+                None
+            }
+        });
+        profiles.insert(lang.to_string(), profile.clone());
+        profile
+    }
+
+    /// Run the profile's query over a tree.
+    pub fn roles(&self, tree: &Tree, source: &str) -> Roles {
+        let mut roles = Roles::default();
+        let capture_names = self.query.capture_names();
+        // Unit node id -> (pattern index, name nodes) from the winning pattern
+        let mut units: HashMap<usize, (usize, Vec<Node>)> = HashMap::new();
+
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&self.query, tree.root_node(), source.as_bytes());
+        while let Some(m) = matches.next() {
+            let mut unit = None;
+            let mut names = Vec::new();
+            for capture in m.captures {
+                match capture_names[capture.index as usize] {
+                    "unit" => unit = Some(capture.node),
+                    "name" => names.push(capture.node),
+                    "container" => {
+                        roles.containers.insert(capture.node.id());
+                    }
+                    "unordered" => {
+                        roles.containers.insert(capture.node.id());
+                        roles.unordered.insert(capture.node.id());
+                    }
+                    _ => {}
+                }
+            }
+            let Some(unit) = unit else { continue };
+
+            // Later patterns override earlier ones; several matches of one pattern
+            // (a declaration listing several names) add up
+            let entry = units.entry(unit.id()).or_insert((m.pattern_index, Vec::new()));
+            if m.pattern_index > entry.0 {
+                *entry = (m.pattern_index, names);
+            } else if m.pattern_index == entry.0 {
+                entry.1.extend(names);
+            }
+        }
+
+        // Take the kind from the innermost unit around the name rather than from a
+        // wrapper like `export_statement`
+        for (unit_id, (_, mut names)) in units.iter().map(|(id, u)| (*id, u.clone())) {
+            if names.is_empty() {
+                roles.units.insert(unit_id, None);
+                continue;
+            }
+            names.sort_by_key(|n| n.start_byte());
+            names.dedup_by_key(|n| n.id());
+
+            let mut holder = names[0];
+            while !units.contains_key(&holder.id()) {
+                match holder.parent() {
+                    Some(parent) => holder = parent,
+                    None => break,
+                }
+            }
+            let name = names
+                .iter()
+                .map(|n| &source[n.byte_range()])
+                .collect::<Vec<_>>()
+                .join(" ");
+            roles.units.insert(unit_id, Some(format!("{}:{}", holder.kind(), name)));
+        }
+
+        roles
+    }
+}
+
+impl Roles {
+    /// Whether the node's children are merged one by one.
+    pub fn is_container(&self, node: Node) -> bool {
+        self.containers.contains(&node.id())
+    }
+
+    /// Whether the node's children may be reordered freely.
+    pub fn is_unordered(&self, node: Node) -> bool {
+        self.unordered.contains(&node.id())
+    }
+
+    /// The identity of a named unit.
+    pub fn unit_identity(&self, node: Node) -> Option<&str> {
+        self.units.get(&node.id())?.as_deref()
+    }
+}
@@ -0,0 +1,318 @@
+use std::slice;
+
+use super::hunk::{HunkSource, HunkStatus, LineRange, MergeHunk, MergeSession};
+use super::sequence::{merge_regions, merge_sequences};
+use super::three_way::split_lines;
+use super::version::find_version_conflicts;
+
+/// Line prefixes that open and close a fenced code block.
+const FENCES: &[&str] = &["```", "~~~"];
+
+/// Characters that may follow a sentence's final punctuation, such as closing
+/// quotes or emphasis.
+const CLOSERS: &[char] = &['"', '\'', ')', ']', '*', '_', '`'];
+
+/// A block of a prose document: a heading, paragraph, list item or fenced code
+/// block, with the blank lines before it.
+#[derive(Debug, Clone)]
+struct Block {
+    text: String,
+    /// Whether this is a fenced code block, which is merged whole.
+    code: bool,
+    lines: LineRange,
+}
+
+/// Check whether a language is merged as prose. Other text files opt in with
+/// a `merge=prose` gitattribute, since plain text is as often data or config.
+pub fn is_prose_language(language: &str) -> bool {
+    language == "markdown"
+}
+
+pub fn fence_of(line: &str) -> Option<&'static str> {
+    let trimmed = line.trim_start();
+    FENCES.iter().copied().find(|f| trimmed.starts_with(f))
+}
+
+fn is_heading(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
+
+fn is_list_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if ["- ", "* ", "+ "].iter().any(|m| trimmed.starts_with(m)) {
+        return true;
+    }
+    let digits = trimmed.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && (trimmed[digits..].starts_with(". ") || trimmed[digits..].starts_with(") "))
+}
+
+/// Split a document into blocks. A paragraph or list item runs until a blank
+/// line or the start of another block; blank lines go with the block after
+/// them, or with the last block at the end of the document.
+fn prose_blocks(content: &str) -> Vec<Block> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let start = i;
+        while i < lines.len() && lines[i].trim().is_empty() {
+            i += 1;
+        }
+        if i == lines.len() {
+            if let Some(last) = blocks.last_mut() {
+                last.text.push('\n');
+                last.text.push_str(&lines[start..].join("\n"));
+                last.lines.end = i;
+                break;
+            }
+        }
+
+        let fence = lines.get(i).and_then(|l| fence_of(l));
+        if let Some(fence) = fence {
+            i += 1;
+            while i < lines.len() && !lines[i].trim_start().starts_with(fence) {
+                i += 1;
+            }
+            i = (i + 1).min(lines.len());
+        } else if i < lines.len() {
+            let heading = is_heading(lines[i]);
+            i += 1;
+            while !heading
+                && i < lines.len()
+                && !lines[i].trim().is_empty()
+                && !is_heading(lines[i])
+                && !is_list_item(lines[i])
+                && fence_of(lines[i]).is_none()
+            {
+                i += 1;
+            }
+        }
+        blocks.push(Block {
+            text: lines[start..i].join("\n"),
+            code: fence.is_some(),
+            lines: LineRange::new(start + 1, i),
+        });
+    }
+    blocks
+}
+
+/// Split prose into sentences and lines, with the whitespace after each as a
+/// piece of its own, so edits to neighbouring sentences don't overlap.
+fn sentences(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if !c.is_whitespace() {
+            continue;
+        }
+        let before = &text[start..i];
+        let ends_sentence = before.trim_end_matches(CLOSERS).ends_with(['.', '!', '?']);
+        if !(before.is_empty() || ends_sentence || c == '\n') {
+            continue;
+        }
+        let mut end = i + c.len_utf8();
+        while let Some((j, w)) = chars.next_if(|(_, w)| w.is_whitespace()) {
+            end = j + w.len_utf8();
+        }
+        if !before.is_empty() {
+            pieces.push(before);
+        }
+        pieces.push(&text[i..end]);
+        start = end;
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+/// The pieces a run of blocks is merged in: sentences, lines and whole code blocks.
+fn pieces(blocks: &[Block]) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            pieces.push("\n");
+        }
+        if block.code {
+            pieces.push(block.text.as_str());
+        } else {
+            pieces.extend(sentences(&block.text));
+        }
+    }
+    pieces
+}
+
+/// The text of a run of blocks and the lines it spans.
+fn side_of(blocks: &[Block]) -> (String, LineRange) {
+    let text = blocks.iter().map(|b| b.text.as_str()).collect::<Vec<_>>().join("\n");
+    let lines = match (blocks.first(), blocks.last()) {
+        (Some(first), Some(last)) => LineRange::new(first.lines.start, last.lines.end),
+        _ => LineRange::empty(),
+    };
+    (text, lines)
+}
+
+/// Merge one changed run of blocks into the result lines, sentence by sentence
+/// if both sides changed it, and add its hunk.
+fn merge_blocks(base: &[Block], local: &[Block], remote: &[Block], result: &mut Vec<String>, hunks: &mut Vec<MergeHunk>) {
+    let (base_content, base_range) = side_of(base);
+    let (local_content, local_range) = side_of(local);
+    let (remote_content, remote_range) = side_of(remote);
+
+    let merged = if local_content == base_content {
+        Some((remote_content.clone(), HunkSource::Remote))
+    } else if remote_content == base_content {
+        Some((local_content.clone(), HunkSource::Local))
+    } else if local_content == remote_content {
+        Some((local_content.clone(), HunkSource::Both))
+    } else {
+        merge_sequences(&pieces(base), &pieces(local), &pieces(remote))
+            .map(|pieces| (pieces.concat(), HunkSource::Conflict))
+    };
+
+    let start = result.len();
+    match &merged {
+        Some((text, _)) => result.extend(split_lines(text).into_iter().map(str::to_string)),
+        None => {
+            result.push("<<<<<<< LOCAL".to_string());
+            result.extend(split_lines(&local_content).into_iter().map(str::to_string));
+            result.push("=======".to_string());
+            result.extend(split_lines(&remote_content).into_iter().map(str::to_string));
+            result.push(">>>>>>> REMOTE".to_string());
+        }
+    }
+    if local_content == base_content && remote_content == base_content {
+        return;
+    }
+
+    let (source, status) = merged.map_or((HunkSource::Conflict, HunkStatus::Unresolved), |(_, source)| {
+        (source, HunkStatus::AutoResolved)
+    });
+    let result_range = if result.len() > start {
+        LineRange::new(start + 1, result.len())
+    } else {
+        LineRange::empty()
+    };
+    hunks.push(MergeHunk {
+        id: hunks.len(),
+        base_range,
+        local_range,
+        remote_range,
+        result_range,
+        source,
+        status,
+        local_content,
+        remote_content,
+        base_content,
+    });
+}
+
+/// Merge Markdown or other prose block by block (headings, paragraphs, list
+/// items, code blocks), and blocks both sides edited sentence by sentence.
+/// Replaces the session's hunks with one per changed run of blocks, so only the
+/// blocks where both sides changed the same sentence conflict.
+pub fn prose_merge(session: &mut MergeSession) {
+    let base = prose_blocks(&session.base_content);
+    let local = prose_blocks(&session.local_content);
+    let remote = prose_blocks(&session.remote_content);
+    let texts = |blocks: &[Block]| blocks.iter().map(|b| b.text.clone()).collect::<Vec<_>>();
+
+    let mut result: Vec<String> = Vec::new();
+    let mut hunks: Vec<MergeHunk> = Vec::new();
+    let unchanged = |blocks: &[Block], result: &mut Vec<String>| {
+        for block in blocks {
+            result.extend(split_lines(&block.text).into_iter().map(str::to_string));
+        }
+    };
+    let mut pos = 0;
+    for region in merge_regions(&texts(&base), &texts(&local), &texts(&remote)) {
+        unchanged(&base[pos..region.base.start], &mut result);
+        pos = region.base.end;
+        let (b, l, r) = (&base[region.base], &local[region.local], &remote[region.remote]);
+        // Blocks edited in place on both sides are merged one by one
+        if !b.is_empty() && b.len() == l.len() && b.len() == r.len() {
+            for ((b, l), r) in b.iter().zip(l).zip(r) {
+                merge_blocks(slice::from_ref(b), slice::from_ref(l), slice::from_ref(r), &mut result, &mut hunks);
+            }
+        } else {
+            merge_blocks(b, l, r, &mut result, &mut hunks);
+        }
+    }
+    unchanged(&base[pos..], &mut result);
+
+    session.result_content = result.join("\n");
+    session.version_conflicts = find_version_conflicts(&hunks);
+    session.hunks = hunks;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge(base: &str, local: &str, remote: &str) -> MergeSession {
+        let mut session = MergeSession::for_test(base, local, remote, "markdown");
+        prose_merge(&mut session);
+        session
+    }
+
+    #[test]
+    fn only_markdown_is_prose_by_default() {
+        assert!(is_prose_language("markdown"));
+        assert!(!is_prose_language("plaintext"));
+    }
+
+    #[test]
+    fn different_sentences_of_a_paragraph_merge() {
+        let base = "# Title\n\nFirst sentence. Second sentence. Third sentence.\n\nLast paragraph.";
+        let local = "# Title\n\nFirst sentence. Second sentence, edited. Third sentence.\n\nLast paragraph.";
+        let remote = "# Title\n\nFirst sentence. Second sentence. Third sentence, edited.\n\nLast paragraph.";
+
+        let session = merge(base, local, remote);
+        assert_eq!(
+            session.result_content,
+            "# Title\n\nFirst sentence. Second sentence, edited. Third sentence, edited.\n\nLast paragraph."
+        );
+        assert_eq!(session.hunks.len(), 1);
+        assert_eq!(session.hunks[0].status, HunkStatus::AutoResolved);
+    }
+
+    #[test]
+    fn reflowed_paragraph_merges_with_an_edit() {
+        let base = "Intro.\n\nOne sentence here. Another one there. A third one.";
+        let local = "Intro.\n\nOne sentence here.\nAnother one there.\nA third one.";
+        let remote = "Intro.\n\nOne sentence here. Another one over there. A third one.";
+
+        let session = merge(base, local, remote);
+        assert_eq!(session.result_content, "Intro.\n\nOne sentence here.\nAnother one over there.\nA third one.");
+        assert!(session.hunks.iter().all(|h| h.status == HunkStatus::AutoResolved));
+    }
+
+    #[test]
+    fn same_sentence_edited_differently_conflicts() {
+        let base = "# Title\n\nKeep this. Change this.\n\nOther paragraph.";
+        let local = "# Title\n\nKeep this. Change this locally.\n\nOther paragraph, edited.";
+        let remote = "# Title\n\nKeep this. Change this remotely.\n\nOther paragraph.";
+
+        let session = merge(base, local, remote);
+        assert_eq!(
+            session.result_content,
+            "# Title\n<<<<<<< LOCAL\n\nKeep this. Change this locally.\n=======\n\nKeep this. Change this remotely.\n>>>>>>> REMOTE\n\nOther paragraph, edited."
+        );
+        let statuses: Vec<HunkStatus> = session.hunks.iter().map(|h| h.status).collect();
+        assert_eq!(statuses, [HunkStatus::Unresolved, HunkStatus::AutoResolved]);
+    }
+
+    #[test]
+    fn code_blocks_merge_whole() {
+        let base = "Text.\n\n```rust\nlet a = 1;\nlet b = 2;\n```";
+        let local = "Text.\n\n```rust\nlet a = 10;\nlet b = 2;\n```";
+        let remote = "Text, edited.\n\n```rust\nlet a = 1;\nlet b = 20;\n```";
+
+        let session = merge(base, local, remote);
+        assert!(session.result_content.starts_with("Text, edited.\n<<<<<<< LOCAL\n"));
+        let conflict = session.hunks.iter().find(|h| h.status == HunkStatus::Unresolved).unwrap();
+        assert_eq!(conflict.local_content, "\n```rust\nlet a = 10;\nlet b = 2;\n```");
+        assert_eq!(conflict.remote_content, "\n```rust\nlet a = 1;\nlet b = 20;\n```");
+    }
+}
@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+
+use super::hunk::HunkSource;
+
+/// An identifier one side renamed everywhere it appears in the file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentifierRename {
+    /// The side that renamed it, `Local` or `Remote`.
+    pub side: HunkSource,
+    pub from: String,
+    pub to: String,
+    /// How many tokens were renamed.
+    pub occurrences: usize,
+    /// Set once the rename has been replayed onto the other side.
+    pub replayed: bool,
+}
+
+#[cfg(feature = "tree-sitter")]
+mod inner {
+    use std::collections::{HashMap, HashSet};
+    use std::mem;
+    use similar::{capture_diff_slices, Algorithm, ChangeTag, DiffOp, TextDiff};
+    use tree_sitter::Tree;
+    use super::IdentifierRename;
+    use crate::merge::grammar::{grammar_name, session_language};
+    use crate::merge::hunk::{HunkSource, MergeSession};
+    use crate::merge::tokens::leaf_tokens;
+
+    /// Find identifiers `side` renamed relative to `base`: every changed occurrence
+    /// maps to the same new name, the old name is gone from `side`, and the new name
+    /// was not used in `base`.
+    fn detect(base: &str, base_tree: &Tree, side: &str, side_tree: &Tree) -> Vec<(String, String, usize)> {
+        let base_tokens = leaf_tokens(base_tree, base);
+        let side_tokens = leaf_tokens(side_tree, side);
+        let base_texts: Vec<&str> = base_tokens.iter().map(|t| t.text).collect();
+        let side_texts: Vec<&str> = side_tokens.iter().map(|t| t.text).collect();
+
+        // Old name -> new name -> occurrences, from same-length replaced runs
+        let mut pairs: HashMap<&str, HashMap<&str, usize>> = HashMap::new();
+        for op in capture_diff_slices(Algorithm::Myers, &base_texts, &side_texts) {
+            if let DiffOp::Replace { old_index, old_len, new_index, new_len } = op {
+                if old_len != new_len {
+                    continue;
+                }
+                for i in 0..old_len {
+                    let (old, new) = (&base_tokens[old_index + i], &side_tokens[new_index + i]);
+                    if old.identifier && new.identifier {
+                        *pairs.entry(old.text).or_default().entry(new.text).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        let base_names: HashSet<&str> =
+            base_tokens.iter().filter(|t| t.identifier).map(|t| t.text).collect();
+        let side_names: HashSet<&str> =
+            side_tokens.iter().filter(|t| t.identifier).map(|t| t.text).collect();
+
+        let mut renames: Vec<(String, String, usize)> = pairs
+            .into_iter()
+            .filter(|(from, targets)| targets.len() == 1 && !side_names.contains(from))
+            .filter_map(|(from, targets)| {
+                let (to, occurrences) = targets.into_iter().next()?;
+                (!base_names.contains(to)).then(|| (from.to_string(), to.to_string(), occurrences))
+            })
+            .collect();
+        renames.sort();
+        renames
+    }
+
+    /// Detect the identifiers each side renamed throughout the file.
+    pub fn detect_identifier_renames(session: &MergeSession) -> Vec<IdentifierRename> {
+        let Some(language) = session_language(session) else {
+            return Vec::new();
+        };
+        if session.base_content.is_empty() {
+            return Vec::new();
+        }
+        let grammar = grammar_name(session);
+        let Some(base_tree) = session.trees.parse(grammar, &language, &session.base_content) else {
+            return Vec::new();
+        };
+
+        let mut renames = Vec::new();
+        for (side, content) in [
+            (HunkSource::Local, &session.local_content),
+            (HunkSource::Remote, &session.remote_content),
+        ] {
+            let Some(tree) = session.trees.parse(grammar, &language, content) else {
+                continue;
+            };
+            for (from, to, occurrences) in detect(&session.base_content, &base_tree, content, &tree) {
+                renames.push(IdentifierRename { side, from, to, occurrences, replayed: false });
+            }
+        }
+        renames
+    }
+
+    /// Rename identifier tokens in the lines of `content` that differ from `base`.
+    fn rename_in_changed_lines(base: &str, content: &str, tree: &Tree, renames: &[(&str, &str)]) -> String {
+        let changed_rows: HashSet<usize> = TextDiff::from_lines(base, content)
+            .iter_all_changes()
+            .filter(|c| c.tag() == ChangeTag::Insert)
+            .filter_map(|c| c.new_index())
+            .collect();
+
+        let mut result = String::with_capacity(content.len());
+        let mut copied = 0;
+        for token in leaf_tokens(tree, content) {
+            if !token.identifier || !changed_rows.contains(&token.row) {
+                continue;
+            }
+            if let Some((_, to)) = renames.iter().find(|(from, _)| *from == token.text) {
+                result.push_str(&content[copied..token.start_byte]);
+                result.push_str(to);
+                copied = token.end_byte();
+            }
+        }
+        result.push_str(&content[copied..]);
+        result
+    }
+
+    /// Replay renames onto the other side's changed regions and merge again with
+    /// `merge`, so code the other side added with the old names picks up the new
+    /// ones. The merge runs on renamed copies of the sides, and its hunks hold the
+    /// renamed lines, so accepting either side keeps the new names; the session
+    /// keeps the real files for the side panes. Any hunk resolutions are
+    /// discarded. Refuses if the sides renamed an identifier to different names.
+    pub fn replay_identifier_renames(
+        session: &mut MergeSession,
+        renames: &[IdentifierRename],
+        merge: impl FnOnce(&mut MergeSession),
+    ) -> Result<(), String> {
+        let Some(language) = session_language(session) else {
+            return Err(format!("Cannot replay renames for {}", session.language));
+        };
+        let grammar = grammar_name(session);
+
+        let all_renames = || renames.iter().chain(&session.identifier_renames);
+        for rename in renames {
+            let other = all_renames().find(|r| r.side != rename.side && r.from == rename.from && r.to != rename.to);
+            if let Some(other) = other {
+                return Err(format!(
+                    "Both sides renamed {}, to {} and to {}",
+                    rename.from, rename.to, other.to
+                ));
+            }
+        }
+
+        let mut renamed = (session.local_content.clone(), session.remote_content.clone());
+        let mut replayed_any = false;
+        for (side, target) in [(HunkSource::Local, HunkSource::Remote), (HunkSource::Remote, HunkSource::Local)] {
+            let pairs: Vec<(&str, &str)> = renames
+                .iter()
+                .filter(|r| r.side == side)
+                .map(|r| (r.from.as_str(), r.to.as_str()))
+                .collect();
+            if pairs.is_empty() {
+                continue;
+            }
+            let content = match target {
+                HunkSource::Local => &mut renamed.0,
+                _ => &mut renamed.1,
+            };
+            if let Some(tree) = session.trees.parse(grammar, &language, content) {
+                *content = rename_in_changed_lines(&session.base_content, content, &tree, &pairs);
+                replayed_any = true;
+            }
+        }
+        if !replayed_any {
+            return Err("None of the renamed identifiers appear in the other side's changes".to_string());
+        }
+
+        // Renaming tokens keeps every line in place, so the hunks' ranges hold
+        // for the real files too
+        let local = mem::replace(&mut session.local_content, renamed.0);
+        let remote = mem::replace(&mut session.remote_content, renamed.1);
+        merge(session);
+        session.local_content = local;
+        session.remote_content = remote;
+
+        session.renames.clear();
+        for rename in session.identifier_renames.iter_mut() {
+            if renames.iter().any(|r| r.side == rename.side && r.from == rename.from && r.to == rename.to) {
+                rename.replayed = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "tree-sitter"))]
+mod inner {
+    use super::IdentifierRename;
+    use crate::merge::hunk::MergeSession;
+
+    pub fn detect_identifier_renames(_session: &MergeSession) -> Vec<IdentifierRename> {
+        Vec::new()
+    }
+
+    pub fn replay_identifier_renames(
+        session: &mut MergeSession,
+        _renames: &[IdentifierRename],
+        _merge: impl FnOnce(&mut MergeSession),
+    ) -> Result<(), String> {
+        Err(format!("Cannot replay renames for {}", session.language))
+    }
+}
+
+pub use inner::{detect_identifier_renames, replay_identifier_renames};
+
+
+#[cfg(all(test, feature = "lang-rust"))]
+mod tests {
+    use super::*;
+    use crate::merge::hunk::{HunkStatus, MergeSession};
+    use crate::merge::resolver::resolve_hunk;
+    use crate::merge::three_way::three_way_merge;
+
+    const BASE: &str = "fn total(xs: &[i32]) -> i32 {\n    xs.iter().sum()\n}\n\nfn main() {\n    let a = total(&[1]);\n    println!(\"{}\", a);\n}\n";
+
+    fn remerge(session: &mut MergeSession) {
+        let (hunks, result_content) =
+            three_way_merge(&session.base_content, &session.local_content, &session.remote_content);
+        session.hunks = hunks;
+        session.result_content = result_content;
+    }
+
+    #[test]
+    fn accepted_sides_keep_the_replayed_names() {
+        let local = BASE.replace("total", "sum_all");
+        // Remote changes the call local renamed, and adds another
+        let remote = BASE.replace("total(&[1]);", "total(&[1, 2]);\n    let b = total(&[3]);");
+        let mut session = MergeSession::for_test(BASE, &local, &remote, "rust");
+        session.identifier_renames = detect_identifier_renames(&session);
+        let rename = session.identifier_renames.iter().find(|r| r.from == "total").unwrap().clone();
+        assert_eq!((rename.side, rename.to.as_str()), (HunkSource::Local, "sum_all"));
+
+        replay_identifier_renames(&mut session, &[rename], remerge).unwrap();
+        assert!(session.identifier_renames[0].replayed);
+        // The side panes still show the real files
+        assert_eq!(session.remote_content, remote);
+
+        let conflict = session.hunks.iter().find(|h| h.status == HunkStatus::Unresolved).unwrap().id;
+        resolve_hunk(&mut session, conflict, HunkStatus::AcceptedRemote);
+        assert!(!session.result_content.contains("total"));
+        assert!(session.result_content.contains("let a = sum_all(&[1, 2]);\n    let b = sum_all(&[3]);"));
+    }
+
+    #[test]
+    fn renaming_one_identifier_two_ways_is_refused() {
+        let local = BASE.replace("total", "sum_all");
+        let remote = BASE.replace("total", "add_up").replace("&[1]", "&[1, 2]");
+        let mut session = MergeSession::for_test(BASE, &local, &remote, "rust");
+        session.identifier_renames = detect_identifier_renames(&session);
+        assert_eq!(session.identifier_renames.len(), 2);
+
+        let local_rename: Vec<IdentifierRename> =
+            session.identifier_renames.iter().filter(|r| r.side == HunkSource::Local).cloned().collect();
+        let result_before = session.result_content.clone();
+        let error = replay_identifier_renames(&mut session, &local_rename, remerge).unwrap_err();
+        assert_eq!(error, "Both sides renamed total, to sum_all and to add_up");
+        assert_eq!(session.result_content, result_before);
+        assert!(session.identifier_renames.iter().all(|r| !r.replayed));
+    }
+}
@@ -1,5 +1,6 @@
-use super::hunk::{HunkSource, HunkStatus, MergeSession};
-use super::three_way::rebuild_result;
+use super::hunk::{HunkSource, HunkStatus, MergeHunk, MergeSession};
+use super::three_way::{rebuild_result, split_lines};
+use super::version;
 
 /// Resolve a single hunk by accepting local, remote, or both content.
 pub fn resolve_hunk(session: &mut MergeSession, hunk_id: usize, status: HunkStatus) {
@@ -20,19 +21,44 @@ pub fn resolve_hunk(session: &mut MergeSession, hunk_id: usize, status: HunkStat
                 format!("{}\n{}", hunk.local_content, hunk.remote_content)
             }
         }
+        HunkStatus::AcceptedHigherVersion | HunkStatus::AcceptedLowerVersion => {
+            let pick = match version::pick_for_status(status) {
+                Some(p) => p,
+                None => return,
+            };
+            match version::pick_versions(&hunk.local_content, &hunk.remote_content, pick) {
+                Some((content, chosen)) => {
+                    version::record_choice(session, hunk_id, chosen);
+                    content
+                }
+                None => return,
+            }
+        }
         _ => return,
     };
 
+    apply_resolution(session, hunk_id, &new_content, status);
+}
+
+/// Replace a hunk's region of the result with `new_content` and shift the
+/// result ranges of every hunk that follows it.
+pub fn apply_resolution(
+    session: &mut MergeSession,
+    hunk_id: usize,
+    new_content: &str,
+    status: HunkStatus,
+) {
+    let hunk = match session.hunks.iter().find(|h| h.id == hunk_id) {
+        Some(h) => h.clone(),
+        None => return,
+    };
+
     // Rebuild result content
-    let new_result = rebuild_result(&session.result_content, &hunk, &new_content);
+    let new_result = rebuild_result(&session.result_content, &hunk, new_content);
 
     // Recompute result ranges after rebuilding
     let old_line_count = hunk.result_range.line_count();
-    let new_line_count = if new_content.is_empty() {
-        0
-    } else {
-        new_content.lines().count()
-    };
+    let new_line_count = split_lines(new_content).len();
     let line_delta = new_line_count as isize - old_line_count as isize;
 
     // Update the resolved hunk
@@ -62,9 +88,42 @@ pub fn resolve_hunk(session: &mut MergeSession, hunk_id: usize, status: HunkStat
         }
     }
 
+    session
+        .trees
+        .edit_result_lines(&session.result_content, &new_result, hunk.result_range);
     session.result_content = new_result;
 }
 
+/// Run `merge_fn` over every unresolved conflict hunk and apply the content it
+/// returns as an auto-resolution. Returns the number of hunks resolved.
+pub fn auto_resolve_conflicts<F>(session: &mut MergeSession, mut merge_fn: F) -> usize
+where
+    F: FnMut(&MergeSession, &MergeHunk) -> Option<String>,
+{
+    let conflict_ids: Vec<usize> = session
+        .hunks
+        .iter()
+        .filter(|h| h.source == HunkSource::Conflict && h.status == HunkStatus::Unresolved)
+        .map(|h| h.id)
+        .collect();
+
+    let mut resolved_count = 0;
+    for hunk_id in conflict_ids {
+        // Earlier resolutions shift result ranges, so re-read the hunk each time
+        let hunk = match session.hunks.iter().find(|h| h.id == hunk_id) {
+            Some(h) => h.clone(),
+            None => continue,
+        };
+
+        if let Some(new_content) = merge_fn(session, &hunk) {
+            apply_resolution(session, hunk_id, &new_content, HunkStatus::AutoResolved);
+            resolved_count += 1;
+        }
+    }
+
+    resolved_count
+}
+
 /// Auto-resolve all hunks that aren't true conflicts.
 pub fn auto_resolve_non_conflicts(session: &mut MergeSession) {
     // For now, auto-resolve doesn't handle true conflicts.
@@ -0,0 +1,180 @@
+use std::hash::Hash;
+use std::ops::Range;
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+
+/// A change one side made to the base sequence: `base[start..end]` replaced by `items`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Edit<T> {
+    start: usize,
+    end: usize,
+    items: Vec<T>,
+}
+
+impl<T> Edit<T> {
+    fn is_insertion(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Extract the edits that turn `base` into `side`.
+fn side_edits<T: Eq + Hash + Ord + Clone>(base: &[T], side: &[T]) -> Vec<Edit<T>> {
+    capture_diff_slices(Algorithm::Myers, base, side)
+        .iter()
+        .map(|op| op.as_tag_tuple())
+        .filter(|(tag, _, _)| *tag != DiffTag::Equal)
+        .map(|(_, old, new)| Edit {
+            start: old.start,
+            end: old.end,
+            items: side[new].to_vec(),
+        })
+        .collect()
+}
+
+/// Check if two edits from different sides touch the same base items.
+fn edits_overlap<T>(a: &Edit<T>, b: &Edit<T>) -> bool {
+    match (a.is_insertion(), b.is_insertion()) {
+        (true, true) => a.start == b.start,
+        (true, false) => b.start < a.start && a.start < b.end,
+        (false, true) => a.start < b.start && b.start < a.end,
+        (false, false) => a.start < b.end && b.start < a.end,
+    }
+}
+
+/// 3-way merge of two edited copies of a sequence, item by item.
+///
+/// Edits to disjoint parts of `base` are both applied, and when both sides insert
+/// at the same position the local items come first. Returns `None` if the sides
+/// made different changes to the same base items.
+pub fn merge_sequences<T: Eq + Hash + Ord + Clone>(
+    base: &[T],
+    local: &[T],
+    remote: &[T],
+) -> Option<Vec<T>> {
+    let local_edits = side_edits(base, local);
+    let remote_edits = side_edits(base, remote);
+
+    let mut result = Vec::new();
+    let mut base_pos = 0;
+    let mut li = 0;
+    let mut ri = 0;
+
+    loop {
+        let (start, end, items): (usize, usize, Vec<T>) =
+            match (local_edits.get(li), remote_edits.get(ri)) {
+                (Some(l), Some(r)) if edits_overlap(l, r) => {
+                    li += 1;
+                    ri += 1;
+                    if l == r {
+                        (l.start, l.end, l.items.clone())
+                    } else if l.is_insertion() && r.is_insertion() {
+                        let mut items = l.items.clone();
+                        items.extend(r.items.iter().cloned());
+                        (l.start, l.end, items)
+                    } else {
+                        return None;
+                    }
+                }
+                (Some(l), Some(r)) => {
+                    if (l.start, l.end) <= (r.start, r.end) {
+                        li += 1;
+                        (l.start, l.end, l.items.clone())
+                    } else {
+                        ri += 1;
+                        (r.start, r.end, r.items.clone())
+                    }
+                }
+                (Some(l), None) => {
+                    li += 1;
+                    (l.start, l.end, l.items.clone())
+                }
+                (None, Some(r)) => {
+                    ri += 1;
+                    (r.start, r.end, r.items.clone())
+                }
+                (None, None) => break,
+            };
+
+        result.extend(base[base_pos..start].iter().cloned());
+        result.extend(items);
+        base_pos = end;
+    }
+
+    result.extend(base[base_pos..].iter().cloned());
+    Some(result)
+}
+
+/// A stretch of the base that one or both sides changed: `base[base]` became
+/// `local[local]` on one side and `remote[remote]` on the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub base: Range<usize>,
+    pub local: Range<usize>,
+    pub remote: Range<usize>,
+}
+
+/// Group the sides' edits to `base` into regions, as diff3 does: edits that
+/// touch the same base items, on either side, end up in one region. Items
+/// between regions are unchanged on both sides.
+pub fn merge_regions<T: Eq + Hash + Ord + Clone>(base: &[T], local: &[T], remote: &[T]) -> Vec<Region> {
+    let local_edits = side_edits(base, local);
+    let remote_edits = side_edits(base, remote);
+    // Items each side's edits have added so far, net of removals
+    let growth = |edits: &[Edit<T>]| -> isize {
+        edits.iter().map(|e| e.items.len() as isize - (e.end - e.start) as isize).sum()
+    };
+    let shift = |pos: usize, by: isize| (pos as isize + by) as usize;
+
+    let mut regions = Vec::new();
+    let (mut li, mut ri) = (0, 0);
+    let (mut local_shift, mut remote_shift) = (0, 0);
+    loop {
+        let first = match (local_edits.get(li), remote_edits.get(ri)) {
+            (Some(l), Some(r)) if (l.start, l.end) <= (r.start, r.end) => l,
+            (_, Some(r)) => r,
+            (Some(l), None) => l,
+            (None, None) => break,
+        };
+        let mut span = Edit { start: first.start, end: first.end, items: Vec::new() };
+        let (local_from, remote_from) = (li, ri);
+        loop {
+            let next = [(&local_edits, &mut li), (&remote_edits, &mut ri)].into_iter().find_map(|(edits, i)| {
+                let edit = edits.get(*i).filter(|e| edits_overlap(&span, e))?;
+                *i += 1;
+                Some((edit.start, edit.end))
+            });
+            let Some((start, end)) = next else { break };
+            span.start = span.start.min(start);
+            span.end = span.end.max(end);
+        }
+
+        let (local_growth, remote_growth) = (growth(&local_edits[local_from..li]), growth(&remote_edits[remote_from..ri]));
+        regions.push(Region {
+            base: span.start..span.end,
+            local: shift(span.start, local_shift)..shift(span.end, local_shift + local_growth),
+            remote: shift(span.start, remote_shift)..shift(span.end, remote_shift + remote_growth),
+        });
+        local_shift += local_growth;
+        remote_shift += remote_growth;
+    }
+    regions
+}
+
+/// Merge the order of keyed items, such as cells or rows. Like [`merge_sequences`],
+/// but when the sides moved or inserted items at the same place it falls back to
+/// local's order, with remote's new items after the item before them in remote.
+pub fn merge_order<T: Eq + Hash + Ord + Clone>(base: &[T], local: &[T], remote: &[T]) -> Vec<T> {
+    if let Some(order) = merge_sequences(base, local, remote) {
+        return order;
+    }
+    let mut order: Vec<T> = local.to_vec();
+    let mut at = 0;
+    for item in remote {
+        if let Some(pos) = order.iter().position(|o| o == item) {
+            at = pos + 1;
+        } else if !base.contains(item) {
+            order.insert(at, item.clone());
+            at += 1;
+        }
+    }
+    order
+}
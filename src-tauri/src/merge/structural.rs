@@ -1,48 +1,632 @@
-#[cfg(feature = "tree-sitter-grammars")]
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+use super::blocks::{bracket_delta, split_blocks};
+use super::data::{is_data_language, merge_data, DataMerge};
+use super::hunk::{HunkSource, HunkStatus, MergeHunk, MergeSession, MergeStrategy};
+use super::markup::{is_markup_language, merge_markup};
+use super::resolver::apply_resolution;
+use super::sequence::merge_sequences;
+use super::three_way::split_lines;
+use crate::config::DataConfig;
+
+/// A declaration one side renamed, matched up by the structural merge so the
+/// rename and the other side's edits to it could be combined.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedRename {
+    pub hunk_id: usize,
+    /// The side that renamed it, `Local` or `Remote`.
+    pub side: HunkSource,
+    pub kind: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// How the structural merge treated one node of a hunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum NodeOutcome {
+    Unchanged,
+    Local,
+    Remote,
+    /// Both sides made the same change.
+    Both,
+    /// Renamed on one side and edited on the other, merged line by line.
+    Merged,
+    Deleted,
+}
+
+/// A node the structural merge matched across base, local and remote.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchedNode {
+    pub kind: String,
+    /// The declared name, for named nodes.
+    pub name: Option<String>,
+    pub outcome: NodeOutcome,
+}
+
+/// Why the Magic Wand leaves a conflict hunk alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(not(feature = "tree-sitter"), allow(dead_code))]
+pub enum RefusalReason {
+    /// A side couldn't be parsed.
+    ParseFailure,
+    /// The conflict lies inside a single node both sides edited.
+    SpanningNode,
+    /// Both sides changed the same node differently.
+    SameNodeChanged,
+    /// One side deleted a node the other edited.
+    DeleteModify,
+    /// Both sides renamed the same node.
+    BothRenamed,
+    /// Both sides inserted or moved nodes at the same place.
+    OverlappingNodes,
+    /// The merged region would add syntax errors.
+    SyntaxErrors,
+    /// A whole-document merge changed the lines around the hunk, so its part of
+    /// the merged document can't be told apart.
+    Misaligned,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Refusal {
+    pub reason: RefusalReason,
+    /// Kind and name of the node that blocked the merge, if one did.
+    pub kind: Option<String>,
+    pub name: Option<String>,
+}
+
+impl Refusal {
+    fn new(reason: RefusalReason) -> Self {
+        Refusal { reason, kind: None, name: None }
+    }
+}
+
+/// What the Magic Wand would do with one conflict hunk.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkPreview {
+    pub hunk_id: usize,
+    /// The content the hunk would be resolved to.
+    pub proposal: Option<String>,
+    /// The status the hunk would get: `AutoResolved`, `FormattingMerged` or
+    /// `HeuristicMerged`.
+    pub status: Option<HunkStatus>,
+    pub nodes: Vec<MatchedNode>,
+    pub renames: Vec<DetectedRename>,
+    pub refusal: Option<Refusal>,
+}
+
+/// A structural resolution for one hunk.
+struct Proposal {
+    content: String,
+    status: HunkStatus,
+    nodes: Vec<MatchedNode>,
+    renames: Vec<DetectedRename>,
+}
+
+/// A syntax error tree-sitter found in the merge result.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxError {
+    /// 1-indexed line and column where the error starts.
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub message: String,
+    /// The hunk whose result range contains the error, if any.
+    pub hunk_id: Option<usize>,
+}
+
+/// A run of hunk lines owned by one sibling node, together with the blank
+/// lines and comments that precede it.
+#[derive(Debug, Clone)]
+struct Chunk {
+    identity: String,
+    /// Whether the identity comes from a declared name, so the node can be
+    /// matched up again if one side renames it.
+    named: bool,
+    text: String,
+}
+
+/// Tell apart repeated identities (overloads, several anonymous blocks).
+fn number_repeats(chunks: &mut [Chunk]) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for chunk in chunks.iter_mut() {
+        let count = seen.entry(chunk.identity.clone()).or_insert(0);
+        if *count > 0 {
+            chunk.identity = format!("{}#{}", chunk.identity, count);
+        }
+        *count += 1;
+    }
+}
+
+/// Order the nodes of an unordered container when the sides moved them around
+/// differently: local's order, minus what remote removed, with remote's new
+/// nodes placed after the node that precedes them in remote.
+fn unordered_ids(base: &[String], local: &[String], remote: &[String]) -> Vec<String> {
+    let base_set: HashSet<&String> = base.iter().collect();
+    let remote_set: HashSet<&String> = remote.iter().collect();
+
+    let mut merged: Vec<String> = local
+        .iter()
+        .filter(|id| !base_set.contains(id) || remote_set.contains(id))
+        .cloned()
+        .collect();
+    let mut insert_at = 0;
+    for id in remote {
+        if let Some(pos) = merged.iter().position(|m| m == id) {
+            insert_at = pos + 1;
+        } else if !base_set.contains(id) {
+            merged.insert(insert_at, id.clone());
+            insert_at += 1;
+        }
+    }
+    merged
+}
+
+/// Split an identity into its node kind and name.
+fn split_identity(identity: &str) -> (&str, &str) {
+    identity.split_once(':').unwrap_or((identity, ""))
+}
+
+/// Describe a chunk's node for a preview.
+fn matched_node(chunk: &Chunk, outcome: NodeOutcome) -> MatchedNode {
+    let (kind, name) = split_identity(&chunk.identity);
+    MatchedNode {
+        kind: kind.to_string(),
+        name: chunk.named.then(|| name.to_string()),
+        outcome,
+    }
+}
+
+fn refuse_at(reason: RefusalReason, chunk: &Chunk) -> Refusal {
+    let node = matched_node(chunk, NodeOutcome::Unchanged);
+    Refusal {
+        reason,
+        kind: Some(node.kind),
+        name: node.name,
+    }
+}
+
+/// Rebuild a hunk from its node chunks in base order, applying each side's
+/// edits, insertions and deletions once. Refuses if both sides changed the
+/// same node differently or one side deleted a node the other edited.
+/// In an unordered container, conflicting moves fall back to `unordered_ids`.
+/// A node in `renamed` that both sides changed is merged line by line, so a
+/// rename on one side and a body edit on the other combine.
+fn merge_chunks(
+    base: &[Chunk],
+    local: &[Chunk],
+    remote: &[Chunk],
+    unordered: bool,
+    renamed: &HashSet<String>,
+) -> Result<(String, Vec<MatchedNode>), Refusal> {
+    fn find<'a>(chunks: &'a [Chunk], identity: &str) -> Option<&'a Chunk> {
+        chunks.iter().find(|c| c.identity == identity)
+    }
+    fn text_of<'a>(chunks: &'a [Chunk], identity: &str) -> Option<&'a str> {
+        find(chunks, identity).map(|c| c.text.as_str())
+    }
+    fn identities(chunks: &[Chunk]) -> Vec<String> {
+        chunks.iter().map(|c| c.identity.clone()).collect()
+    }
+
+    for chunk in base {
+        match (text_of(local, &chunk.identity), text_of(remote, &chunk.identity)) {
+            (None, Some(r)) if r != chunk.text => return Err(refuse_at(RefusalReason::DeleteModify, chunk)),
+            (Some(l), None) if l != chunk.text => return Err(refuse_at(RefusalReason::DeleteModify, chunk)),
+            _ => {}
+        }
+    }
+
+    let (base_ids, local_ids, remote_ids) = (identities(base), identities(local), identities(remote));
+    let merged = merge_sequences(&base_ids, &local_ids, &remote_ids)
+        .or_else(|| unordered.then(|| unordered_ids(&base_ids, &local_ids, &remote_ids)))
+        .ok_or_else(|| Refusal::new(RefusalReason::OverlappingNodes))?;
+
+    // One node on every side means the conflict sits inside it
+    let spanning = base.len() <= 1 && local.len() <= 1 && remote.len() <= 1;
+    let same_node = |chunk: &Chunk| {
+        let reason = if spanning { RefusalReason::SpanningNode } else { RefusalReason::SameNodeChanged };
+        refuse_at(reason, chunk)
+    };
+
+    let mut seen = HashSet::new();
+    let mut kept = HashSet::new();
+    let mut parts = Vec::new();
+    let mut nodes = Vec::new();
+    for identity in &merged {
+        // Both sides inserting the same node lists it twice
+        if !seen.insert(identity.as_str()) {
+            continue;
+        }
+        let (chunk, text, outcome) = match (
+            find(base, identity),
+            find(local, identity),
+            find(remote, identity),
+        ) {
+            (Some(b), Some(l), Some(r)) => {
+                if l.text == b.text && r.text == b.text {
+                    (l, l.text.clone(), NodeOutcome::Unchanged)
+                } else if l.text == b.text {
+                    (r, r.text.clone(), NodeOutcome::Remote)
+                } else if r.text == b.text {
+                    (l, l.text.clone(), NodeOutcome::Local)
+                } else if l.text == r.text {
+                    (l, l.text.clone(), NodeOutcome::Both)
+                } else if renamed.contains(identity) {
+                    let lines = merge_sequences(
+                        &split_lines(&b.text),
+                        &split_lines(&l.text),
+                        &split_lines(&r.text),
+                    )
+                    .ok_or_else(|| same_node(b))?;
+                    (l, lines.join("\n"), NodeOutcome::Merged)
+                } else {
+                    return Err(same_node(b));
+                }
+            }
+            (None, Some(l), Some(r)) => {
+                if l.text == r.text {
+                    (l, l.text.clone(), NodeOutcome::Both)
+                } else {
+                    return Err(same_node(l));
+                }
+            }
+            (None, Some(l), None) => (l, l.text.clone(), NodeOutcome::Local),
+            (None, None, Some(r)) => (r, r.text.clone(), NodeOutcome::Remote),
+            // Deleted by one side and left untouched by the other
+            _ => continue,
+        };
+        if chunk.named || outcome != NodeOutcome::Unchanged {
+            nodes.push(matched_node(chunk, outcome));
+        }
+        kept.insert(identity.as_str());
+        parts.push(text);
+    }
+    for chunk in base.iter().filter(|c| !kept.contains(c.identity.as_str())) {
+        nodes.push(matched_node(chunk, NodeOutcome::Deleted));
+    }
+
+    Ok((parts.join("\n"), nodes))
+}
+
+/// One side's hunk lines split into blocks without a grammar, each identified
+/// by its first line.
+fn block_chunks(content: &str) -> Vec<Chunk> {
+    let mut chunks: Vec<Chunk> = split_blocks(content)
+        .into_iter()
+        .map(|block| Chunk {
+            identity: format!("block:{}", block.header),
+            named: !block.header.is_empty(),
+            text: block.text,
+        })
+        .collect();
+    number_repeats(&mut chunks);
+    chunks
+}
+
+/// Merge a conflict hunk block by block for a language without a grammar, with
+/// blocks found from indentation, blank lines and brackets. As nothing checks
+/// the result parses, it's refused unless its brackets balance like the sides'.
+fn heuristic_proposal(hunk: &MergeHunk) -> Result<Proposal, Refusal> {
+    let base = block_chunks(&hunk.base_content);
+    let local = block_chunks(&hunk.local_content);
+    let remote = block_chunks(&hunk.remote_content);
+    let (content, nodes) = merge_chunks(&base, &local, &remote, false, &HashSet::new())?;
+
+    let expected = bracket_delta(&hunk.local_content) + bracket_delta(&hunk.remote_content)
+        - bracket_delta(&hunk.base_content);
+    if bracket_delta(&content) != expected {
+        return Err(Refusal::new(RefusalReason::SyntaxErrors));
+    }
+    Ok(Proposal {
+        content,
+        status: HunkStatus::HeuristicMerged,
+        nodes,
+        renames: Vec::new(),
+    })
+}
+
+/// Split a key-by-key or element-by-element merge of the whole document into a proposal for each
+/// unresolved conflict hunk: the merged lines between the result lines on either
+/// side of the hunk, which the merge must have left alone.
+fn data_proposals(session: &MergeSession, merged: &DataMerge) -> Vec<(usize, Result<Proposal, Refusal>)> {
+    let result_lines = split_lines(&session.result_content);
+    let merged_lines: Vec<&str> = merged.content.lines().collect();
+    // The merged line each unchanged result line ended up on
+    let mut merged_line: Vec<Option<usize>> = vec![None; result_lines.len()];
+    for op in capture_diff_slices(Algorithm::Myers, &result_lines, &merged_lines) {
+        if let DiffOp::Equal { old_index, new_index, len } = op {
+            for i in 0..len {
+                merged_line[old_index + i] = Some(new_index + i);
+            }
+        }
+    }
+
+    session
+        .hunks
+        .iter()
+        .filter(|h| h.source == HunkSource::Conflict && h.status == HunkStatus::Unresolved)
+        .map(|hunk| {
+            // 0-indexed result lines `start..end`
+            let (start, end) = (hunk.result_range.start.saturating_sub(1), hunk.result_range.end);
+            let from = if start == 0 { Some(0) } else { merged_line[start - 1].map(|m| m + 1) };
+            let to = if end >= result_lines.len() { Some(merged_lines.len()) } else { merged_line[end] };
+            let (from, to) = match (from, to) {
+                (Some(from), Some(to)) if from <= to => (from, to),
+                _ => return (hunk.id, Err(Refusal::new(RefusalReason::Misaligned))),
+            };
+
+            // Keys on merged lines `from + 1..=to`, or at that spot if it's empty
+            let keys: Vec<_> = merged
+                .keys
+                .iter()
+                .filter(|k| k.start_line <= to.max(from + 1) && k.end_line > from)
+                .collect();
+            if let Some(conflict) = keys.iter().find_map(|k| k.result.err().map(|reason| (reason, k))) {
+                let (reason, key) = conflict;
+                return (
+                    hunk.id,
+                    Err(Refusal {
+                        reason,
+                        kind: Some(key.kind.to_string()),
+                        name: Some(key.path.clone()),
+                    }),
+                );
+            }
+            let nodes = keys
+                .iter()
+                .filter_map(|k| {
+                    Some(MatchedNode {
+                        kind: k.kind.to_string(),
+                        name: Some(k.path.clone()),
+                        outcome: k.result.ok()?,
+                    })
+                })
+                .collect();
+            let proposal = Proposal {
+                content: merged_lines[from..to].join("\n"),
+                status: HunkStatus::AutoResolved,
+                nodes,
+                renames: Vec::new(),
+            };
+            (hunk.id, Ok(proposal))
+        })
+        .collect()
+}
+
+#[cfg(feature = "tree-sitter")]
 mod inner {
-    use tree_sitter::{Language, Parser, Node, Tree};
-    use crate::merge::hunk::{HunkSource, HunkStatus, MergeHunk, MergeSession};
-    use crate::merge::three_way::rebuild_result;
-
-    fn get_language(lang: &str) -> Option<Language> {
-        match lang {
-            "rust" => Some(tree_sitter_rust::LANGUAGE.into()),
-            "typescript" => Some(tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()),
-            "javascript" => Some(tree_sitter_javascript::LANGUAGE.into()),
-            "python" => Some(tree_sitter_python::LANGUAGE.into()),
-            "go" => Some(tree_sitter_go::LANGUAGE.into()),
-            "java" => Some(tree_sitter_java::LANGUAGE.into()),
-            "c" => Some(tree_sitter_c::LANGUAGE.into()),
-            "cpp" => Some(tree_sitter_cpp::LANGUAGE.into()),
-            _ => None,
-        }
-    }
-
-    fn parse(source: &str, language: Language) -> Option<Tree> {
-        let mut parser = Parser::new();
-        parser.set_language(&language).ok()?;
-        parser.parse(source, None)
-    }
-
-    /// Find top-level AST nodes that span the given byte range.
-    fn find_spanning_nodes<'a>(
-        root: Node<'a>,
-        start_byte: usize,
-        end_byte: usize,
-    ) -> Vec<Node<'a>> {
-        let mut nodes = Vec::new();
-        let mut cursor = root.walk();
-
-        for child in root.children(&mut cursor) {
-            if child.end_byte() <= start_byte {
-                continue;
+    use std::collections::{HashMap, HashSet};
+    use similar::{capture_diff_slices, Algorithm, DiffOp, TextDiff};
+    use tree_sitter::{Language, Node, Tree};
+    use super::{
+        heuristic_proposal, merge_chunks, number_repeats, refuse_at, split_identity, Chunk,
+        DetectedRename, Proposal, Refusal, RefusalReason, SyntaxError,
+    };
+    use crate::merge::grammar::{get_language, grammar_name, session_language};
+    use crate::merge::hunk::{HunkSource, HunkStatus, LineRange, MergeHunk, MergeSession};
+    use crate::merge::injection::{find_injections, isolate, Injection};
+    use crate::merge::profile::{Profile, Roles};
+    use crate::merge::three_way::split_lines;
+    use crate::merge::tokens::{leaf_tokens, Token};
+    use crate::merge::trees::parse_from;
+
+    /// One side of the merge: its parsed tree, source and profile roles.
+    struct Side<'a> {
+        tree: Tree,
+        source: &'a str,
+        roles: Roles,
+    }
+
+    impl<'a> Side<'a> {
+        fn new(source: &'a str, tree: Tree, profile: Option<&Profile>) -> Self {
+            let roles = profile.map(|p| p.roles(&tree, source)).unwrap_or_default();
+            Side { tree, source, roles }
+        }
+    }
+
+    /// Find the deepest container node (or the root) that fully encloses the byte range.
+    fn find_container<'t>(root: Node<'t>, roles: &Roles, start_byte: usize, end_byte: usize) -> Node<'t> {
+        let mut container = root;
+        let mut node = root;
+        loop {
+            let mut cursor = node.walk();
+            let enclosing = node
+                .children(&mut cursor)
+                .find(|c| c.start_byte() <= start_byte && end_byte <= c.end_byte());
+            match enclosing {
+                Some(child) => {
+                    if roles.is_container(child) {
+                        container = child;
+                    }
+                    node = child;
+                }
+                None => break,
             }
-            if child.start_byte() >= end_byte {
-                break;
+        }
+        container
+    }
+
+    /// Key used to match a node across base, local and remote: the identity the
+    /// profile gives a unit, otherwise its kind plus its `name` field or full text.
+    /// Also says whether the key holds a name rather than the node's text.
+    fn node_identity(node: Node, source: &str, roles: &Roles) -> (String, bool) {
+        if let Some(identity) = roles.unit_identity(node) {
+            return (identity.to_string(), true);
+        }
+        match node.child_by_field_name("name") {
+            Some(name) => (format!("{}:{}", node.kind(), &source[name.byte_range()]), true),
+            None => (format!("{}:{}", node.kind(), &source[node.byte_range()]), false),
+        }
+    }
+
+    /// First and last line (1-indexed) a node occupies.
+    fn node_lines(node: Node) -> (usize, usize) {
+        let start = node.start_position();
+        let end = node.end_position();
+        // A node ending at column 0 stops on the previous line
+        let end_line = if end.column == 0 && end.row > start.row {
+            end.row
+        } else {
+            end.row + 1
+        };
+        (start.row + 1, end_line)
+    }
+
+    /// Split one side's hunk lines `start..=end` into chunks owned by the children
+    /// of `parent`. Comments and blank lines attach to the node that follows them,
+    /// or to the last node when nothing follows.
+    fn side_chunks(
+        parent: Node,
+        side: &Side,
+        content: &str,
+        start: usize,
+        end: usize,
+    ) -> Vec<Chunk> {
+        let lines: Vec<&str> = if end < start {
+            Vec::new()
+        } else {
+            content.split('\n').collect()
+        };
+
+        let mut cursor = parent.walk();
+        let siblings: Vec<(usize, usize, (String, bool))> = parent
+            .children(&mut cursor)
+            .filter(|n| !n.kind().contains("comment"))
+            .map(|n| {
+                let (first, last) = node_lines(n);
+                (first, last, node_identity(n, side.source, &side.roles))
+            })
+            .filter(|(first, last, _)| *first <= end && *last >= start)
+            .collect();
+
+        let mut chunks: Vec<Chunk> = Vec::new();
+        let mut pending: Vec<&str> = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let line_no = start + i;
+            let owner = siblings
+                .iter()
+                .find(|(first, last, _)| *first <= line_no && line_no <= *last)
+                .map(|(_, _, identity)| identity);
+
+            match (owner, chunks.last_mut()) {
+                (None, _) => pending.push(line),
+                (Some((identity, _)), Some(last)) if last.identity == *identity && pending.is_empty() => {
+                    last.text.push('\n');
+                    last.text.push_str(line);
+                }
+                (Some((identity, named)), _) => {
+                    pending.push(line);
+                    chunks.push(Chunk {
+                        identity: identity.clone(),
+                        named: *named,
+                        text: pending.join("\n"),
+                    });
+                    pending.clear();
+                }
+            }
+        }
+
+        if !pending.is_empty() {
+            match chunks.last_mut() {
+                Some(last) => {
+                    last.text.push('\n');
+                    last.text.push_str(&pending.join("\n"));
+                }
+                None => chunks.push(Chunk {
+                    identity: format!("trivia:{}", pending.join("\n")),
+                    named: false,
+                    text: pending.join("\n"),
+                }),
+            }
+        }
+
+        number_repeats(&mut chunks);
+        chunks
+    }
+
+    /// How alike a vanished node and a new node must be, once their names are
+    /// blanked out, to count as the same node renamed.
+    const RENAME_SIMILARITY: f32 = 0.6;
+
+    /// Line similarity of two node texts with each one's own name blanked out.
+    fn renamed_similarity(base: &Chunk, side: &Chunk) -> f32 {
+        let (_, base_name) = split_identity(&base.identity);
+        let (_, side_name) = split_identity(&side.identity);
+        if base_name.is_empty() || side_name.is_empty() {
+            return 0.0;
+        }
+        let base_text = replace_identifier(&base.text, base_name, "\u{0}");
+        let side_text = replace_identifier(&side.text, side_name, "\u{0}");
+        TextDiff::from_lines(&base_text, &side_text).ratio()
+    }
+
+    /// Replace whole-word occurrences of identifier `from` with `to`.
+    fn replace_identifier(text: &str, from: &str, to: &str) -> String {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let mut out = String::with_capacity(text.len());
+        let mut copied = 0;
+        for (pos, _) in text.match_indices(from) {
+            let end = pos + from.len();
+            let bounded = !text[..pos].chars().next_back().is_some_and(is_word)
+                && !text[end..].chars().next().is_some_and(is_word);
+            if bounded {
+                out.push_str(&text[copied..pos]);
+                out.push_str(to);
+                copied = end;
+            }
+        }
+        out.push_str(&text[copied..]);
+        out
+    }
+
+    /// Find named base nodes that one side renamed: the name is gone from that side
+    /// and a new node of the same kind with a similar body took its place. Those
+    /// side nodes take the base identity so the merge lines them up, and the
+    /// `(old, new)` identity pairs are returned.
+    fn match_renames(base: &[Chunk], side: &mut [Chunk]) -> Vec<(String, String)> {
+        let base_ids: HashSet<&str> = base.iter().map(|c| c.identity.as_str()).collect();
+        let side_ids: HashSet<String> = side.iter().map(|c| c.identity.clone()).collect();
+
+        let mut renames = Vec::new();
+        for old in base.iter().filter(|c| c.named && !side_ids.contains(&c.identity)) {
+            let (kind, _) = split_identity(&old.identity);
+            let best = side
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| {
+                    c.named
+                        && !base_ids.contains(c.identity.as_str())
+                        && split_identity(&c.identity).0 == kind
+                })
+                .map(|(i, c)| (i, renamed_similarity(old, c)))
+                .filter(|(_, score)| *score >= RENAME_SIMILARITY)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+
+            if let Some((i, _)) = best {
+                let new_identity = std::mem::replace(&mut side[i].identity, old.identity.clone());
+                renames.push((old.identity.clone(), new_identity));
+            }
+        }
+        renames
+    }
+
+    /// Replay one side's renames onto the base and other side's copies of the
+    /// renamed nodes, so a rename and an edit of the same lines line up.
+    fn replay_renames(renames: &[(String, String)], base: &mut [Chunk], other: &mut [Chunk]) {
+        for (old, new) in renames {
+            let (_, from) = split_identity(old);
+            let (_, to) = split_identity(new);
+            for chunk in base.iter_mut().chain(other.iter_mut()).filter(|c| c.identity == *old) {
+                chunk.text = replace_identifier(&chunk.text, from, to);
             }
-            nodes.push(child);
         }
-        nodes
     }
 
     /// Get byte offset for a line number (1-indexed) in the source.
@@ -79,163 +663,634 @@ mod inner {
         byte_offset
     }
 
-    /// Check if changes in a conflict affect different top-level AST nodes.
-    /// If local changes one function and remote changes a different function,
-    /// we can auto-resolve by taking both.
-    fn can_structural_resolve(
-        base_tree: &Tree,
-        local_tree: &Tree,
-        remote_tree: &Tree,
-        hunk: &MergeHunk,
-        base_src: &str,
-        local_src: &str,
-        remote_src: &str,
-    ) -> bool {
-        if hunk.source != HunkSource::Conflict || hunk.status != HunkStatus::Unresolved {
-            return false;
+    /// Shrink a byte range so it starts and ends on non-whitespace, so blank lines
+    /// around a change don't push it outside the node that encloses it.
+    fn trim_range(source: &str, start: usize, end: usize) -> (usize, usize) {
+        let end = end.min(source.len());
+        if start >= end {
+            return (start, end);
         }
+        let text = &source[start..end];
+        let trimmed_start = start + (text.len() - text.trim_start().len());
+        let trimmed_end = end - (text.len() - text.trim_end().len());
+        if trimmed_start >= trimmed_end {
+            (start, end)
+        } else {
+            (trimmed_start, trimmed_end)
+        }
+    }
 
+    /// Try to merge a conflict hunk node by node. Siblings are compared inside the
+    /// deepest container (impl/class body, field list, ...) enclosing the conflict,
+    /// so local editing one method and remote another resolves to both edits.
+    fn structural_merge_hunk(
+        base: &Side,
+        local: &Side,
+        remote: &Side,
+        hunk: &MergeHunk,
+    ) -> Result<Proposal, Refusal> {
         // Get byte ranges for the conflict in base
-        let base_start = line_to_byte(base_src, hunk.base_range.start);
-        let base_end = end_of_line_byte(base_src, hunk.base_range.end);
+        let base_start = line_to_byte(base.source, hunk.base_range.start);
+        let base_end = end_of_line_byte(base.source, hunk.base_range.end);
+
+        // Get byte ranges for local/remote changes
+        let (local_start, local_end) = trim_range(
+            local.source,
+            line_to_byte(local.source, hunk.local_range.start),
+            end_of_line_byte(local.source, hunk.local_range.end),
+        );
+        let (remote_start, remote_end) = trim_range(
+            remote.source,
+            line_to_byte(remote.source, hunk.remote_range.start),
+            end_of_line_byte(remote.source, hunk.remote_range.end),
+        );
 
-        // Find AST nodes spanning the conflict in base
-        let base_nodes = find_spanning_nodes(base_tree.root_node(), base_start, base_end);
+        // Descend to the deepest container enclosing the conflict on every side.
+        // If the sides disagree on what encloses it, compare at the top level.
+        let base_container = find_container(base.tree.root_node(), &base.roles, base_start, base_end);
+        let local_container =
+            find_container(local.tree.root_node(), &local.roles, local_start, local_end);
+        let remote_container =
+            find_container(remote.tree.root_node(), &remote.roles, remote_start, remote_end);
+        // A pure insertion can sit just past the end of its container in base
+        // (e.g. after the last method of a Python class), so only the sides count.
+        let is_insertion = hunk.base_range.end < hunk.base_range.start;
+        let (base_parent, local_parent, remote_parent) = if (is_insertion
+            || base_container.kind() == local_container.kind())
+            && local_container.kind() == remote_container.kind()
+        {
+            (base_container, local_container, remote_container)
+        } else {
+            (base.tree.root_node(), local.tree.root_node(), remote.tree.root_node())
+        };
 
-        if base_nodes.len() < 2 {
-            // Single node or no nodes — can't split structurally
-            return false;
-        }
+        let mut base_chunks = side_chunks(
+            base_parent,
+            base,
+            &hunk.base_content,
+            hunk.base_range.start,
+            hunk.base_range.end,
+        );
+        let mut local_chunks = side_chunks(
+            local_parent,
+            local,
+            &hunk.local_content,
+            hunk.local_range.start,
+            hunk.local_range.end,
+        );
+        let mut remote_chunks = side_chunks(
+            remote_parent,
+            remote,
+            &hunk.remote_content,
+            hunk.remote_range.start,
+            hunk.remote_range.end,
+        );
 
-        // Get byte ranges for local/remote changes
-        let local_start = line_to_byte(local_src, hunk.local_range.start);
-        let local_end = end_of_line_byte(local_src, hunk.local_range.end);
-        let remote_start = line_to_byte(remote_src, hunk.remote_range.start);
-        let remote_end = end_of_line_byte(remote_src, hunk.remote_range.end);
-
-        // Find what nodes local and remote touch
-        let local_nodes = find_spanning_nodes(local_tree.root_node(), local_start, local_end);
-        let remote_nodes = find_spanning_nodes(remote_tree.root_node(), remote_start, remote_end);
-
-        // Check if local and remote affect different sets of node kinds at different positions
-        // Simple heuristic: if the top-level node types don't overlap in their
-        // approximate source positions, they're structurally independent
-        let local_kinds: Vec<(&str, usize, usize)> = local_nodes
+        let local_renames = match_renames(&base_chunks, &mut local_chunks);
+        let remote_renames = match_renames(&base_chunks, &mut remote_chunks);
+        // Both sides renaming the same node is a conflict
+        let both_renamed = local_renames
             .iter()
-            .map(|n| (n.kind(), n.start_byte(), n.end_byte()))
-            .collect();
-        let remote_kinds: Vec<(&str, usize, usize)> = remote_nodes
+            .find(|(old, _)| remote_renames.iter().any(|(o, _)| o == old));
+        if let Some((old, _)) = both_renamed {
+            let chunk = base_chunks.iter().find(|c| c.identity == *old);
+            return Err(match chunk {
+                Some(chunk) => refuse_at(RefusalReason::BothRenamed, chunk),
+                None => Refusal::new(RefusalReason::BothRenamed),
+            });
+        }
+        replay_renames(&local_renames, &mut base_chunks, &mut remote_chunks);
+        replay_renames(&remote_renames, &mut base_chunks, &mut local_chunks);
+        let renamed: HashSet<String> = local_renames
             .iter()
-            .map(|n| (n.kind(), n.start_byte(), n.end_byte()))
+            .chain(remote_renames.iter())
+            .map(|(old, _)| old.clone())
             .collect();
 
-        // If any node ranges overlap between local and remote, it's not safe
-        for (_, ls, le) in &local_kinds {
-            for (_, rs, re) in &remote_kinds {
-                if ls < re && rs < le {
-                    return false;
+        let unordered = local.roles.is_unordered(local_parent) && remote.roles.is_unordered(remote_parent);
+        let (content, nodes) = merge_chunks(&base_chunks, &local_chunks, &remote_chunks, unordered, &renamed)?;
+
+        let renames = local_renames
+            .into_iter()
+            .map(|rename| (HunkSource::Local, rename))
+            .chain(remote_renames.into_iter().map(|rename| (HunkSource::Remote, rename)))
+            .map(|(side, (old, new))| {
+                let (kind, from) = split_identity(&old);
+                let (_, to) = split_identity(&new);
+                DetectedRename {
+                    hunk_id: hunk.id,
+                    side,
+                    kind: kind.to_string(),
+                    from: from.to_string(),
+                    to: to.to_string(),
                 }
+            })
+            .collect();
+        Ok(Proposal {
+            content,
+            status: HunkStatus::AutoResolved,
+            nodes,
+            renames,
+        })
+    }
+
+    /// Collect ERROR and MISSING nodes, outermost first. Nothing below an ERROR
+    /// node is reported separately.
+    fn collect_syntax_errors(node: Node, source: &str, errors: &mut Vec<SyntaxError>) {
+        if node.is_error() || node.is_missing() {
+            let message = if node.is_missing() {
+                format!("missing `{}`", node.kind())
+            } else {
+                let text = source[node.byte_range()].lines().next().unwrap_or("").trim();
+                let snippet: String = text.chars().take(40).collect();
+                format!("unexpected `{}`", snippet)
+            };
+            errors.push(SyntaxError {
+                line: node.start_position().row + 1,
+                column: node.start_position().column + 1,
+                end_line: node.end_position().row + 1,
+                message,
+                hunk_id: None,
+            });
+            return;
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if child.has_error() {
+                collect_syntax_errors(child, source, errors);
             }
         }
+    }
 
-        true
+    /// Count syntax errors touching lines `start..=end` (1-indexed).
+    fn count_errors_in(tree: &Tree, source: &str, start: usize, end: usize) -> usize {
+        let mut errors = Vec::new();
+        collect_syntax_errors(tree.root_node(), source, &mut errors);
+        errors
+            .iter()
+            .filter(|e| e.line <= end && e.end_line >= start)
+            .count()
     }
 
-    pub fn structural_auto_resolve(session: &mut MergeSession) -> usize {
-        let language = match get_language(&session.language) {
-            Some(l) => l,
-            None => return 0,
+    /// One side's hunk lines as a byte range (without the final newline) and the
+    /// leaf tokens inside it. `None` if a token crosses the range's edges.
+    fn region_tokens<'s>(side: &'s Side, range: &LineRange) -> Option<(usize, usize, Vec<Token<'s>>)> {
+        let start = line_to_byte(side.source, range.start);
+        let mut end = if range.end < range.start {
+            start
+        } else {
+            end_of_line_byte(side.source, range.end)
         };
+        if end > start && side.source[..end].ends_with('\n') {
+            end -= 1;
+        }
 
-        let base_tree = match parse(&session.base_content, language.clone()) {
-            Some(t) => t,
-            None => return 0,
+        let mut tokens = Vec::new();
+        for token in leaf_tokens(&side.tree, side.source) {
+            if token.end_byte() <= start || token.start_byte >= end {
+                continue;
+            }
+            if token.start_byte < start || token.end_byte() > end {
+                return None;
+            }
+            tokens.push(token);
+        }
+        Some((start, end, tokens))
+    }
+
+    fn same_tokens(a: &[Token], b: &[Token]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.text == y.text && x.depth == y.depth)
+    }
+
+    /// Apply the token edits `edited` made to `base` onto `formatted`, a reformat of
+    /// `base` with the same tokens. Unchanged tokens keep `formatted`'s layout and
+    /// inserted ones bring the edited side's spacing with them.
+    fn reformat_edit(
+        base: &[Token],
+        (formatted_source, formatted_start, formatted_end, formatted): (&str, usize, usize, &[Token]),
+        (edited_source, edited_start, edited): (&str, usize, &[Token]),
+    ) -> String {
+        // Whitespace before token `i` of a side, then the token itself
+        let piece = |source: &str, start: usize, tokens: &[Token], i: usize| {
+            let gap_start = if i == 0 { start } else { tokens[i - 1].end_byte() };
+            source[gap_start..tokens[i].end_byte()].to_string()
         };
-        let local_tree = match parse(&session.local_content, language.clone()) {
+
+        let base_texts: Vec<&str> = base.iter().map(|t| t.text).collect();
+        let edited_texts: Vec<&str> = edited.iter().map(|t| t.text).collect();
+        let mut content = String::new();
+        for op in capture_diff_slices(Algorithm::Myers, &base_texts, &edited_texts) {
+            match op {
+                DiffOp::Equal { old_index, len, .. } => {
+                    for i in old_index..old_index + len {
+                        content.push_str(&piece(formatted_source, formatted_start, formatted, i));
+                    }
+                }
+                DiffOp::Delete { .. } => {}
+                DiffOp::Insert { new_index, new_len, .. } | DiffOp::Replace { new_index, new_len, .. } => {
+                    for i in new_index..new_index + new_len {
+                        content.push_str(&piece(edited_source, edited_start, edited, i));
+                    }
+                }
+            }
+        }
+
+        let tail_start = formatted.last().map_or(formatted_start, |t| t.end_byte());
+        content.push_str(&formatted_source[tail_start..formatted_end]);
+        content
+    }
+
+    /// Resolve a conflict where one side only reformatted the region (its tokens
+    /// match base) by applying the other side's edits to the reformatted text.
+    fn formatting_merge_hunk(base: &Side, local: &Side, remote: &Side, hunk: &MergeHunk) -> Option<String> {
+        let (_, _, base_tokens) = region_tokens(base, &hunk.base_range)?;
+        let (local_start, local_end, local_tokens) = region_tokens(local, &hunk.local_range)?;
+        let (remote_start, remote_end, remote_tokens) = region_tokens(remote, &hunk.remote_range)?;
+
+        let local_formatting = same_tokens(&base_tokens, &local_tokens);
+        let remote_formatting = same_tokens(&base_tokens, &remote_tokens);
+        match (local_formatting, remote_formatting) {
+            // Both sides only reformatted: keep local's formatting
+            (true, true) => Some(hunk.local_content.clone()),
+            (true, false) => Some(reformat_edit(
+                &base_tokens,
+                (local.source, local_start, local_end, &local_tokens),
+                (remote.source, remote_start, &remote_tokens),
+            )),
+            (false, true) => Some(reformat_edit(
+                &base_tokens,
+                (remote.source, remote_start, remote_end, &remote_tokens),
+                (local.source, local_start, &local_tokens),
+            )),
+            (false, false) => None,
+        }
+    }
+
+    /// Check whether a candidate resolution breaks the syntax of its region.
+    ///
+    /// The candidate is spliced into the local file in place of the hunk's local
+    /// lines rather than into the result, so markers of other unresolved
+    /// conflicts don't count against it.
+    fn introduces_syntax_errors(
+        language: &Language,
+        local: &Side,
+        hunk: &MergeHunk,
+        new_content: &str,
+    ) -> bool {
+        let lines = split_lines(local.source);
+        let start = hunk.local_range.start.max(1);
+        let splice_start = (start - 1).min(lines.len());
+        let splice_end = hunk.local_range.end.clamp(splice_start, lines.len());
+        let new_lines = split_lines(new_content);
+
+        let mut probe: Vec<&str> = lines[..splice_start].to_vec();
+        probe.extend(new_lines.iter().copied());
+        probe.extend(lines[splice_end..].iter().copied());
+        let probe = probe.join("\n");
+
+        // Only the spliced lines changed, so reparse incrementally from local's tree
+        let probe_tree = match parse_from(language, &probe, Some((local.source, &local.tree))) {
             Some(t) => t,
-            None => return 0,
+            None => return true,
         };
-        let remote_tree = match parse(&session.remote_content, language) {
-            Some(t) => t,
-            None => return 0,
+
+        let before = count_errors_in(&local.tree, local.source, start, hunk.local_range.end.max(start));
+        let after = count_errors_in(
+            &probe_tree,
+            &probe,
+            start,
+            (start + new_lines.len()).saturating_sub(1).max(start),
+        );
+        after > before
+    }
+
+    /// Report syntax errors in the current result, each mapped to the hunk whose
+    /// result range contains it. Returns `None` without a grammar for the language.
+    pub fn validate_result(session: &MergeSession) -> Option<Vec<SyntaxError>> {
+        let language = session_language(session)?;
+        let tree = session
+            .trees
+            .parse_result(grammar_name(session), &language, &session.result_content)?;
+
+        let mut errors = Vec::new();
+        collect_syntax_errors(tree.root_node(), &session.result_content, &mut errors);
+        for error in errors.iter_mut() {
+            error.hunk_id = session
+                .hunks
+                .iter()
+                .find(|h| {
+                    !h.result_range.is_empty()
+                        && h.result_range.start <= error.end_line
+                        && error.line <= h.result_range.end
+                })
+                .map(|h| h.id);
+        }
+        Some(errors)
+    }
+
+    /// Work out a resolution for one conflict hunk: a formatting-only merge if one
+    /// side just reformatted, otherwise a node-by-node merge.
+    fn propose(
+        language: &Language,
+        (base, local, remote): (&Side, &Side, &Side),
+        hunk: &MergeHunk,
+    ) -> Result<Proposal, Refusal> {
+        if let Some(content) = formatting_merge_hunk(base, local, remote, hunk) {
+            if !introduces_syntax_errors(language, local, hunk, &content) {
+                return Ok(Proposal {
+                    content,
+                    status: HunkStatus::FormattingMerged,
+                    nodes: Vec::new(),
+                    renames: Vec::new(),
+                });
+            }
+        }
+
+        let proposal = structural_merge_hunk(base, local, remote, hunk)?;
+        // Leave the conflict for the user if the merged code doesn't parse
+        if introduces_syntax_errors(language, local, hunk, &proposal.content) {
+            return Err(Refusal::new(RefusalReason::SyntaxErrors));
+        }
+        Ok(proposal)
+    }
+
+    /// Narrow a hunk to the lines where its sides differ. Returns the narrowed hunk
+    /// and the lines all three sides share before and after it.
+    fn trim_hunk(hunk: &MergeHunk) -> (MergeHunk, Vec<&str>, Vec<&str>) {
+        let base = split_lines(&hunk.base_content);
+        let local = split_lines(&hunk.local_content);
+        let remote = split_lines(&hunk.remote_content);
+        let shortest = base.len().min(local.len()).min(remote.len());
+        let same = |b: &str, l: &str, r: &str| b == l && l == r;
+
+        let before = (0..shortest)
+            .take_while(|&i| same(base[i], local[i], remote[i]))
+            .count();
+        let after = (0..shortest - before)
+            .take_while(|&i| {
+                same(base[base.len() - 1 - i], local[local.len() - 1 - i], remote[remote.len() - 1 - i])
+            })
+            .count();
+
+        let narrow = |range: &LineRange, lines: &[&str]| {
+            let range = if range.is_empty() || before + after == 0 {
+                *range
+            } else {
+                LineRange::new(range.start + before, range.end - after)
+            };
+            (range, lines[before..lines.len() - after].join("\n"))
         };
+        let (base_range, base_content) = narrow(&hunk.base_range, &base);
+        let (local_range, local_content) = narrow(&hunk.local_range, &local);
+        let (remote_range, remote_content) = narrow(&hunk.remote_range, &remote);
+        let trimmed = MergeHunk {
+            base_range,
+            local_range,
+            remote_range,
+            base_content,
+            local_content,
+            remote_content,
+            ..hunk.clone()
+        };
+        (trimmed, base[..before].to_vec(), base[base.len() - after..].to_vec())
+    }
 
-        let mut resolved_count = 0;
-        let conflict_ids: Vec<usize> = session
+    /// The embedded region that holds one side's hunk lines, if any.
+    fn enclosing<'i>(injections: &'i [Injection], source: &str, range: &LineRange) -> Option<&'i Injection> {
+        let start = line_to_byte(source, range.start);
+        let end = if range.end < range.start {
+            start
+        } else {
+            end_of_line_byte(source, range.end)
+        };
+        let end = if end > start && source[..end].ends_with('\n') { end - 1 } else { end };
+        injections.iter().find(|i| i.start <= start && end <= i.end)
+    }
+
+    /// Merge a conflict hunk inside embedded code, like a `<script>` body or a
+    /// fenced code block, with the embedded language's grammar and profile. Each
+    /// side is parsed with everything outside its region blanked out. `None` if
+    /// the lines where the sides differ aren't inside a region of the same
+    /// language on every side, or that language has no grammar.
+    fn embedded_proposal(
+        session: &MergeSession,
+        injections: &[Vec<Injection>; 3],
+        hunk: &MergeHunk,
+    ) -> Option<Result<Proposal, Refusal>> {
+        let (trimmed, before, after) = trim_hunk(hunk);
+        let base = enclosing(&injections[0], &session.base_content, &trimmed.base_range)?;
+        let local = enclosing(&injections[1], &session.local_content, &trimmed.local_range)?;
+        let remote = enclosing(&injections[2], &session.remote_content, &trimmed.remote_range)?;
+        if base.language != local.language || local.language != remote.language {
+            return None;
+        }
+        let grammar = local.language.as_str();
+        let language = get_language(grammar)?;
+
+        let profile = Profile::load(grammar, &language);
+        let sources = (
+            isolate(&session.base_content, base),
+            isolate(&session.local_content, local),
+            isolate(&session.remote_content, remote),
+        );
+        let trees = (
+            session.trees.parse(grammar, &language, &sources.0),
+            session.trees.parse(grammar, &language, &sources.1),
+            session.trees.parse(grammar, &language, &sources.2),
+        );
+        let (base, local, remote) = match trees {
+            (Some(b), Some(l), Some(r)) => (
+                Side::new(&sources.0, b, profile.as_deref()),
+                Side::new(&sources.1, l, profile.as_deref()),
+                Side::new(&sources.2, r, profile.as_deref()),
+            ),
+            _ => return Some(Err(Refusal::new(RefusalReason::ParseFailure))),
+        };
+
+        let proposal = propose(&language, (&base, &local, &remote), &trimmed).map(|proposal| {
+            let lines: Vec<&str> = before
+                .iter()
+                .copied()
+                .chain(split_lines(&proposal.content))
+                .chain(after.iter().copied())
+                .collect();
+            Proposal { content: lines.join("\n"), ..proposal }
+        });
+        Some(proposal)
+    }
+
+    /// Proposals for the unresolved conflict hunks that lie inside embedded code,
+    /// keyed by hunk id.
+    pub fn embedded_proposals(session: &MergeSession) -> HashMap<usize, Result<Proposal, Refusal>> {
+        let grammar = grammar_name(session);
+        let language = session_language(session);
+        let injections = [&session.base_content, &session.local_content, &session.remote_content].map(|source| {
+            let tree = language.as_ref().and_then(|l| session.trees.parse(grammar, l, source));
+            find_injections(grammar, language.as_ref().zip(tree.as_ref()), source)
+        });
+        if injections.iter().all(Vec::is_empty) {
+            return HashMap::new();
+        }
+
+        session
             .hunks
             .iter()
             .filter(|h| h.source == HunkSource::Conflict && h.status == HunkStatus::Unresolved)
-            .map(|h| h.id)
-            .collect();
+            .filter_map(|hunk| Some((hunk.id, embedded_proposal(session, &injections, hunk)?)))
+            .collect()
+    }
 
-        for hunk_id in conflict_ids {
-            let hunk: MergeHunk = match session.hunks.iter().find(|h| h.id == hunk_id) {
-                Some(h) => h.clone(),
-                None => continue,
-            };
+    /// A proposal or refusal for every unresolved conflict hunk. Hunks inside
+    /// embedded code are merged in the embedded language.
+    pub fn proposals(session: &MergeSession) -> Vec<(usize, Result<Proposal, Refusal>)> {
+        let conflicts = session
+            .hunks
+            .iter()
+            .filter(|h| h.source == HunkSource::Conflict && h.status == HunkStatus::Unresolved);
+        let refuse_all = |reason| conflicts.clone().map(|h| (h.id, Err(Refusal::new(reason)))).collect();
+        let mut embedded = embedded_proposals(session);
 
-            if can_structural_resolve(
-                &base_tree,
-                &local_tree,
-                &remote_tree,
-                &hunk,
-                &session.base_content,
-                &session.local_content,
-                &session.remote_content,
-            ) {
-                // Auto-resolve by accepting both (local first, then remote)
-                let new_content = if hunk.local_content.is_empty() {
-                    hunk.remote_content.clone()
-                } else if hunk.remote_content.is_empty() {
-                    hunk.local_content.clone()
-                } else {
-                    format!("{}\n{}", hunk.local_content, hunk.remote_content)
-                };
+        // Without a grammar, fall back to splitting hunks into blocks heuristically
+        let language = match session_language(session) {
+            Some(l) => l,
+            None => {
+                return conflicts
+                    .map(|h| (h.id, embedded.remove(&h.id).unwrap_or_else(|| heuristic_proposal(h))))
+                    .collect()
+            }
+        };
+        let grammar = grammar_name(session);
+        let profile = Profile::load(grammar, &language);
+        let sides = (
+            session.trees.parse(grammar, &language, &session.base_content),
+            session.trees.parse(grammar, &language, &session.local_content),
+            session.trees.parse(grammar, &language, &session.remote_content),
+        );
+        let (base, local, remote) = match sides {
+            (Some(b), Some(l), Some(r)) => (
+                Side::new(&session.base_content, b, profile.as_deref()),
+                Side::new(&session.local_content, l, profile.as_deref()),
+                Side::new(&session.remote_content, r, profile.as_deref()),
+            ),
+            _ => return refuse_all(RefusalReason::ParseFailure),
+        };
 
-                let new_result = rebuild_result(
-                    &session.result_content,
-                    &hunk,
-                    &new_content,
-                );
+        conflicts
+            .map(|hunk| {
+                let proposal = embedded
+                    .remove(&hunk.id)
+                    .unwrap_or_else(|| propose(&language, (&base, &local, &remote), hunk));
+                (hunk.id, proposal)
+            })
+            .collect()
+    }
+}
 
-                // Update ranges
-                let old_line_count = hunk.result_range.line_count();
-                let new_line_count = new_content.lines().count();
-                let line_delta = new_line_count as isize - old_line_count as isize;
+#[cfg(not(feature = "tree-sitter"))]
+mod inner {
+    use std::collections::HashMap;
+    use super::{heuristic_proposal, Proposal, Refusal, SyntaxError};
+    use crate::merge::hunk::{HunkSource, HunkStatus, MergeSession};
 
-                if let Some(h) = session.hunks.iter_mut().find(|h| h.id == hunk_id) {
-                    h.status = HunkStatus::AutoResolved;
-                    if new_line_count > 0 {
-                        h.result_range.end = h.result_range.start + new_line_count - 1;
-                    }
-                }
+    /// Embedded code needs tree-sitter to merge.
+    pub fn embedded_proposals(_session: &MergeSession) -> HashMap<usize, Result<Proposal, Refusal>> {
+        HashMap::new()
+    }
 
-                let resolved_start = hunk.result_range.start;
-                for h in session.hunks.iter_mut() {
-                    if h.id != hunk_id && h.result_range.start > resolved_start {
-                        h.result_range.start =
-                            (h.result_range.start as isize + line_delta) as usize;
-                        h.result_range.end =
-                            (h.result_range.end as isize + line_delta) as usize;
-                    }
-                }
+    /// Without tree-sitter, every hunk gets the heuristic block merge.
+    pub fn proposals(session: &MergeSession) -> Vec<(usize, Result<Proposal, Refusal>)> {
+        session
+            .hunks
+            .iter()
+            .filter(|h| h.source == HunkSource::Conflict && h.status == HunkStatus::Unresolved)
+            .map(|h| (h.id, heuristic_proposal(h)))
+            .collect()
+    }
 
-                session.result_content = new_result;
-                resolved_count += 1;
-            }
-        }
+    pub fn validate_result(_session: &MergeSession) -> Option<Vec<SyntaxError>> {
+        None
+    }
+}
 
-        resolved_count
+pub use inner::validate_result;
+
+/// A proposal or refusal for every unresolved conflict hunk: from a key-by-key
+/// merge for JSON, TOML and YAML, an element-by-element merge for XML and HTML
+/// (with the node-by-node merge for scripts and styles), otherwise from the
+/// node-by-node merge. Prose only gets proposals for conflicts inside code blocks.
+fn proposals(session: &MergeSession, data: &DataConfig) -> Vec<(usize, Result<Proposal, Refusal>)> {
+    if session.strategy == MergeStrategy::Prose {
+        // The prose merge already merged the text around the code, sentence by sentence
+        let mut embedded: Vec<_> = inner::embedded_proposals(session).into_iter().collect();
+        embedded.sort_by_key(|(hunk_id, _)| *hunk_id);
+        return embedded;
     }
+    if is_data_language(&session.language) {
+        if let Some(merged) = merge_data(session, data) {
+            return data_proposals(session, &merged);
+        }
+    }
+    if is_markup_language(&session.language) {
+        if let Some(merged) = merge_markup(session) {
+            // Conflicts the element merge leaves inside scripts and styles get the
+            // embedded language's node-by-node merge instead
+            let mut embedded = inner::embedded_proposals(session);
+            return data_proposals(session, &merged)
+                .into_iter()
+                .map(|(hunk_id, result)| match (result, embedded.remove(&hunk_id)) {
+                    (Err(_), Some(embedded)) => (hunk_id, embedded),
+                    (result, _) => (hunk_id, result),
+                })
+                .collect();
+        }
+    }
+    inner::proposals(session)
 }
 
-#[cfg(not(feature = "tree-sitter-grammars"))]
-mod inner {
-    use crate::merge::hunk::MergeSession;
+/// Preview what the Magic Wand would do with each unresolved conflict hunk,
+/// without changing the session.
+pub fn preview_structural(session: &MergeSession, data: &DataConfig) -> Vec<HunkPreview> {
+    proposals(session, data)
+        .into_iter()
+        .map(|(hunk_id, result)| match result {
+            Ok(proposal) => HunkPreview {
+                hunk_id,
+                proposal: Some(proposal.content),
+                status: Some(proposal.status),
+                nodes: proposal.nodes,
+                renames: proposal.renames,
+                refusal: None,
+            },
+            Err(refusal) => HunkPreview {
+                hunk_id,
+                proposal: None,
+                status: None,
+                nodes: Vec::new(),
+                renames: Vec::new(),
+                refusal: Some(refusal),
+            },
+        })
+        .collect()
+}
 
-    pub fn structural_auto_resolve(_session: &mut MergeSession) -> usize {
-        0
+/// Apply the structural resolutions proposed for the given hunks, or for every
+/// hunk that has one. Returns the number of hunks resolved.
+pub fn apply_structural(session: &mut MergeSession, hunk_ids: Option<&[usize]>, data: &DataConfig) -> usize {
+    let mut resolved_count = 0;
+    for (hunk_id, result) in proposals(session, data) {
+        let Ok(proposal) = result else { continue };
+        if hunk_ids.is_some_and(|ids| !ids.contains(&hunk_id)) {
+            continue;
+        }
+        apply_resolution(session, hunk_id, &proposal.content, proposal.status);
+        session.renames.extend(proposal.renames);
+        resolved_count += 1;
     }
+    resolved_count
 }
 
-pub use inner::structural_auto_resolve;
+/// Resolve every conflict hunk the structural merge can.
+pub fn structural_auto_resolve(session: &mut MergeSession, data: &DataConfig) -> usize {
+    apply_structural(session, None, data)
+}
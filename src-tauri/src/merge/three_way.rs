@@ -83,6 +83,26 @@ fn ranges_overlap(a: &DiffRegion, b: &DiffRegion) -> bool {
     a_start <= b_end && b_start <= a_end
 }
 
+/// A side's line range covering base lines `base_start..=base_end`, which contain
+/// the base lines of its run of regions. Lines outside the regions are unchanged
+/// from base.
+fn widen_to_base(regions: &[DiffRegion], base_start: usize, base_end: usize) -> (usize, usize) {
+    let (first, last) = (&regions[0], &regions[regions.len() - 1]);
+    let before = first.base_start - base_start;
+    let after = base_end - last.base_end;
+    (first.side_start - before, last.side_end + after)
+}
+
+/// Split hunk or result content into lines, keeping a trailing empty line
+/// (unlike `str::lines`) so content joined with `\n` round-trips exactly.
+pub fn split_lines(content: &str) -> Vec<&str> {
+    if content.is_empty() {
+        Vec::new()
+    } else {
+        content.split('\n').collect()
+    }
+}
+
 /// Get lines from content (1-indexed range, inclusive).
 fn get_lines(content: &str, start: usize, end: usize) -> String {
     if start == 0 || end < start {
@@ -111,29 +131,51 @@ pub fn three_way_merge(base: &str, local: &str, remote: &str) -> (Vec<MergeHunk>
 
         match (lr, rr) {
             (Some(l), Some(r)) if ranges_overlap(l, r) => {
-                // Conflict: both sides changed overlapping base region
+                // Conflict: both sides changed overlapping base region. Take in every
+                // further region on either side that overlaps it, then widen each
+                // side to the whole base region so neither drops the unchanged
+                // lines the other side's change covers.
+                let mut span = DiffRegion {
+                    base_start: l.base_start.min(r.base_start),
+                    base_end: l.base_end.max(r.base_end),
+                    side_start: 0,
+                    side_end: 0,
+                };
+                let (first_local, first_remote) = (li, ri);
+                li += 1;
+                ri += 1;
+                loop {
+                    let next = match (local_regions.get(li), remote_regions.get(ri)) {
+                        (Some(next), _) if ranges_overlap(next, &span) => {
+                            li += 1;
+                            next
+                        }
+                        (_, Some(next)) if ranges_overlap(next, &span) => {
+                            ri += 1;
+                            next
+                        }
+                        _ => break,
+                    };
+                    span.base_start = span.base_start.min(next.base_start);
+                    span.base_end = span.base_end.max(next.base_end);
+                }
+                let (base_start, base_end) = (span.base_start, span.base_end);
+                let (local_start, local_end) = widen_to_base(&local_regions[first_local..li], base_start, base_end);
+                let (remote_start, remote_end) =
+                    widen_to_base(&remote_regions[first_remote..ri], base_start, base_end);
                 hunks.push(MergeHunk {
                     id: hunk_id,
-                    base_range: LineRange::new(
-                        l.base_start.min(r.base_start),
-                        l.base_end.max(r.base_end),
-                    ),
-                    local_range: LineRange::new(l.side_start, l.side_end),
-                    remote_range: LineRange::new(r.side_start, r.side_end),
+                    base_range: LineRange::new(base_start, base_end),
+                    local_range: LineRange::new(local_start, local_end),
+                    remote_range: LineRange::new(remote_start, remote_end),
                     result_range: LineRange::empty(),
                     source: HunkSource::Conflict,
                     status: HunkStatus::Unresolved,
-                    local_content: get_lines(local, l.side_start, l.side_end),
-                    remote_content: get_lines(remote, r.side_start, r.side_end),
-                    base_content: get_lines(
-                        base,
-                        l.base_start.min(r.base_start),
-                        l.base_end.max(r.base_end),
-                    ),
+                    local_content: get_lines(local, local_start, local_end),
+                    remote_content: get_lines(remote, remote_start, remote_end),
+                    base_content: get_lines(base, base_start, base_end),
                 });
                 hunk_id += 1;
-                li += 1;
-                ri += 1;
             }
             (Some(l), Some(r)) => {
                 // No overlap — take whichever comes first in the base
@@ -244,7 +286,7 @@ fn build_result(base: &str, _local: &str, _remote: &str, hunks: &[MergeHunk]) ->
             HunkSource::Local => {
                 // Apply local change
                 if !hunk.local_content.is_empty() {
-                    for line in hunk.local_content.lines() {
+                    for line in split_lines(&hunk.local_content) {
                         result_lines.push(line.to_string());
                     }
                 }
@@ -252,7 +294,7 @@ fn build_result(base: &str, _local: &str, _remote: &str, hunks: &[MergeHunk]) ->
             HunkSource::Remote => {
                 // Apply remote change
                 if !hunk.remote_content.is_empty() {
-                    for line in hunk.remote_content.lines() {
+                    for line in split_lines(&hunk.remote_content) {
                         result_lines.push(line.to_string());
                     }
                 }
@@ -260,11 +302,11 @@ fn build_result(base: &str, _local: &str, _remote: &str, hunks: &[MergeHunk]) ->
             HunkSource::Conflict => {
                 // For conflicts, insert conflict markers
                 result_lines.push("<<<<<<< LOCAL".to_string());
-                for line in hunk.local_content.lines() {
+                for line in split_lines(&hunk.local_content) {
                     result_lines.push(line.to_string());
                 }
                 result_lines.push("=======".to_string());
-                for line in hunk.remote_content.lines() {
+                for line in split_lines(&hunk.remote_content) {
                     result_lines.push(line.to_string());
                 }
                 result_lines.push(">>>>>>> REMOTE".to_string());
@@ -272,7 +314,7 @@ fn build_result(base: &str, _local: &str, _remote: &str, hunks: &[MergeHunk]) ->
             HunkSource::Both => {
                 // Both sides made same change — take either
                 if !hunk.local_content.is_empty() {
-                    for line in hunk.local_content.lines() {
+                    for line in split_lines(&hunk.local_content) {
                         result_lines.push(line.to_string());
                     }
                 }
@@ -294,7 +336,7 @@ fn build_result(base: &str, _local: &str, _remote: &str, hunks: &[MergeHunk]) ->
 
 /// Compute result_range for each hunk based on the generated result content.
 fn compute_result_ranges(hunks: &mut Vec<MergeHunk>, result: &str) {
-    let result_lines: Vec<&str> = result.lines().collect();
+    let result_lines: Vec<&str> = split_lines(result);
 
     for hunk in hunks.iter_mut() {
         let search_content = match hunk.source {
@@ -312,7 +354,7 @@ fn compute_result_ranges(hunks: &mut Vec<MergeHunk>, result: &str) {
             continue;
         }
 
-        let search_lines: Vec<&str> = search_content.lines().collect();
+        let search_lines: Vec<&str> = split_lines(&search_content);
         if search_lines.is_empty() {
             hunk.result_range = LineRange::empty();
             continue;
@@ -345,25 +387,51 @@ pub fn rebuild_result(
         return current_result.to_string();
     }
 
-    let lines: Vec<&str> = current_result.lines().collect();
+    let lines: Vec<&str> = split_lines(current_result);
     let mut result = Vec::new();
 
     // Lines before the hunk
-    for i in 0..(hunk.result_range.start - 1).min(lines.len()) {
-        result.push(lines[i].to_string());
+    for line in lines.iter().take(hunk.result_range.start - 1) {
+        result.push(line.to_string());
     }
 
     // Insert new content
-    if !new_content.is_empty() {
-        for line in new_content.lines() {
-            result.push(line.to_string());
-        }
+    for line in split_lines(new_content) {
+        result.push(line.to_string());
     }
 
     // Lines after the hunk
-    for i in hunk.result_range.end..lines.len() {
-        result.push(lines[i].to_string());
+    for line in lines.iter().skip(hunk.result_range.end) {
+        result.push(line.to_string());
     }
 
     result.join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflict_takes_in_every_overlapping_region() {
+        // Remote's one region overlaps both of local's
+        let (hunks, result) = three_way_merge("a\nb\nc\nd\ne\n", "a\nB\nc\nD\ne\n", "a\nb2\nc2\nd2\ne\n");
+
+        assert_eq!(hunks.len(), 1);
+        let hunk = &hunks[0];
+        assert_eq!(hunk.source, HunkSource::Conflict);
+        assert_eq!(hunk.base_content, "b\nc\nd");
+        assert_eq!(hunk.local_content, "B\nc\nD");
+        assert_eq!(hunk.remote_content, "b2\nc2\nd2");
+        assert_eq!(rebuild_result(&result, hunk, &hunk.local_content), "a\nB\nc\nD\ne");
+    }
+
+    #[test]
+    fn separate_changes_stay_separate_hunks() {
+        let (hunks, result) = three_way_merge("a\nb\nc\nd\ne\n", "a\nB\nc\nd\ne\n", "a\nb\nc\nD\ne\n");
+
+        let sources: Vec<HunkSource> = hunks.iter().map(|h| h.source).collect();
+        assert_eq!(sources, [HunkSource::Local, HunkSource::Remote]);
+        assert_eq!(result, "a\nB\nc\nD\ne");
+    }
+}
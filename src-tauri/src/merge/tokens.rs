@@ -0,0 +1,47 @@
+use tree_sitter::{Node, Tree};
+
+/// A leaf token of a parsed file.
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub identifier: bool,
+    pub start_byte: usize,
+    pub row: usize,
+    /// Number of ancestors, so re-nesting (e.g. Python indentation) shows up.
+    pub depth: usize,
+}
+
+impl Token<'_> {
+    /// Byte offset just past the token.
+    pub fn end_byte(&self) -> usize {
+        self.start_byte + self.text.len()
+    }
+}
+
+fn is_identifier(kind: &str) -> bool {
+    kind.contains("identifier") || kind == "name" || kind == "constant"
+}
+
+fn collect_tokens<'a>(node: Node, source: &'a str, depth: usize, tokens: &mut Vec<Token<'a>>) {
+    if node.child_count() == 0 {
+        tokens.push(Token {
+            text: &source[node.byte_range()],
+            identifier: is_identifier(node.kind()),
+            start_byte: node.start_byte(),
+            row: node.start_position().row,
+            depth,
+        });
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_tokens(child, source, depth + 1, tokens);
+    }
+}
+
+/// The leaf tokens of a tree in source order. Empty (missing) nodes are skipped.
+pub fn leaf_tokens<'a>(tree: &Tree, source: &'a str) -> Vec<Token<'a>> {
+    let mut tokens = Vec::new();
+    collect_tokens(tree.root_node(), source, 0, &mut tokens);
+    tokens.retain(|t| !t.text.is_empty());
+    tokens
+}
@@ -0,0 +1,300 @@
+use std::fmt;
+
+use super::hunk::LineRange;
+
+#[cfg(feature = "tree-sitter")]
+mod inner {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashMap;
+    use std::hash::{Hash, Hasher};
+    use std::sync::Arc;
+    use parking_lot::Mutex;
+    use tree_sitter::{InputEdit, Language, Parser, Point, Tree};
+
+    use super::LineRange;
+
+    /// Past this many cached trees the least recently used one is dropped, which
+    /// only happens when the sides are rewritten (e.g. by replaying renames) over
+    /// and over. The sides in use keep their trees.
+    const MAX_CACHED: usize = 16;
+
+    /// Parsed trees kept with a session, so structural features don't reparse
+    /// content they've already seen. Clones share the cache.
+    #[derive(Clone, Default)]
+    pub struct TreeCache {
+        trees: Arc<Mutex<CachedTrees>>,
+    }
+
+    #[derive(Default)]
+    struct CachedTrees {
+        /// (grammar, content hash) -> tree
+        by_content: HashMap<(String, u64), CachedTree>,
+        /// Bumped on every lookup, to find the least recently used tree.
+        clock: u64,
+        /// The last result parsed, reparsed incrementally as the result changes.
+        result: Option<ResultTree>,
+    }
+
+    struct CachedTree {
+        content: String,
+        tree: Tree,
+        last_used: u64,
+    }
+
+    struct ResultTree {
+        grammar: String,
+        content: String,
+        tree: Tree,
+        /// Whether `tree` has been edited to match `content` but not reparsed.
+        edited: bool,
+    }
+
+    fn content_hash(source: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Row and byte column of a byte offset.
+    fn point_at(source: &str, byte: usize) -> Point {
+        let before = &source[..byte];
+        let row = before.matches('\n').count();
+        let column = before.rfind('\n').map_or(byte, |nl| byte - nl - 1);
+        Point { row, column }
+    }
+
+    /// Describe the change from `old` to `new` as a single edit spanning
+    /// everything between their common prefix and common suffix.
+    fn edit_between(old: &str, new: &str) -> Option<InputEdit> {
+        if old == new {
+            return None;
+        }
+        let mut prefix = old
+            .bytes()
+            .zip(new.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+        let max_suffix = old.len().min(new.len()) - prefix;
+        let mut suffix = old
+            .bytes()
+            .rev()
+            .zip(new.bytes().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+            suffix -= 1;
+        }
+
+        let old_end_byte = old.len() - suffix;
+        let new_end_byte = new.len() - suffix;
+        Some(InputEdit {
+            start_byte: prefix,
+            old_end_byte,
+            new_end_byte,
+            start_position: point_at(old, prefix),
+            old_end_position: point_at(old, old_end_byte),
+            new_end_position: point_at(new, new_end_byte),
+        })
+    }
+
+    /// Parse `source` from scratch, or incrementally from the tree of a previous
+    /// version of it.
+    pub fn parse_from(language: &Language, source: &str, previous: Option<(&str, &Tree)>) -> Option<Tree> {
+        let mut parser = Parser::new();
+        parser.set_language(language).ok()?;
+        let Some((old_source, old_tree)) = previous else {
+            return parser.parse(source, None);
+        };
+        let Some(edit) = edit_between(old_source, source) else {
+            return Some(old_tree.clone());
+        };
+        let mut tree = old_tree.clone();
+        tree.edit(&edit);
+        parser.parse(source, Some(&tree))
+    }
+
+    /// The edit replacing the result lines in `lines` (1-based, inclusive) of
+    /// `old` to give `new`. Falls back to comparing the texts if the lines don't
+    /// line up with the change.
+    fn edit_of_lines(old: &str, new: &str, lines: LineRange) -> Option<InputEdit> {
+        let line_start = |n: usize| {
+            old.split_inclusive('\n')
+                .take(n.saturating_sub(1))
+                .map(str::len)
+                .sum::<usize>()
+                .min(old.len())
+        };
+        let mut start_byte = line_start(lines.start);
+        let mut old_end_byte = old[start_byte..]
+            .split_inclusive('\n')
+            .take(lines.line_count())
+            .map(str::len)
+            .sum::<usize>()
+            + start_byte;
+        // The line range ends before its last newline
+        if old_end_byte > start_byte && old[..old_end_byte].ends_with('\n') {
+            old_end_byte -= 1;
+        }
+        let delta = new.len() as isize - old.len() as isize;
+        if (old_end_byte as isize + delta) < start_byte as isize {
+            // All the lines went, so one of the newlines around them went too
+            if old_end_byte < old.len() {
+                old_end_byte += 1;
+            } else {
+                start_byte = start_byte.saturating_sub(1);
+            }
+        }
+        let new_end_byte = (old_end_byte as isize + delta).max(start_byte as isize) as usize;
+
+        let lines_up = new_end_byte <= new.len()
+            && old.get(..start_byte) == new.get(..start_byte)
+            && old.get(old_end_byte..) == new.get(new_end_byte..);
+        if !lines_up {
+            return edit_between(old, new);
+        }
+        Some(InputEdit {
+            start_byte,
+            old_end_byte,
+            new_end_byte,
+            start_position: point_at(old, start_byte),
+            old_end_position: point_at(old, old_end_byte),
+            new_end_position: point_at(new, new_end_byte),
+        })
+    }
+
+    impl TreeCache {
+        /// The tree for `source` under the named grammar, parsed on first use.
+        pub fn parse(&self, grammar: &str, language: &Language, source: &str) -> Option<Tree> {
+            let key = (grammar.to_string(), content_hash(source));
+            let mut trees = self.trees.lock();
+            trees.clock += 1;
+            let now = trees.clock;
+            if let Some(cached) = trees.by_content.get_mut(&key) {
+                if cached.content == source {
+                    cached.last_used = now;
+                    return Some(cached.tree.clone());
+                }
+            }
+
+            let tree = parse_from(language, source, None)?;
+            if trees.by_content.len() >= MAX_CACHED {
+                let oldest = trees
+                    .by_content
+                    .iter()
+                    .min_by_key(|(_, cached)| cached.last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    trees.by_content.remove(&oldest);
+                }
+            }
+            let cached = CachedTree {
+                content: source.to_string(),
+                tree: tree.clone(),
+                last_used: now,
+            };
+            trees.by_content.insert(key, cached);
+            Some(tree)
+        }
+
+        /// The tree for the merge result, reparsed incrementally from the last
+        /// result parsed so resolving hunks and typing stay cheap.
+        pub fn parse_result(&self, grammar: &str, language: &Language, source: &str) -> Option<Tree> {
+            let mut trees = self.trees.lock();
+            let previous = trees.result.as_ref().filter(|r| r.grammar == grammar);
+            let tree = match previous {
+                Some(r) if r.edited && r.content == source => {
+                    let mut parser = Parser::new();
+                    parser.set_language(language).ok()?;
+                    parser.parse(source, Some(&r.tree))?
+                }
+                Some(r) if !r.edited => parse_from(language, source, Some((&r.content, &r.tree)))?,
+                _ => parse_from(language, source, None)?,
+            };
+            trees.result = Some(ResultTree {
+                grammar: grammar.to_string(),
+                content: source.to_string(),
+                tree: tree.clone(),
+                edited: false,
+            });
+            Some(tree)
+        }
+
+        /// Follow a resolution that replaced the result lines in `lines`, editing
+        /// the cached result tree so the next parse of the result is incremental.
+        pub fn edit_result_lines(&self, old: &str, new: &str, lines: LineRange) {
+            self.edit_result_with(old, new, || edit_of_lines(old, new, lines));
+        }
+
+        /// Follow an edit typed into the result.
+        pub fn edit_result(&self, old: &str, new: &str) {
+            self.edit_result_with(old, new, || edit_between(old, new));
+        }
+
+        fn edit_result_with(&self, old: &str, new: &str, edit: impl FnOnce() -> Option<InputEdit>) {
+            let mut trees = self.trees.lock();
+            let Some(result) = trees.result.as_mut() else { return };
+            if result.content != old {
+                // The tree is of some other result; it can't follow this edit
+                trees.result = None;
+                return;
+            }
+            if let Some(edit) = edit() {
+                result.tree.edit(&edit);
+                result.content = new.to_string();
+                result.edited = true;
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn replaced(old: &str, new: &str, start: usize, end: usize) -> (usize, usize, usize) {
+            let edit = edit_of_lines(old, new, LineRange::new(start, end)).unwrap();
+            assert_eq!(&old[..edit.start_byte], &new[..edit.start_byte]);
+            assert_eq!(&old[edit.old_end_byte..], &new[edit.new_end_byte..]);
+            (edit.start_byte, edit.old_end_byte, edit.new_end_byte)
+        }
+
+        #[test]
+        fn edits_cover_the_replaced_lines() {
+            assert_eq!(replaced("a\nX\nb", "a\nY\nZ\nb", 2, 2), (2, 3, 5));
+            assert_eq!(replaced("X\nb", "Y\nb", 1, 1), (0, 1, 1));
+            assert_eq!(replaced("a\nX", "a\nYY", 2, 2), (2, 3, 4));
+        }
+
+        #[test]
+        fn removed_lines_take_a_newline_with_them() {
+            assert_eq!(replaced("a\nX\nb", "a\nb", 2, 2), (2, 4, 2));
+            assert_eq!(replaced("a\nX", "a", 2, 2), (1, 3, 1));
+        }
+    }
+}
+
+#[cfg(not(feature = "tree-sitter"))]
+mod inner {
+    /// Parsed trees kept with a session; empty without tree-sitter support.
+    #[derive(Clone, Default)]
+    pub struct TreeCache;
+
+    impl TreeCache {
+        pub fn edit_result_lines(&self, _old: &str, _new: &str, _lines: super::LineRange) {}
+
+        pub fn edit_result(&self, _old: &str, _new: &str) {}
+    }
+}
+
+#[cfg(feature = "tree-sitter")]
+pub use inner::parse_from;
+pub use inner::TreeCache;
+
+impl fmt::Debug for TreeCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TreeCache").finish_non_exhaustive()
+    }
+}
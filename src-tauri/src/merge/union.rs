@@ -0,0 +1,142 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::hunk::MergeSession;
+use super::resolver::auto_resolve_conflicts;
+
+/// File names that are sets of lines, where both sides appending is never a real conflict.
+const UNION_FILE_NAMES: &[&str] = &[
+    ".gitignore",
+    ".dockerignore",
+    ".npmignore",
+    ".prettierignore",
+    ".eslintignore",
+    "CODEOWNERS",
+    "requirements.txt",
+];
+
+/// Check whether a path looks like an order-insensitive line-set file.
+pub fn is_union_file(path: &str) -> bool {
+    let name = match Path::new(path).file_name() {
+        Some(n) => n.to_string_lossy().to_string(),
+        None => return false,
+    };
+    let lower = name.to_lowercase();
+
+    UNION_FILE_NAMES.contains(&name.as_str())
+        || (lower.starts_with("requirements") && lower.ends_with(".txt"))
+        || lower.contains("allowlist")
+}
+
+/// Merge three versions of a line set.
+///
+/// Lines either side removed from base are dropped, additions from both sides are kept,
+/// and the result follows local's order with remote additions placed after the line
+/// that precedes them in remote. Repeated non-blank lines are kept only once.
+pub fn union_merge(base: &str, local: &str, remote: &str) -> String {
+    let base_set: HashSet<&str> = base.lines().collect();
+    let local_set: HashSet<&str> = local.lines().collect();
+    let remote_set: HashSet<&str> = remote.lines().collect();
+
+    let removed_by_local: HashSet<&str> =
+        base_set.iter().filter(|l| !local_set.contains(*l)).copied().collect();
+    let removed_by_remote: HashSet<&str> =
+        base_set.iter().filter(|l| !remote_set.contains(*l)).copied().collect();
+
+    let mut merged: Vec<&str> = local
+        .lines()
+        .filter(|l| !removed_by_remote.contains(l))
+        .collect();
+
+    // Slot remote additions in after the nearest preceding remote line we kept
+    let mut insert_at = 0;
+    for line in remote.lines() {
+        if let Some(pos) = merged.iter().position(|m| *m == line) {
+            insert_at = pos + 1;
+        } else if !removed_by_local.contains(line) {
+            merged.insert(insert_at, line);
+            insert_at += 1;
+        }
+    }
+
+    let mut seen = HashSet::new();
+    merged
+        .into_iter()
+        .filter(|l| l.trim().is_empty() || seen.insert(*l))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Resolve conflict hunks in a union-strategy session by merging them as line sets.
+/// Added lines already present elsewhere in the result are not repeated.
+pub fn union_auto_resolve(session: &mut MergeSession) -> usize {
+    auto_resolve_conflicts(session, |session, hunk| {
+        let merged = union_merge(&hunk.base_content, &hunk.local_content, &hunk.remote_content);
+
+        let base_lines: HashSet<&str> = hunk.base_content.lines().collect();
+        let elsewhere: HashSet<&str> = session
+            .result_content
+            .lines()
+            .enumerate()
+            .filter(|(i, _)| {
+                hunk.result_range.is_empty()
+                    || *i + 1 < hunk.result_range.start
+                    || *i + 1 > hunk.result_range.end
+            })
+            .map(|(_, l)| l)
+            .collect();
+
+        let new_content = merged
+            .lines()
+            .filter(|l| base_lines.contains(l) || l.trim().is_empty() || !elsewhere.contains(l))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Some(new_content)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::hunk::HunkStatus;
+
+    #[test]
+    fn recognises_line_set_files() {
+        assert!(is_union_file("web/.gitignore"));
+        assert!(is_union_file(".github/CODEOWNERS"));
+        assert!(is_union_file("requirements-dev.txt"));
+        assert!(is_union_file("config/domain-allowlist.txt"));
+        assert!(!is_union_file("src/main.rs"));
+        assert!(!is_union_file("notes.txt"));
+    }
+
+    #[test]
+    fn keeps_additions_and_removals_from_both_sides() {
+        let base = "target\nnode_modules\n*.log\n";
+        let local = "target\nnode_modules\n*.log\n.env\n";
+        let remote = "target\n*.log\ndist\n";
+
+        assert_eq!(union_merge(base, local, remote), "target\n*.log\ndist\n.env");
+    }
+
+    #[test]
+    fn lines_added_on_both_sides_are_kept_once() {
+        let base = "a\nb\n";
+        let local = "a\nb\nc\nd\n";
+        let remote = "a\nb\nd\ne\n";
+
+        assert_eq!(union_merge(base, local, remote), "a\nb\nc\nd\ne");
+    }
+
+    #[test]
+    fn resolves_conflicting_appends() {
+        let base = "target\n*.log\n";
+        let local = "target\n*.log\n.env\n.idea/\n";
+        let remote = "target\n*.log\ndist\n.idea/\n";
+        let mut session = MergeSession::for_test(base, local, remote, "plaintext");
+
+        assert_eq!(union_auto_resolve(&mut session), 1);
+        assert_eq!(session.result_content, "target\n*.log\ndist\n.env\n.idea/");
+        assert!(session.hunks.iter().all(|h| h.status != HunkStatus::Unresolved));
+    }
+}
@@ -0,0 +1,319 @@
+use std::cmp::Ordering;
+use serde::{Deserialize, Serialize};
+
+use super::hunk::{HunkSource, HunkStatus, MergeHunk, MergeSession};
+use super::resolver::auto_resolve_conflicts;
+
+/// Which side of a version-only conflict to keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionPick {
+    Max,
+    Min,
+}
+
+/// A conflict hunk whose sides differ only in version or number literals.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionConflict {
+    pub hunk_id: usize,
+    pub local_versions: Vec<String>,
+    pub remote_versions: Vec<String>,
+    /// The literals kept once the hunk is resolved, empty until then.
+    pub chosen: Vec<String>,
+}
+
+/// A piece of hunk text: plain text or a version/number literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Version(&'a str),
+}
+
+/// Split text into plain text and version literals: dotted numbers like `1.4.2`
+/// or `2.0.0-rc.1+build`, tags like `v3`, and numbers assigned with `=` like
+/// `retries = "3"`. Other numbers (dates, ports, issue numbers, digits inside
+/// identifiers like `x86_64`) stay part of the text.
+fn segments(text: &str) -> Vec<Segment<'_>> {
+    let bytes = text.as_bytes();
+    let is_word = |i: usize| bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_';
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() || (i > 0 && is_word(i - 1)) {
+            // A tag like `v1.4.2` starts at its `v`
+            let tagged = bytes[i].is_ascii_digit()
+                && matches!(bytes[i - 1], b'v' | b'V')
+                && (i == 1 || !is_word(i - 2));
+            if !tagged {
+                i += 1;
+                continue;
+            }
+        }
+
+        let start = if i > 0 && is_word(i - 1) { i - 1 } else { i };
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        while i + 1 < bytes.len() && bytes[i] == b'.' && bytes[i + 1].is_ascii_digit() {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+        }
+        let dotted = text[start..i].contains('.');
+        // Pre-release and build metadata: `-rc.1`, `+build.5`
+        for marker in [b'-', b'+'] {
+            if i + 1 < bytes.len() && bytes[i] == marker && bytes[i + 1].is_ascii_alphanumeric() {
+                let mut j = i + 1;
+                while j < bytes.len() && (bytes[j].is_ascii_alphanumeric() || bytes[j] == b'.') {
+                    j += 1;
+                }
+                while bytes[j - 1] == b'.' {
+                    j -= 1;
+                }
+                i = j;
+            }
+        }
+
+        let tagged = !bytes[start].is_ascii_digit();
+        // A plain number on its own after `=`, not part of a date or time
+        let assigned = text[..start].trim_end_matches([' ', '\t', '"', '\'']).ends_with('=')
+            && text[start..i].bytes().all(|b| b.is_ascii_digit())
+            && !matches!(bytes.get(i), Some(b'-' | b'/' | b':'));
+        if !dotted && !tagged && !assigned {
+            continue;
+        }
+        if text_start < start {
+            segments.push(Segment::Text(&text[text_start..start]));
+        }
+        segments.push(Segment::Version(&text[start..i]));
+        text_start = i;
+    }
+
+    if text_start < text.len() {
+        segments.push(Segment::Text(&text[text_start..]));
+    }
+    segments
+}
+
+/// Compare two version literals: numeric components first, then a release sorts
+/// above any pre-release of the same version. A `v` prefix and build metadata
+/// are ignored.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(v: &str) -> (Vec<u64>, Option<&str>) {
+        let v = v.trim_start_matches(['v', 'V']);
+        let v = v.split('+').next().unwrap_or(v);
+        let (core, pre) = match v.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (v, None),
+        };
+        let parts = core.split('.').map(|p| p.parse().unwrap_or(u64::MAX)).collect();
+        (parts, pre)
+    }
+
+    let (a_parts, a_pre) = split(a);
+    let (b_parts, b_pre) = split(b);
+    let len = a_parts.len().max(b_parts.len());
+    for i in 0..len {
+        let ordering = a_parts.get(i).unwrap_or(&0).cmp(b_parts.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a_pre), Some(b_pre)) => {
+            for (a_id, b_id) in a_pre.split('.').zip(b_pre.split('.')) {
+                let ordering = match (a_id.parse::<u64>(), b_id.parse::<u64>()) {
+                    (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num),
+                    _ => a_id.cmp(b_id),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            a_pre.split('.').count().cmp(&b_pre.split('.').count())
+        }
+    }
+}
+
+/// Pair up the differing literals of two texts that are otherwise identical.
+/// Returns `None` if anything besides version/number literals differs.
+fn differing_versions<'a>(local: &'a str, remote: &'a str) -> Option<Vec<(&'a str, &'a str)>> {
+    let local_segments = segments(local);
+    let remote_segments = segments(remote);
+    if local_segments.len() != remote_segments.len() {
+        return None;
+    }
+
+    let mut pairs = Vec::new();
+    for (l, r) in local_segments.iter().zip(remote_segments.iter()) {
+        match (l, r) {
+            (Segment::Text(lt), Segment::Text(rt)) if lt == rt => {}
+            (Segment::Version(lv), Segment::Version(rv)) => {
+                if lv != rv {
+                    pairs.push((*lv, *rv));
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    if pairs.is_empty() {
+        None
+    } else {
+        Some(pairs)
+    }
+}
+
+/// Resolve a version-only conflict by keeping the higher or lower literal at each
+/// position. Returns the resolved text and the literals that were chosen.
+pub fn pick_versions(local: &str, remote: &str, pick: VersionPick) -> Option<(String, Vec<String>)> {
+    differing_versions(local, remote)?;
+
+    let mut content = String::new();
+    let mut chosen = Vec::new();
+    for (l, r) in segments(local).into_iter().zip(segments(remote)) {
+        match (l, r) {
+            (Segment::Version(lv), Segment::Version(rv)) if lv != rv => {
+                let higher = compare_versions(lv, rv) != Ordering::Less;
+                let keep = match pick {
+                    VersionPick::Max if higher => lv,
+                    VersionPick::Min if !higher => lv,
+                    _ => rv,
+                };
+                content.push_str(keep);
+                chosen.push(keep.to_string());
+            }
+            (Segment::Version(v), _) | (Segment::Text(v), _) => content.push_str(v),
+        }
+    }
+
+    Some((content, chosen))
+}
+
+/// Find the conflict hunks whose sides differ only in version/number literals.
+pub fn find_version_conflicts(hunks: &[MergeHunk]) -> Vec<VersionConflict> {
+    hunks
+        .iter()
+        .filter(|h| h.source == HunkSource::Conflict)
+        .filter_map(|h| {
+            let pairs = differing_versions(&h.local_content, &h.remote_content)?;
+            Some(VersionConflict {
+                hunk_id: h.id,
+                local_versions: pairs.iter().map(|(l, _)| l.to_string()).collect(),
+                remote_versions: pairs.iter().map(|(_, r)| r.to_string()).collect(),
+                chosen: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// Record which literals were kept for a resolved version conflict.
+pub fn record_choice(session: &mut MergeSession, hunk_id: usize, chosen: Vec<String>) {
+    if let Some(conflict) = session.version_conflicts.iter_mut().find(|c| c.hunk_id == hunk_id) {
+        conflict.chosen = chosen;
+    }
+}
+
+/// Map a version-pick resolution status to the pick it stands for.
+pub fn pick_for_status(status: HunkStatus) -> Option<VersionPick> {
+    match status {
+        HunkStatus::AcceptedHigherVersion => Some(VersionPick::Max),
+        HunkStatus::AcceptedLowerVersion => Some(VersionPick::Min),
+        _ => None,
+    }
+}
+
+/// Resolve every version-only conflict with the configured pick.
+pub fn version_auto_resolve(session: &mut MergeSession, pick: VersionPick) -> usize {
+    let mut choices = Vec::new();
+    let resolved_count = auto_resolve_conflicts(session, |_, hunk| {
+        let (content, chosen) = pick_versions(&hunk.local_content, &hunk.remote_content, pick)?;
+        choices.push((hunk.id, chosen));
+        Some(content)
+    });
+
+    for (hunk_id, chosen) in choices {
+        record_choice(session, hunk_id, chosen);
+    }
+    resolved_count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::hunk::HunkStatus;
+
+    #[test]
+    fn tags_compare_by_number() {
+        assert_eq!(compare_versions("v1.4.2", "v1.10.0"), Ordering::Less);
+        assert_eq!(compare_versions("V2", "v1.9"), Ordering::Greater);
+
+        let (content, chosen) = pick_versions("tag: v1.4.2", "tag: v1.10.0", VersionPick::Max).unwrap();
+        assert_eq!(content, "tag: v1.10.0");
+        assert_eq!(chosen, ["v1.10.0"]);
+        let (content, _) = pick_versions("tag: v1.4.2", "tag: v1.10.0", VersionPick::Min).unwrap();
+        assert_eq!(content, "tag: v1.4.2");
+    }
+
+    #[test]
+    fn pre_releases_sort_below_the_release() {
+        let ordered = ["1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-beta", "1.0.0-rc.2", "1.0.0-rc.10", "1.0.0", "1.0.1"];
+        for pair in ordered.windows(2) {
+            assert_eq!(compare_versions(pair[0], pair[1]), Ordering::Less, "{} < {}", pair[0], pair[1]);
+        }
+        assert_eq!(compare_versions("1.0.0+build.5", "1.0.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn picks_each_differing_literal() {
+        let local = "serde = \"1.0.150\"\nretries = 3";
+        let remote = "serde = \"1.0.160\"\nretries = 5";
+        let (content, chosen) = pick_versions(local, remote, VersionPick::Max).unwrap();
+        assert_eq!(content, "serde = \"1.0.160\"\nretries = 5");
+        assert_eq!(chosen, ["1.0.160", "5"]);
+    }
+
+    #[test]
+    fn dates_and_other_numbers_are_not_versions() {
+        for (local, remote) in [
+            ("released: 2024-01-05", "released: 2024-02-01"),
+            ("date = 2024-01-05", "date = 2024-02-01"),
+            ("listen localhost:8080", "listen localhost:8081"),
+            ("fixes #123", "fixes #124"),
+            ("pages 1-2", "pages 1-3"),
+            ("target x86_64", "target x86_32"),
+        ] {
+            assert_eq!(pick_versions(local, remote, VersionPick::Max), None, "{local}");
+        }
+    }
+
+    #[test]
+    fn auto_resolve_records_the_chosen_literals() {
+        let base = "[package]\nname = \"w\"\nversion = \"1.4.2\"\nupdated = 2024-01-05\n";
+        let local = base.replace("1.4.2", "1.5.0").replace("01-05", "02-01");
+        let remote = base.replace("1.4.2", "1.4.3").replace("01-05", "03-01");
+        let mut session = MergeSession::for_test(base, &local, &remote, "toml");
+        session.version_conflicts = find_version_conflicts(&session.hunks);
+
+        // The version and the date conflict together; the date isn't a version
+        assert!(session.version_conflicts.is_empty());
+        assert_eq!(version_auto_resolve(&mut session, VersionPick::Max), 0);
+
+        let local = base.replace("1.4.2", "1.5.0");
+        let remote = base.replace("1.4.2", "1.4.3");
+        let mut session = MergeSession::for_test(base, &local, &remote, "toml");
+        session.version_conflicts = find_version_conflicts(&session.hunks);
+        assert_eq!(version_auto_resolve(&mut session, VersionPick::Max), 1);
+        assert_eq!(session.result_content, local.trim_end());
+        assert_eq!(session.version_conflicts[0].chosen, ["1.5.0"]);
+        assert!(session.hunks.iter().all(|h| h.status != HunkStatus::Unresolved));
+    }
+}
@@ -1,16 +1,19 @@
 use parking_lot::Mutex;
+use crate::config::Config;
 use crate::merge::hunk::MergeSession;
 
 pub struct AppState {
     pub session: Mutex<Option<MergeSession>>,
+    pub config: Config,
     /// Track whether the user explicitly aborted (exit code 1) vs saved (exit code 0)
     pub aborted: Mutex<bool>,
 }
 
 impl AppState {
-    pub fn new() -> Self {
+    pub fn new(config: Config) -> Self {
         Self {
             session: Mutex::new(None),
+            config,
             aborted: Mutex::new(false),
         }
     }
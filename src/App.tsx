@@ -4,8 +4,17 @@ import StatusBar from "./components/StatusBar";
 import { useMergeSession } from "./hooks/useMergeSession";
 
 export default function App() {
-  const { session, loading, error, resolveHunk, saveResult, setResultContent } =
-    useMergeSession();
+  const {
+    session,
+    loading,
+    error,
+    previews,
+    resolveHunk,
+    applyProposal,
+    replayRenames,
+    saveResult,
+    setResultContent,
+  } = useMergeSession();
 
   if (loading) {
     return (
@@ -36,10 +45,17 @@ export default function App() {
       <Toolbar
         filename={session.mergedPath}
         onSave={saveResult}
+        renames={session.identifierRenames}
+        resolvedConflicts={
+          session.hunks.filter((h) => h.source === "Conflict" && h.status !== "Unresolved").length
+        }
+        onReplayRenames={replayRenames}
       />
       <MergeView
         session={session}
+        previews={previews}
         resolveHunk={resolveHunk}
+        applyProposal={applyProposal}
         onResultEdit={setResultContent}
       />
       <StatusBar session={session} />
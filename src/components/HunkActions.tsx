@@ -1,11 +1,51 @@
 import { useEffect, useState } from "react";
 import type * as Monaco from "monaco-editor";
-import type { MergeHunk, HunkStatus } from "../lib/types";
+import type {
+  MergeHunk,
+  HunkPreview,
+  HunkStatus,
+  RefusalReason,
+  VersionConflict,
+} from "../lib/types";
 
 interface HunkActionsProps {
   hunks: MergeHunk[];
+  versionConflicts: VersionConflict[];
+  previews: HunkPreview[];
   editor: Monaco.editor.IStandaloneCodeEditor | null;
   onResolve: (hunkId: number, status: HunkStatus) => void;
+  onApplyProposal: (hunkId: number) => void;
+}
+
+const REFUSAL_TEXT: Record<RefusalReason, string> = {
+  ParseFailure: "a side could not be parsed",
+  SpanningNode: "both sides edited inside the same node",
+  SameNodeChanged: "both sides changed the same node",
+  DeleteModify: "one side deleted a node the other edited",
+  BothRenamed: "both sides renamed the same node",
+  OverlappingNodes: "both sides inserted or moved nodes at the same place",
+  SyntaxErrors: "the merged code would not parse",
+  Misaligned: "the whole-document merge doesn't line up with this hunk",
+};
+
+// Tooltip describing what the Magic Wand would do with a hunk.
+function describePreview(preview: HunkPreview): string {
+  if (preview.refusal) {
+    const { reason, kind, name } = preview.refusal;
+    const node = kind ? ` (${[kind, name].filter(Boolean).join(" ")})` : "";
+    return `Magic Wand can't resolve this: ${REFUSAL_TEXT[reason]}${node}`;
+  }
+  if (preview.status === "FormattingMerged") {
+    return "Magic Wand: one side only reformatted; apply the other side's edits to it";
+  }
+  const nodes = preview.nodes
+    .filter((n) => n.outcome !== "Unchanged")
+    .map((n) => `${n.kind} ${n.name ?? ""}: ${n.outcome}`.replace(" :", ":"));
+  const title =
+    preview.status === "HeuristicMerged"
+      ? "Magic Wand (heuristic, no grammar for this language):"
+      : "Magic Wand:";
+  return [title, ...nodes].join("\n");
 }
 
 interface ButtonPosition {
@@ -13,7 +53,14 @@ interface ButtonPosition {
   top: number;
 }
 
-export default function HunkActions({ hunks, editor, onResolve }: HunkActionsProps) {
+export default function HunkActions({
+  hunks,
+  versionConflicts,
+  previews,
+  editor,
+  onResolve,
+  onApplyProposal,
+}: HunkActionsProps) {
   const [positions, setPositions] = useState<ButtonPosition[]>([]);
 
   useEffect(() => {
@@ -50,35 +97,66 @@ export default function HunkActions({ hunks, editor, onResolve }: HunkActionsPro
 
   return (
     <>
-      {positions.map((pos) => (
-        <div
-          key={pos.hunkId}
-          className="hunk-actions"
-          style={{ top: pos.top, right: 20 }}
-        >
-          <button
-            className="accept-local"
-            onClick={() => onResolve(pos.hunkId, "AcceptedLocal")}
-            title="Accept Local (Cmd+1)"
-          >
-            Local
-          </button>
-          <button
-            className="accept-remote"
-            onClick={() => onResolve(pos.hunkId, "AcceptedRemote")}
-            title="Accept Remote (Cmd+2)"
-          >
-            Remote
-          </button>
-          <button
-            className="accept-both"
-            onClick={() => onResolve(pos.hunkId, "AcceptedBoth")}
-            title="Accept Both"
+      {positions.map((pos) => {
+        const preview = previews.find((p) => p.hunkId === pos.hunkId);
+        return (
+          <div
+            key={pos.hunkId}
+            className="hunk-actions"
+            style={{ top: pos.top, right: 20 }}
           >
-            Both
-          </button>
-        </div>
-      ))}
+            <button
+              className="accept-local"
+              onClick={() => onResolve(pos.hunkId, "AcceptedLocal")}
+              title="Accept Local (Cmd+1)"
+            >
+              Local
+            </button>
+            <button
+              className="accept-remote"
+              onClick={() => onResolve(pos.hunkId, "AcceptedRemote")}
+              title="Accept Remote (Cmd+2)"
+            >
+              Remote
+            </button>
+            <button
+              className="accept-both"
+              onClick={() => onResolve(pos.hunkId, "AcceptedBoth")}
+              title="Accept Both"
+            >
+              Both
+            </button>
+            {versionConflicts.some((c) => c.hunkId === pos.hunkId) && (
+              <>
+                <button
+                  className="accept-version"
+                  onClick={() => onResolve(pos.hunkId, "AcceptedHigherVersion")}
+                  title="Take the higher version"
+                >
+                  Max
+                </button>
+                <button
+                  className="accept-version"
+                  onClick={() => onResolve(pos.hunkId, "AcceptedLowerVersion")}
+                  title="Take the lower version"
+                >
+                  Min
+                </button>
+              </>
+            )}
+            {preview && (
+              <button
+                className="accept-wand"
+                disabled={!preview.proposal}
+                onClick={() => onApplyProposal(pos.hunkId)}
+                title={describePreview(preview)}
+              >
+                Wand
+              </button>
+            )}
+          </div>
+        );
+      })}
     </>
   );
 }
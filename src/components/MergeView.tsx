@@ -10,17 +10,21 @@ import {
   buildRemoteDecorations,
   buildResultDecorations,
 } from "../lib/decorations";
-import type { MergeSession, HunkStatus } from "../lib/types";
+import type { MergeSession, HunkPreview, HunkStatus } from "../lib/types";
 
 interface MergeViewProps {
   session: MergeSession;
+  previews: HunkPreview[];
   resolveHunk: (hunkId: number, status: HunkStatus) => void;
+  applyProposal: (hunkId: number) => void;
   onResultEdit: (content: string) => void;
 }
 
 export default function MergeView({
   session,
+  previews,
   resolveHunk,
+  applyProposal,
   onResultEdit,
 }: MergeViewProps) {
   const localEditorRef = useRef<Monaco.editor.IStandaloneCodeEditor | null>(null);
@@ -97,8 +101,11 @@ export default function MergeView({
               />
               <HunkActions
                 hunks={session.hunks}
+                versionConflicts={session.versionConflicts}
+                previews={previews}
                 editor={resultEditorRef.current}
                 onResolve={resolveHunk}
+                onApplyProposal={applyProposal}
               />
             </div>
           </Panel>
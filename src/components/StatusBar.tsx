@@ -8,6 +8,7 @@ export default function StatusBar({ session }: StatusBarProps) {
   const conflicts = session.hunks.filter((h) => h.source === "Conflict");
   const resolved = conflicts.filter((h) => h.status !== "Unresolved");
   const allResolved = conflicts.length > 0 && resolved.length === conflicts.length;
+  const pickedVersions = session.versionConflicts.filter((c) => c.chosen.length > 0);
 
   return (
     <div className={`status-bar ${allResolved ? "all-resolved" : ""}`}>
@@ -17,8 +18,45 @@ export default function StatusBar({ session }: StatusBarProps) {
             ? "No conflicts"
             : `${resolved.length}/${conflicts.length} conflicts resolved`}
         </span>
+        {session.renames.length > 0 && (
+          <span
+            title={session.renames
+              .map((r) => `${r.from} → ${r.to} (${r.side})`)
+              .join("\n")}
+          >
+            {session.renames.length === 1
+              ? `Renamed ${session.renames[0].from} → ${session.renames[0].to}`
+              : `${session.renames.length} renames detected`}
+          </span>
+        )}
+        {pickedVersions.length > 0 && (
+          <span
+            title={pickedVersions
+              .map((c) => `${c.localVersions.join(", ")} / ${c.remoteVersions.join(", ")} → ${c.chosen.join(", ")}`)
+              .join("\n")}
+          >
+            {pickedVersions.length === 1
+              ? `Kept ${pickedVersions[0].chosen.join(", ")}`
+              : `${pickedVersions.length} version conflicts picked`}
+          </span>
+        )}
+        {session.lockfile?.regenerate && (
+          <span title="Conflicts were resolved with the local lockfile">
+            Run {session.lockfile.regenerate} to regenerate the lockfile
+          </span>
+        )}
+        {session.lockfile && session.lockfile.warnings.length > 0 && (
+          <span title={session.lockfile.warnings.join("\n")}>
+            {session.lockfile.warnings.length === 1
+              ? "1 package version picked"
+              : `${session.lockfile.warnings.length} package versions picked`}
+          </span>
+        )}
       </div>
       <div className="status-right">
+        {session.normalized && (
+          <span title="Base, local and remote were formatted before merging">formatted</span>
+        )}
         <span>{session.language || "plaintext"}</span>
       </div>
     </div>
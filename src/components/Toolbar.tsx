@@ -1,11 +1,22 @@
 import { abortMerge, autoResolve } from "../lib/tauri-commands";
+import type { IdentifierRename } from "../lib/types";
 
 interface ToolbarProps {
   filename: string;
   onSave: () => void;
+  renames: IdentifierRename[];
+  /** Conflicts already resolved, which replaying renames discards. */
+  resolvedConflicts: number;
+  onReplayRenames: (renames: IdentifierRename[]) => void;
 }
 
-export default function Toolbar({ filename, onSave }: ToolbarProps) {
+export default function Toolbar({
+  filename,
+  onSave,
+  renames,
+  resolvedConflicts,
+  onReplayRenames,
+}: ToolbarProps) {
   const handleAbort = async () => {
     try {
       await abortMerge();
@@ -23,7 +34,17 @@ export default function Toolbar({ filename, onSave }: ToolbarProps) {
     }
   };
 
+  const handleReplayRenames = () => {
+    const discarded =
+      resolvedConflicts === 1 ? "1 resolved conflict" : `${resolvedConflicts} resolved conflicts`;
+    if (resolvedConflicts > 0 && !window.confirm(`Replaying renames merges again and discards ${discarded}. Continue?`)) {
+      return;
+    }
+    onReplayRenames(pendingRenames);
+  };
+
   const basename = filename.split("/").pop() || filename;
+  const pendingRenames = renames.filter((r) => !r.replayed);
 
   return (
     <div className="toolbar">
@@ -33,6 +54,16 @@ export default function Toolbar({ filename, onSave }: ToolbarProps) {
       <button className="magic" onClick={handleMagicWand} title="Magic Wand - Auto-resolve">
         Magic Wand
       </button>
+      {pendingRenames.length > 0 && (
+        <button
+          onClick={handleReplayRenames}
+          title={pendingRenames
+            .map((r) => `${r.from} → ${r.to} (renamed on ${r.side})`)
+            .join("\n")}
+        >
+          Replay {pendingRenames.length === 1 ? "rename" : `${pendingRenames.length} renames`}
+        </button>
+      )}
       <span className="filename" title={filename}>
         {basename}
       </span>
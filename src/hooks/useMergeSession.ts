@@ -1,11 +1,12 @@
 import { useCallback, useEffect, useState } from "react";
-import type { MergeSession, HunkStatus } from "../lib/types";
+import type { MergeSession, HunkPreview, HunkStatus, IdentifierRename } from "../lib/types";
 import * as commands from "../lib/tauri-commands";
 
 export function useMergeSession() {
   const [session, setSession] = useState<MergeSession | null>(null);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
+  const [previews, setPreviews] = useState<HunkPreview[]>([]);
 
   useEffect(() => {
     commands
@@ -20,6 +21,25 @@ export function useMergeSession() {
       });
   }, []);
 
+  // Re-preview the Magic Wand whenever the hunks change
+  const hunks = session?.hunks;
+  useEffect(() => {
+    if (!hunks) return;
+    commands
+      .previewStructural()
+      .then(setPreviews)
+      .catch((e) => console.error("Failed to preview structural merge:", e));
+  }, [hunks]);
+
+  const applyProposal = useCallback(async (hunkId: number) => {
+    try {
+      const updated = await commands.applyStructural([hunkId]);
+      setSession(updated);
+    } catch (e) {
+      console.error("Failed to apply structural resolution:", e);
+    }
+  }, []);
+
   const resolveHunk = useCallback(
     async (hunkId: number, status: HunkStatus) => {
       try {
@@ -32,6 +52,16 @@ export function useMergeSession() {
     [],
   );
 
+  const replayRenames = useCallback(async (renames: IdentifierRename[]) => {
+    try {
+      const updated = await commands.replayRenames(renames);
+      setSession(updated);
+    } catch (e) {
+      console.error("Failed to replay renames:", e);
+      window.alert(String(e));
+    }
+  }, []);
+
   const saveResult = useCallback(async () => {
     if (!session) return;
     try {
@@ -54,5 +84,15 @@ export function useMergeSession() {
     [session],
   );
 
-  return { session, loading, error, resolveHunk, saveResult, setResultContent };
+  return {
+    session,
+    loading,
+    error,
+    previews,
+    resolveHunk,
+    applyProposal,
+    replayRenames,
+    saveResult,
+    setResultContent,
+  };
 }
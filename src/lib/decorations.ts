@@ -11,6 +11,8 @@ const COLORS = {
   conflictBg: "rgba(255, 140, 0, 0.15)",
   conflictBorder: "rgba(255, 140, 0, 0.5)",
   resolvedBg: "rgba(100, 100, 100, 0.1)",
+  formattingBorder: "rgba(197, 134, 192, 0.6)",
+  heuristicBorder: "rgba(220, 220, 170, 0.6)",
 };
 
 function rangeForLines(
@@ -81,11 +83,16 @@ export function buildResultDecorations(
         range: rangeForLines(monaco, hunk.resultRange.start, hunk.resultRange.end),
         options: {
           isWholeLine: true,
-          className: isResolved
-            ? "hunk-resolved"
-            : isConflict
-              ? "hunk-conflict-result"
-              : "hunk-clean",
+          className:
+            hunk.status === "FormattingMerged"
+              ? "hunk-formatting"
+              : hunk.status === "HeuristicMerged"
+                ? "hunk-heuristic"
+                : isResolved
+                  ? "hunk-resolved"
+                  : isConflict
+                    ? "hunk-conflict-result"
+                    : "hunk-clean",
         },
       };
     });
@@ -97,6 +104,8 @@ export const decorationStyles = `
   .hunk-conflict { background: ${COLORS.conflictBg}; border-left: 3px solid ${COLORS.conflictBorder}; }
   .hunk-conflict-result { background: ${COLORS.conflictBg}; border-left: 3px solid ${COLORS.conflictBorder}; }
   .hunk-resolved { background: ${COLORS.resolvedBg}; }
+  .hunk-formatting { background: ${COLORS.resolvedBg}; border-left: 3px dashed ${COLORS.formattingBorder}; }
+  .hunk-heuristic { background: ${COLORS.resolvedBg}; border-left: 3px dotted ${COLORS.heuristicBorder}; }
   .hunk-clean { background: ${COLORS.localBg}; }
   .glyph-conflict { background: orange; width: 6px !important; margin-left: 3px; border-radius: 2px; }
 `;
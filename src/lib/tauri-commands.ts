@@ -1,5 +1,11 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { MergeSession, HunkStatus } from "./types";
+import type {
+  MergeSession,
+  HunkPreview,
+  HunkStatus,
+  IdentifierRename,
+  SyntaxError,
+} from "./types";
 
 export async function getMergeSession(): Promise<MergeSession> {
   return invoke<MergeSession>("get_merge_session");
@@ -28,6 +34,22 @@ export async function autoResolve(): Promise<MergeSession> {
   return invoke<MergeSession>("auto_resolve");
 }
 
+export async function previewStructural(): Promise<HunkPreview[]> {
+  return invoke<HunkPreview[]>("preview_structural");
+}
+
+export async function applyStructural(hunkIds: number[]): Promise<MergeSession> {
+  return invoke<MergeSession>("apply_structural", { hunkIds });
+}
+
+export async function validateResult(): Promise<SyntaxError[]> {
+  return invoke<SyntaxError[]>("validate_result");
+}
+
+export async function replayRenames(renames: IdentifierRename[]): Promise<MergeSession> {
+  return invoke<MergeSession>("replay_renames", { renames });
+}
+
 export async function registerGitMergetool(): Promise<string> {
   return invoke<string>("register_git_mergetool");
 }
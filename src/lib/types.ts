@@ -4,10 +4,16 @@ export type HunkStatus =
   | "AcceptedRemote"
   | "AcceptedBoth"
   | "CustomEdit"
-  | "AutoResolved";
+  | "AutoResolved"
+  | "AcceptedHigherVersion"
+  | "AcceptedLowerVersion"
+  | "FormattingMerged"
+  | "HeuristicMerged";
 
 export type HunkSource = "Local" | "Remote" | "Both" | "Conflict";
 
+export type MergeStrategy = "Text" | "Union" | "Changelog" | "Lockfile" | "Notebook" | "Csv" | "Prose" | "Po";
+
 export interface LineRange {
   start: number;
   end: number;
@@ -26,6 +32,75 @@ export interface MergeHunk {
   baseContent: string;
 }
 
+export interface VersionConflict {
+  hunkId: number;
+  localVersions: string[];
+  remoteVersions: string[];
+  chosen: string[];
+}
+
+export interface DetectedRename {
+  hunkId: number;
+  side: HunkSource;
+  kind: string;
+  from: string;
+  to: string;
+}
+
+export interface LockfileReport {
+  warnings: string[];
+  regenerate: string | null;
+}
+
+export interface IdentifierRename {
+  side: HunkSource;
+  from: string;
+  to: string;
+  occurrences: number;
+  replayed: boolean;
+}
+
+export type NodeOutcome = "Unchanged" | "Local" | "Remote" | "Both" | "Merged" | "Deleted";
+
+export interface MatchedNode {
+  kind: string;
+  name: string | null;
+  outcome: NodeOutcome;
+}
+
+export type RefusalReason =
+  | "ParseFailure"
+  | "SpanningNode"
+  | "SameNodeChanged"
+  | "DeleteModify"
+  | "BothRenamed"
+  | "OverlappingNodes"
+  | "SyntaxErrors"
+  | "Misaligned";
+
+export interface Refusal {
+  reason: RefusalReason;
+  kind: string | null;
+  name: string | null;
+}
+
+export interface HunkPreview {
+  hunkId: number;
+  proposal: string | null;
+  status: HunkStatus | null;
+  nodes: MatchedNode[];
+  renames: DetectedRename[];
+  refusal: Refusal | null;
+}
+
+export interface SyntaxError {
+  line: number;
+  column: number;
+  endLine: number;
+  message: string;
+  hunkId: number | null;
+}
+
 export interface MergeSession {
   basePath: string;
   localPath: string;
@@ -37,4 +112,10 @@ export interface MergeSession {
   resultContent: string;
   hunks: MergeHunk[];
   language: string;
+  strategy: MergeStrategy;
+  versionConflicts: VersionConflict[];
+  renames: DetectedRename[];
+  identifierRenames: IdentifierRename[];
+  normalized: boolean;
+  lockfile: LockfileReport | null;
 }